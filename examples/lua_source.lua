@@ -0,0 +1,37 @@
+-- A counter with enable, generated by a source defined in Lua
+counter = {}
+
+function counter:signals()
+	return {
+		"en",
+		{ name = "count", width = 8 },
+	}
+end
+
+function counter:cycle_count()
+	return 1000
+end
+
+function counter:sample(ids, first, last)
+	local rows = {}
+
+	for cycle = first, last - 1 do
+		local row = {}
+
+		for i, id in ipairs(ids) do
+			if id == "en" then
+				row[i] = (cycle // 4) % 2 == 0
+			elseif cycle == 0 then
+				row[i] = "xxxxxxxx"
+			else
+				row[i] = (cycle // 8 * 4 + math.min(cycle % 8, 4)) % 256
+			end
+		end
+
+		rows[#rows + 1] = row
+	end
+
+	return rows
+end
+
+wave = lua_source(counter, 10, "ns")
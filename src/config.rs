@@ -9,6 +9,7 @@ pub struct Config {
     config_dir: Option<PathBuf>,
     script_dir: Option<PathBuf>,
     plugin_dirs: Vec<PathBuf>,
+    index_dir: Option<PathBuf>,
     readline_config: rustyline::config::Config,
     readline_history: Option<PathBuf>,
 }
@@ -18,6 +19,7 @@ impl Config {
         let config_dir = Self::find_config_dir();
         let script_dir = Self::find_script_dir(&config_dir);
         let plugin_dirs = Self::find_plugins(&config_dir);
+        let index_dir = Self::find_index_dir(&config_dir);
         let readline_config = Self::default_readline_config();
         let readline_history = Self::find_readline_history(&config_dir);
 
@@ -25,6 +27,7 @@ impl Config {
             config_dir,
             script_dir,
             plugin_dirs,
+            index_dir,
             readline_config,
             readline_history,
         }
@@ -34,6 +37,7 @@ impl Config {
         let config_dir = Some(PathBuf::from("./"));
         let script_dir = Some(PathBuf::from("./"));
         let plugin_dirs = vec![];
+        let index_dir = None;
         let readline_config = Self::default_readline_config();
         let readline_history = None;
 
@@ -41,6 +45,7 @@ impl Config {
             config_dir,
             script_dir,
             plugin_dirs,
+            index_dir,
             readline_config,
             readline_history,
         }
@@ -107,6 +112,21 @@ impl Config {
         rv
     }
 
+    fn find_index_dir(config_dir: &Option<PathBuf>) -> Option<PathBuf> {
+        config_dir.as_ref().and_then(|cfg_dir| {
+            let mut path = PathBuf::from(cfg_dir);
+            path.push("index");
+
+            if !path.exists() {
+                // Try to create directory. On failure go on without index.
+                std::fs::create_dir(&path)
+                    .map_or(None, |_| Some(path))
+            } else {
+                Some(path)
+            }
+        })
+    }
+
     fn default_readline_config() -> rustyline::config::Config {
         rustyline::config::Builder::new()
             .max_history_size(1000)
@@ -143,6 +163,11 @@ impl Config {
         &self.plugin_dirs
     }
 
+    /// Directory to store index files of waveforms in
+    pub fn get_index_dir(&self) -> Option<&PathBuf> {
+        self.index_dir.as_ref()
+    }
+
     pub fn wave_cache_capacity(&self) -> usize {
         8
     }
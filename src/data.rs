@@ -1,11 +1,16 @@
+mod logic;
+mod sampling;
 mod simtime;
 
+pub use logic::*;
+pub use sampling::*;
 pub use simtime::*;
 
 use ndarray::prelude::*;
+use std::path::PathBuf;
 
 use crate::error::*;
-use crate::formatting::WaveFormat;
+use crate::formatting::{EnumTables, WaveFormat};
 
 //
 // Types
@@ -19,6 +24,24 @@ pub struct Signal<I> {
 
 pub type CycleValues<T> = Array2<T>;
 
+/// Cycles, that a filter keeps
+///
+/// Cycle `i` after the filter shows cycle `first + i * step` before it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CycleMap {
+    pub first: usize,
+    pub step: usize,
+    /// Number of cycles after the filter
+    pub count: usize,
+}
+
+impl CycleMap {
+    /// Cycle before the filter, that is shown as `cycle`
+    pub fn upstream(&self, cycle: usize) -> usize {
+        self.first + cycle * self.step
+    }
+}
+
 #[derive(Default)]
 pub struct PipelineConfig {
     pub name_list: Vec<String>,
@@ -37,7 +60,23 @@ pub trait QuerySource {
     fn query_signals(&self) -> Result<Self::IntoSignalIter>;
     fn query_time_range(&self) -> Result<SimTimeRange>;
     fn query_time(&self, cycle: usize) -> SimTime;
-    //fn query_cycle(&self, time: SimTime) -> usize;
+
+    /// First cycle, that starts at or after `time`
+    fn query_cycle(&self, time: SimTime) -> usize {
+        let mut low = 0;
+        let mut high = self.query_cycle_count();
+
+        while low < high {
+            let mid = (low + high) / 2;
+            if self.query_time(mid) < time {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+
+        low
+    }
 
     fn query_cycle_count(&self) -> usize;
     //{
@@ -46,6 +85,18 @@ pub trait QuerySource {
         //let duration = time_range.1 - time_range.0;
         //Ok(duration / cycle_time)
     //}
+
+    /// Load data appended to the source since it was loaded. Returns true, if there was any.
+    fn query_refresh(&mut self) -> Result<bool> { Ok(false) }
+
+    /// Load the source again, e.g. after a new simulation run replaced its file.
+    fn query_reload(&mut self) -> Result<()> { Ok(()) }
+
+    /// Files on disk, that the source is loaded from.
+    fn query_files(&self) -> Vec<PathBuf> { vec![] }
+
+    /// Names of the enumerations, that formats of the signals refer to.
+    fn query_enums(&self) -> EnumTables { EnumTables::default() }
 }
 
 pub trait LookupId {
@@ -65,10 +116,14 @@ pub trait Sample {
         -> Result<CycleValues<Self::Value>>;
 }
 
-pub trait Transform {
+pub trait Transform<I> {
     type Value;
 
-    fn transform(&mut self, _values: &mut CycleValues<Self::Value>) {}
+    /// Change `values` in place. They hold one row per cycle and one column for each id returned
+    /// by `rev_translate_ids(ids)`. Afterwards, there must be one column for each of `ids`.
+    fn transform(&mut self, _ids: &[I], _values: &mut CycleValues<Self::Value>) -> Result<()> {
+        Ok(())
+    }
     //fn transform(&self, _value: &mut Self::Value) {}
 }
 
@@ -77,6 +132,9 @@ pub trait TranslateSignals<I> {
     type IntoIdIter: IntoIterator<Item = I>;
 
     fn translate_signals(&self, signals: Self::IntoSigIter) -> Result<Self::IntoSigIter>;
+
+    /// Ids to sample from the previous stage for the given ids. A signal computed from several
+    /// others maps to all of their ids.
     fn rev_translate_ids(&self, signals: Self::IntoIdIter) -> Result<Self::IntoIdIter>;
 }
 
@@ -93,8 +151,23 @@ pub trait Source<I, J, V>:
 {}
 
 pub trait Filter<I, V>:
-    Transform<Value = V>
+    Transform<I, Value = V>
     + TranslateSignals<I>
     + ConfigurePipeline
 {
+    /// Cycles to keep out of the cycles of `prev`, the pipeline before this filter. `None` keeps
+    /// all cycles, which is what most filters do.
+    fn map_cycles(&self, _prev: &dyn QuerySource<Id = I, IntoSignalIter = Vec<Signal<I>>>) -> Option<CycleMap> {
+        None
+    }
+
+    /// Number of cycles, by which the signal `id` before this filter is shown later after it,
+    /// and the value to show for cycles shifted in from outside of the trace. `id` is one of the
+    /// ids returned by `rev_translate_ids`. `None` shows the signal in its own cycles.
+    fn cycle_shift(&self, _id: &I) -> Result<Option<(isize, V)>> {
+        Ok(None)
+    }
+
+    /// Add the names of the enumerations, that formats set by this filter refer to.
+    fn add_enums(&self, _enums: &mut EnumTables) {}
 }
@@ -0,0 +1,267 @@
+use rug::{Assign, Integer};
+use std::hash::{Hash, Hasher};
+
+/// State of a single bit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogicBit {
+    Zero,
+    One,
+    /// Unknown or uninitialized
+    X,
+    /// High impedance
+    Z,
+}
+
+impl LogicBit {
+    /// Map a character of a bit string. Weak levels 'l' and 'h' read as 0 and 1, anything
+    /// unknown as X.
+    pub fn from_ascii(c: u8) -> Self {
+        match c {
+            b'0' | b'l' | b'L' => Self::Zero,
+            b'1' | b'h' | b'H' => Self::One,
+            b'z' | b'Z' => Self::Z,
+            _ => Self::X,
+        }
+    }
+
+    pub fn to_ascii(self) -> u8 {
+        match self {
+            Self::Zero => b'0',
+            Self::One => b'1',
+            Self::X => b'x',
+            Self::Z => b'z',
+        }
+    }
+}
+
+/// Four-state value of a signal
+///
+/// Every bit is either 0, 1, X or Z. Bits in state X or Z read as 0 in [`LogicValue::value`], so
+/// values without unknown bits behave like plain integers.
+///
+/// A sampled value may be flagged as glitch, when the signal changed more than once since the
+/// previous sample, and as mismatch, when it differs from the value it is compared with in a
+/// diff. Flags are not part of the value and ignored in comparisons.
+#[derive(Clone, Debug, Default)]
+pub struct LogicValue {
+    value: Integer,
+    x: Integer,
+    z: Integer,
+    glitch: bool,
+    mismatch: bool,
+}
+
+impl LogicValue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Value with the given masks of bits in state X and Z. X takes precedence over Z, and bits
+    /// in either state are cleared in `value`.
+    pub fn from_parts(value: Integer, x: Integer, z: Integer) -> Self {
+        let z = z & !x.clone();
+        let value = value & !Integer::from(&x | &z);
+
+        Self {
+            value,
+            x,
+            z,
+            glitch: false,
+            mismatch: false,
+        }
+    }
+
+    /// Value with all `width` bits in state X
+    pub fn unknown(width: u32) -> Self {
+        let x = (Integer::from(1) << width) - Integer::from(1);
+        Self::from_parts(Integer::new(), x, Integer::new())
+    }
+
+    /// Known bits of the value
+    pub fn value(&self) -> &Integer {
+        &self.value
+    }
+
+    /// Mask of bits in state X
+    pub fn x_mask(&self) -> &Integer {
+        &self.x
+    }
+
+    /// Mask of bits in state Z
+    pub fn z_mask(&self) -> &Integer {
+        &self.z
+    }
+
+    /// True, if no bit is in state X or Z.
+    pub fn is_known(&self) -> bool {
+        self.x == 0 && self.z == 0
+    }
+
+    pub fn has_x(&self) -> bool {
+        self.x != 0
+    }
+
+    pub fn has_z(&self) -> bool {
+        self.z != 0
+    }
+
+    /// True, if the signal changed more than once between the previous sample and this one.
+    pub fn has_glitch(&self) -> bool {
+        self.glitch
+    }
+
+    pub fn set_glitch(&mut self, glitch: bool) {
+        self.glitch = glitch;
+    }
+
+    /// True, if the value differs from the one in the other wave of a diff.
+    pub fn has_mismatch(&self) -> bool {
+        self.mismatch
+    }
+
+    pub fn set_mismatch(&mut self, mismatch: bool) {
+        self.mismatch = mismatch;
+    }
+
+    pub fn bit(&self, i: u32) -> LogicBit {
+        if self.x.get_bit(i) {
+            LogicBit::X
+        } else if self.z.get_bit(i) {
+            LogicBit::Z
+        } else if self.value.get_bit(i) {
+            LogicBit::One
+        } else {
+            LogicBit::Zero
+        }
+    }
+
+    pub fn set_bit(&mut self, i: u32, bit: LogicBit) {
+        self.value.set_bit(i, bit == LogicBit::One);
+        self.x.set_bit(i, bit == LogicBit::X);
+        self.z.set_bit(i, bit == LogicBit::Z);
+    }
+
+    /// Assign bits given with the most significant bit first.
+    pub fn assign_bits<T>(&mut self, bits: T)
+        where
+            T: IntoIterator<Item = LogicBit>,
+            T::IntoIter: ExactSizeIterator,
+    {
+        self.value.assign(0);
+        self.clear_unknown();
+
+        let bits = bits.into_iter();
+        let len = bits.len();
+        for (i, bit) in bits.enumerate() {
+            self.set_bit((len - 1 - i) as u32, bit);
+        }
+    }
+
+    /// Bits as string, most significant bit first. Leading zeros are left out.
+    pub fn to_bit_string(&self) -> String {
+        (0..self.significant_bits().max(1))
+            .rev()
+            .map(|i| self.bit(i).to_ascii() as char)
+            .collect()
+    }
+
+    /// Number of bits needed to represent all set, X and Z bits.
+    pub fn significant_bits(&self) -> u32 {
+        self.value.significant_bits()
+            .max(self.x.significant_bits())
+            .max(self.z.significant_bits())
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.value.to_f64()
+    }
+
+    /// Interpret value as bit pattern of an IEEE-754 double.
+    pub fn to_real(&self) -> f64 {
+        f64::from_bits(self.value.to_u64_wrapping())
+    }
+
+    fn clear_unknown(&mut self) {
+        self.x.assign(0);
+        self.z.assign(0);
+    }
+}
+
+impl PartialEq for LogicValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value && self.x == other.x && self.z == other.z
+    }
+}
+
+impl Eq for LogicValue {}
+
+impl Hash for LogicValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.hash(state);
+        self.x.hash(state);
+        self.z.hash(state);
+    }
+}
+
+impl<T> Assign<T> for LogicValue
+    where
+        Integer: Assign<T>
+{
+    fn assign(&mut self, src: T) {
+        self.value.assign(src);
+        self.clear_unknown();
+    }
+}
+
+macro_rules! impl_from_known {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for LogicValue {
+                fn from(value: $t) -> Self {
+                    Self {
+                        value: Integer::from(value),
+                        x: Integer::new(),
+                        z: Integer::new(),
+                        glitch: false,
+                        mismatch: false,
+                    }
+                }
+            }
+        )*
+    }
+}
+
+impl_from_known!(Integer, i32, u32, i64, u64);
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_assign_bits() {
+        use LogicBit::*;
+
+        let mut v = LogicValue::new();
+        v.assign_bits(vec![One, X, Zero, Z]);
+
+        assert_eq!(Z, v.bit(0));
+        assert_eq!(Zero, v.bit(1));
+        assert_eq!(X, v.bit(2));
+        assert_eq!(One, v.bit(3));
+        assert_eq!(Zero, v.bit(4));
+        assert_eq!(Integer::from(8), *v.value());
+        assert_eq!(4, v.significant_bits());
+        assert!(!v.is_known());
+        assert_eq!("1x0z", v.to_bit_string());
+
+        v.assign(5);
+        assert!(v.is_known());
+        assert_eq!(LogicValue::from(5), v);
+
+        v.set_glitch(true);
+        assert!(v.has_glitch());
+        assert_eq!(LogicValue::from(5), v);
+    }
+}
@@ -0,0 +1,82 @@
+use super::{SimTime, SimTimeUnit};
+use crate::error::*;
+
+/// Which value of a cycle is shown
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SamplePolicy {
+    /// Value at the start of the cycle, including changes at that time
+    Start,
+    /// Last value before the end of the cycle
+    #[default]
+    End,
+}
+
+impl SamplePolicy {
+    pub fn from_string(s: impl AsRef<str>) -> Result<Self> {
+        match s.as_ref() {
+            "start" => Ok(Self::Start),
+            "end" => Ok(Self::End),
+            other => Err(Error::InvalidArgument(format!("'{}' is not a sample policy, use 'start' or 'end'", other))),
+        }
+    }
+}
+
+/// Defines the point in time within each cycle, at which signals are sampled
+///
+/// The sample point given by the policy is shifted by the offset. A negative offset samples
+/// before the cycle boundary, e.g. 1 ns before the active clock edge, to avoid races with
+/// registers changing at that edge.
+#[derive(Clone, Copy, Debug)]
+pub struct Sampling {
+    pub policy: SamplePolicy,
+    offset: SimTime,
+    before: bool,
+}
+
+impl Sampling {
+    pub fn new(policy: SamplePolicy, offset: i64, unit: SimTimeUnit) -> Self {
+        Self {
+            policy,
+            offset: SimTime::new(offset.unsigned_abs(), unit),
+            before: offset < 0,
+        }
+    }
+
+    /// Offset of sample point in multiples of `unit`, rounded towards zero
+    pub fn offset_in(&self, unit: SimTime) -> i64 {
+        let steps = (self.offset / unit) as i64;
+
+        if self.before {
+            -steps
+        } else {
+            steps
+        }
+    }
+}
+
+impl Default for Sampling {
+    fn default() -> Self {
+        Self::new(SamplePolicy::default(), 0, SimTimeUnit::S)
+    }
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_offset_in() {
+        let sampling = Sampling::new(SamplePolicy::End, -1, SimTimeUnit::Ns);
+        assert_eq!(-1000, sampling.offset_in(SimTime::from_ps(1)));
+        assert_eq!(0, sampling.offset_in(SimTime::from_us(1)));
+
+        let sampling = Sampling::new(SamplePolicy::Start, 250, SimTimeUnit::Ps);
+        assert_eq!(2, sampling.offset_in(SimTime::from_ps(100)));
+        assert_eq!(0, Sampling::default().offset_in(SimTime::from_fs(1)));
+
+        assert_eq!(SamplePolicy::Start, SamplePolicy::from_string("start").unwrap());
+        assert!(SamplePolicy::from_string("middle").is_err());
+    }
+}
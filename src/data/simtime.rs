@@ -95,6 +95,15 @@ impl SimTime {
     fn to_bigint(&self) -> Integer {
         Integer::from(self.value) * self.unit.to_multiplier()
     }
+
+    /// Unit of `self` or `other`, that can represent both
+    fn finer_unit(&self, other: &Self) -> SimTimeUnit {
+        if self.unit.to_multiplier() < other.unit.to_multiplier() {
+            self.unit
+        } else {
+            other.unit
+        }
+    }
 }
 
 impl std::ops::Mul<u64> for SimTime {
@@ -119,19 +128,54 @@ impl std::ops::Div<SimTime> for SimTime {
     }
 }
 
-//impl std::ops::Sub<SimTime> for SimTime {
-    //type Output = Self;
+impl std::ops::Add<SimTime> for SimTime {
+    type Output = Self;
 
-    //fn sub(self, rhs: Self) -> Self::Output {
-        //let a = self.to_bigint();
-        //let b = rhs.to_bigint();
-        //let res = a - b;
+    fn add(self, rhs: Self) -> Self::Output {
+        let unit = self.finer_unit(&rhs);
+        let sum = (self.to_bigint() + rhs.to_bigint()) / unit.to_multiplier();
 
-        //Self {
-            //value
-        //}
-    //}
-//}
+        Self {
+            value: sum.to_u64().expect("Integer overflow in addition"),
+            unit,
+        }
+    }
+}
+
+impl std::ops::Sub<SimTime> for SimTime {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        let unit = self.finer_unit(&rhs);
+        let difference = (self.to_bigint() - rhs.to_bigint()) / unit.to_multiplier();
+
+        Self {
+            value: difference.to_u64().expect("Negative result of subtraction"),
+            unit,
+        }
+    }
+}
+
+/// Times are compared by their value in femtoseconds, no matter which unit they are given in.
+impl PartialEq for SimTime {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_bigint() == other.to_bigint()
+    }
+}
+
+impl Eq for SimTime {}
+
+impl PartialOrd for SimTime {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SimTime {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.to_bigint().cmp(&other.to_bigint())
+    }
+}
 
 #[derive(Debug)]
 pub struct SimTimeRange(pub SimTime, pub SimTime);
@@ -158,6 +202,25 @@ mod test {
         assert_eq!((13.0e-3 / 10.0e-12) as u64, ms_13 / ps_10);
     }
 
+    #[test]
+    fn test_simtime_add_sub() {
+        let sum = SimTime::from_ns(3) + SimTime::from_ps(250);
+        assert_eq!(3250, sum.get_value());
+        assert_eq!(Some(3250), sum.as_ps());
+
+        let difference = SimTime::from_us(2) - SimTime::from_ns(500);
+        assert_eq!(1500, difference.get_value());
+        assert_eq!(Some(1_500_000), difference.as_ps());
+    }
+
+    #[test]
+    fn test_simtime_cmp() {
+        assert_eq!(SimTime::from_ns(2), SimTime::from_ps(2000));
+        assert!(SimTime::from_fs(500) < SimTime::from_ps(1));
+        assert!(SimTime::from_fs(1500) > SimTime::from_ps(1));
+        assert!(SimTime::from_s(1) > SimTime::from_fs(999_999_999_999_999));
+    }
+
     #[test]
     fn test_simtime_mul() {
         let a = SimTime::from_ms(15323);
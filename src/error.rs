@@ -16,6 +16,9 @@ pub enum Error {
     #[error("Do not know how to load '{0:}'")]
     UnknownFileFormat(String),
 
+    #[error("Malformed waveform data: {0:}")]
+    MalformedData(String),
+
     #[error("IO error")]
     IoError(#[from] std::io::Error),
 
@@ -34,6 +37,9 @@ pub enum Error {
     #[error("The given text '{0:}' can not be interpreted as time.")]
     InvalidTime(String),
 
+    #[error("Invalid argument: {0:}")]
+    InvalidArgument(String),
+
     #[error("Regex error")]
     RegexErr(#[from] regex::Error),
 
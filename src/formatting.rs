@@ -1,14 +1,236 @@
+use crate::data::{LogicBit, LogicValue};
 use rug::Integer;
+use rug::integer::Order;
 use viow_plugin_api::SignalType;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
 use std::convert::From;
+use std::hash::{Hash, Hasher};
 
 #[derive(Clone,Copy,PartialEq)]
 pub enum WaveFormat {
     Bit,
     Vector(u32),
     BitVector(u32),
-    Analog(u32, f64, f64),
+    Octal(u32),
+    /// Vector shown as decimal number
+    Number(u32, Numeric),
+    /// Vector shown as analog waveform
+    Analog(u32, Numeric, Scale),
+    /// IEEE-754 double, shown as analog waveform between min and max
+    Real(f64, f64),
+    /// Bytes of a string, first character most significant
+    Text,
     Comment,
+    /// Vector shown by the names of its values, e.g. states of a state machine
+    Enum(u32, EnumId),
+}
+
+/// How the bits of a vector are read as a number
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Numeric {
+    Unsigned,
+    /// Two's complement
+    Signed,
+    /// Signed fixed point in Q format, with the given number of fraction bits
+    Fixed(u32),
+    /// IEEE-754 half, single or double precision float, depending on the width
+    Float,
+}
+
+impl Numeric {
+    /// Numeric by name: `unsigned`, `signed`, `q<n>` for `n` fraction bits or `float`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "unsigned" => Some(Numeric::Unsigned),
+            "signed" => Some(Numeric::Signed),
+            "float" => Some(Numeric::Float),
+            _ => name.strip_prefix('q')
+                .and_then(|frac| frac.parse().ok())
+                .map(Numeric::Fixed),
+        }
+    }
+
+    /// True, if a vector of `width` bits can be read this way.
+    pub fn fits(self, width: u32) -> bool {
+        match self {
+            Numeric::Unsigned => true,
+            Numeric::Signed => width > 0,
+            Numeric::Fixed(frac) => width > frac,
+            Numeric::Float => matches!(width, 16 | 32 | 64),
+        }
+    }
+
+    /// Value of the known bits of `value`, as a number of `width` bits.
+    pub fn to_f64(self, value: &LogicValue, width: u32) -> f64 {
+        match self {
+            Numeric::Unsigned => value.to_f64(),
+            Numeric::Signed => to_signed(value, width).to_f64(),
+            Numeric::Fixed(frac) => to_signed(value, width).to_f64() / 2f64.powi(frac as i32),
+            Numeric::Float => match width {
+                16 => half_to_f64(value.value().to_u64_wrapping() as u16),
+                32 => f32::from_bits(value.value().to_u64_wrapping() as u32) as f64,
+                _ => value.to_real(),
+            },
+        }
+    }
+}
+
+/// Range of values, that an analog waveform covers from its lowest to its highest glyph
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scale {
+    /// From min to max
+    Fixed(f64, f64),
+    /// From the least to the largest value of the whole trace. Symmetric around zero, if set.
+    Trace { symmetric: bool },
+    /// Like `Trace`, but only over the values in view
+    Window { symmetric: bool },
+}
+
+/// Read the lower `width` bits of the value as two's complement.
+fn to_signed(value: &LogicValue, width: u32) -> Integer {
+    let x = value.value().clone();
+
+    if width > 0 && x.get_bit(width - 1) {
+        x - (Integer::from(1) << width)
+    } else {
+        x
+    }
+}
+
+fn half_to_f64(bits: u16) -> f64 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let mantissa = (bits & 0x3ff) as f64;
+
+    match exponent {
+        0 => sign * mantissa * 2f64.powi(-24),
+        0x1f if mantissa == 0.0 => sign * f64::INFINITY,
+        0x1f => f64::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2f64.powi(exponent - 15),
+    }
+}
+
+/// Names for the values of an enumeration
+#[derive(Clone, Debug, Hash, PartialEq)]
+pub struct EnumNames(BTreeMap<Integer, String>);
+
+/// Refers to a table of enumeration names
+///
+/// Formats are copied around freely, so they only hold the id of their table. The id is a hash
+/// of the names, so equal tables get the same id, no matter which source, filter or script run
+/// defines them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EnumId(u64);
+
+/// Tables of enumeration names by id, as collected from a pipeline by its wave
+#[derive(Clone, Debug, Default)]
+pub struct EnumTables(HashMap<EnumId, EnumNames>);
+
+impl EnumTables {
+    /// Add `names` and return their id
+    pub fn insert(&mut self, names: EnumNames) -> EnumId {
+        let id = names.id();
+        self.0.insert(id, names);
+        id
+    }
+
+    pub fn get(&self, id: EnumId) -> Option<&EnumNames> {
+        self.0.get(&id)
+    }
+
+    /// Add the tables of `other`
+    pub fn extend(&mut self, other: EnumTables) {
+        self.0.extend(other.0);
+    }
+}
+
+impl EnumNames {
+    pub fn new<T>(names: T) -> Self
+        where
+            T: IntoIterator<Item = (Integer, String)>,
+    {
+        Self(names.into_iter().collect())
+    }
+
+    /// Id of the table. Equal names have equal ids.
+    pub fn id(&self) -> EnumId {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        EnumId(hasher.finish())
+    }
+
+    /// Name of a known value, if there is one.
+    pub fn name(&self, value: &LogicValue) -> Option<&str> {
+        if value.is_known() {
+            self.0.get(value.value()).map(String::as_str)
+        } else {
+            None
+        }
+    }
+}
+
+impl WaveFormat {
+    /// Number of bits of a value. None for text and comments, whose values have no fixed size.
+    pub fn width(&self) -> Option<u32> {
+        match *self {
+            WaveFormat::Bit => Some(1),
+            WaveFormat::Vector(size)
+            | WaveFormat::BitVector(size)
+            | WaveFormat::Octal(size)
+            | WaveFormat::Number(size, _)
+            | WaveFormat::Analog(size, ..)
+            | WaveFormat::Enum(size, _) => Some(size),
+            WaveFormat::Real(_, _) => Some(64),
+            WaveFormat::Text | WaveFormat::Comment => None,
+        }
+    }
+
+    /// Format of a vector of `width` bits by name: `hex`, `bin`, `oct` or the name of a
+    /// `Numeric`. None, if the name is unknown or does not fit the width.
+    pub fn from_name(name: &str, width: u32) -> Option<Self> {
+        match name {
+            "hex" => Some(WaveFormat::Vector(width)),
+            "bin" => Some(WaveFormat::BitVector(width)),
+            "oct" => Some(WaveFormat::Octal(width)),
+            _ => Numeric::from_name(name)
+                .filter(|numeric| numeric.fits(width))
+                .map(|numeric| WaveFormat::Number(width, numeric)),
+        }
+    }
+
+    /// Next format, when toggling through the representations of a vector
+    ///
+    /// Cycles through hex, binary, octal, unsigned, signed and float, if the width fits. A
+    /// `default` format outside of this cycle, e.g. an enumeration, is part of it as well.
+    pub fn toggle(self, default: WaveFormat) -> WaveFormat {
+        use WaveFormat::*;
+
+        let size = match self {
+            Vector(size) | BitVector(size) | Octal(size) | Number(size, _) | Analog(size, ..)
+            | Enum(size, _) => size,
+            _ => return self,
+        };
+
+        let mut cycle = vec![
+            Vector(size),
+            BitVector(size),
+            Octal(size),
+            Number(size, Numeric::Unsigned),
+            Number(size, Numeric::Signed),
+        ];
+        if Numeric::Float.fits(size) {
+            cycle.push(Number(size, Numeric::Float));
+        }
+        if default.width() == Some(size) && !cycle.contains(&default) {
+            cycle.insert(0, default);
+        }
+
+        match cycle.iter().position(|format| *format == self) {
+            Some(i) => cycle[(i + 1) % cycle.len()],
+            None => cycle[0],
+        }
+    }
 }
 
 impl From<SignalType> for WaveFormat {
@@ -24,7 +246,7 @@ impl From<SignalType> for WaveFormat {
 
 fn build_waveform_vec<'a, T>(line_data: T, zoom: usize) -> String 
     where
-        T: Iterator<Item = &'a Integer>
+        T: Iterator<Item = &'a LogicValue>
 {
     line_data
         .map(|x| core::iter::repeat(x).take(zoom))
@@ -35,7 +257,7 @@ fn build_waveform_vec<'a, T>(line_data: T, zoom: usize) -> String
 
 fn build_waveform_bitvec<'a, T>(line_data: T, zoom: usize) -> String 
     where
-        T: Iterator<Item = &'a Integer>
+        T: Iterator<Item = &'a LogicValue>
 {
     line_data
         .map(|x| core::iter::repeat(x).take(zoom))
@@ -46,7 +268,7 @@ fn build_waveform_bitvec<'a, T>(line_data: T, zoom: usize) -> String
 
 fn build_waveform_bit<'a, T>(line_data: T, zoom: usize) -> String 
     where
-        T: Iterator<Item = &'a Integer>
+        T: Iterator<Item = &'a LogicValue>
 {
     line_data
         .map(|x| core::iter::repeat(x).take(zoom))
@@ -55,71 +277,304 @@ fn build_waveform_bit<'a, T>(line_data: T, zoom: usize) -> String
         .collect()
 }
 
-fn build_waveform_analog<'a, T>(line_data: T, zoom: usize, min: f64, max: f64) -> String 
+fn build_waveform_analog<'a, T, F>(line_data: T, zoom: usize, min: f64, max: f64, to_f64: F) -> String 
     where
-        T: Iterator<Item = &'a Integer>
+        T: Iterator<Item = &'a LogicValue>,
+        F: Fn(&LogicValue) -> f64,
 {
     line_data
         .map(|x| core::iter::repeat(x).take(zoom))
         .flatten()
-        .map(|x| format_analog(x, min, max))
+        .map(|x| format_analog(x, min, max, &to_f64))
         .collect()
 }
 
+fn build_waveform_text<'a, T>(line_data: T, zoom: usize) -> String 
+    where
+        T: Iterator<Item = &'a LogicValue>
+{
+    line_data
+        .map(|x| core::iter::repeat(x).take(zoom))
+        .flatten()
+        .fold(FormatAcc::new(), format_text_folder)
+        .msg
+}
+
+/// Waveform of values, that are labeled with the text of `format_folder`
+fn build_waveform_labeled<'a, T>(line_data: T, zoom: usize, format: WaveFormat, enums: &EnumTables) -> String
+    where
+        T: Iterator<Item = &'a LogicValue>
+{
+    line_data
+        .map(|x| core::iter::repeat(x).take(zoom))
+        .flatten()
+        .fold(FormatAcc::new(), |acc, value| format_folder(acc, value, format, enums))
+        .msg
+}
+
 fn build_waveform_comment<'a, T>(line_data: T, zoom: usize) -> String
     where
-        T: Iterator<Item = &'a Integer>
+        T: Iterator<Item = &'a LogicValue>
 {
     core::iter::repeat('.')
         .take(zoom * line_data.count())
         .collect()
 }
 
-pub fn build_waveform<'a, T>(line_data: T, format: WaveFormat, zoom: usize) -> String 
+/// Glyph marking cycles, in which the signal changed more than once
+const GLITCH_GLYPH: char = '↯';
+
+pub fn build_waveform<'a, T>(line_data: T, format: WaveFormat, zoom: usize, enums: &EnumTables) -> String
+    where
+        T: Iterator<Item = &'a LogicValue>
+{
+    let line_data: Vec<&LogicValue> = line_data.collect();
+    let cells = line_data.iter().copied();
+
+    let waveform = match format {
+        WaveFormat::Bit => build_waveform_bit(cells, zoom),
+        WaveFormat::Vector(_) => build_waveform_vec(cells, zoom),
+        WaveFormat::BitVector(_) => build_waveform_bitvec(cells, zoom),
+        WaveFormat::Analog(size, numeric, scale) => {
+            let (min, max) = match scale {
+                Scale::Fixed(min, max) => (min, max),
+                Scale::Trace { symmetric } | Scale::Window { symmetric } => {
+                    value_range(line_data.iter().copied(), size, numeric, symmetric)
+                }
+            };
+            build_waveform_analog(cells, zoom, min, max, |value| numeric.to_f64(value, size))
+        }
+        WaveFormat::Real(min, max) => build_waveform_analog(cells, zoom, min, max, LogicValue::to_real),
+        WaveFormat::Text => build_waveform_text(cells, zoom),
+        WaveFormat::Comment => return build_waveform_comment(cells, zoom),
+        WaveFormat::Octal(_)
+        | WaveFormat::Number(..)
+        | WaveFormat::Enum(..) => build_waveform_labeled(cells, zoom, format, enums),
+    };
+
+    mark_glitches(waveform, &line_data, zoom)
+}
+
+/// Range of values covered by an analog waveform of `line_data` in `format`
+///
+/// Ranges of `Scale::Trace` are taken over `line_data` as well, so they must be resolved to fixed
+/// ranges before, e.g. by the wave. None, if the format is not analog.
+pub fn analog_range<'a, T>(line_data: T, format: WaveFormat) -> Option<(f64, f64)>
     where
-        T: Iterator<Item = &'a Integer>
+        T: Iterator<Item = &'a LogicValue>
 {
     match format {
-        WaveFormat::Bit => build_waveform_bit(line_data, zoom),
-        WaveFormat::Vector(_) => build_waveform_vec(line_data, zoom),
-        WaveFormat::BitVector(_) => build_waveform_bitvec(line_data, zoom),
-        WaveFormat::Analog(_, min, max) => build_waveform_analog(line_data, zoom, min, max),
-        WaveFormat::Comment => build_waveform_comment(line_data, zoom),
+        WaveFormat::Analog(_, _, Scale::Fixed(min, max)) | WaveFormat::Real(min, max) => Some((min, max)),
+        WaveFormat::Analog(size, numeric, Scale::Trace { symmetric })
+        | WaveFormat::Analog(size, numeric, Scale::Window { symmetric }) => {
+            Some(value_range(line_data, size, numeric, symmetric))
+        }
+        _ => None,
     }
 }
 
+/// Range from the least to the largest known value, read as `numeric` numbers of `size` bits
+///
+/// If `symmetric`, the range is widened to be symmetric around zero. Empty ranges are widened by
+/// one in both directions.
+pub fn value_range<'a, T>(values: T, size: u32, numeric: Numeric, symmetric: bool) -> (f64, f64)
+    where
+        T: Iterator<Item = &'a LogicValue>
+{
+    extent_range(value_extent(values, size, numeric), symmetric)
+}
 
-pub fn format_value(value: &Integer, format: WaveFormat) -> String {
-    match format {
-        WaveFormat::Bit => format!("{:b}", value),
-        WaveFormat::Vector(size) | WaveFormat::Analog(size, _, _) => {
-            let hex_digits = if size % 4 == 0 {
-                size / 4
+/// Least and largest finite value of the known values, if there are any
+pub fn value_extent<'a, T>(values: T, size: u32, numeric: Numeric) -> Option<(f64, f64)>
+    where
+        T: Iterator<Item = &'a LogicValue>
+{
+    let (min, max) = values
+        .filter(|value| value.is_known())
+        .map(|value| numeric.to_f64(value, size))
+        .filter(|x| x.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), x| (min.min(x), max.max(x)));
+
+    if min > max {
+        None
+    } else {
+        Some((min, max))
+    }
+}
+
+/// Range of an analog waveform of values from the least to the largest in `extent`
+pub fn extent_range(extent: Option<(f64, f64)>, symmetric: bool) -> (f64, f64) {
+    let (min, max) = match extent {
+        None => (0.0, 0.0),
+        Some((min, max)) if symmetric => {
+            let bound = min.abs().max(max.abs());
+            (-bound, bound)
+        }
+        Some(extent) => extent,
+    };
+
+    if min < max {
+        (min, max)
+    } else {
+        (min - 1.0, max + 1.0)
+    }
+}
+
+/// Range like `[-1.5, 2]`, with up to three decimal places
+pub fn format_range((min, max): (f64, f64)) -> String {
+    fn bound(x: f64) -> String {
+        let text = format!("{:.3}", x);
+        let text = text.trim_end_matches('0').trim_end_matches('.');
+
+        if text == "-0" {
+            "0".to_string()
+        } else {
+            text.to_string()
+        }
+    }
+
+    format!("[{}, {}]", bound(min), bound(max))
+}
+
+/// Replace the first glyph of each cycle, in which the signal glitched.
+fn mark_glitches(waveform: String, line_data: &[&LogicValue], zoom: usize) -> String {
+    waveform
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let glitch = i % zoom == 0
+                && line_data.get(i / zoom).is_some_and(|value| value.has_glitch());
+
+            if glitch {
+                GLITCH_GLYPH
             } else {
-                size / 4 + 1
-            };
-            format!("{:#0width$x}", value, width = (hex_digits as usize) + 2)
+                c
+            }
+        })
+        .collect()
+}
+
+
+/// Text of `value` in `format`. Enumerations are looked up in `enums`.
+pub fn format_value(value: &LogicValue, format: WaveFormat, enums: &EnumTables) -> String {
+    match format {
+        WaveFormat::Bit => format_digits(value, 1, 0),
+        WaveFormat::Vector(size) | WaveFormat::Analog(size, Numeric::Unsigned, ..) => {
+            format!("0x{}", format_digits(value, 4, size))
+        }
+        WaveFormat::BitVector(size) => format!("0b{}", format_digits(value, 1, size)),
+        WaveFormat::Octal(size) => format!("0o{}", format_digits(value, 3, size)),
+        WaveFormat::Number(size, numeric) | WaveFormat::Analog(size, numeric, ..) => {
+            format_number(value, size, numeric)
         }
-        WaveFormat::BitVector(size) => format!("{:#0width$b}", value, width = size as usize + 2),
+        WaveFormat::Real(_, _) if value.is_known() => format!("{}", value.to_real()),
+        WaveFormat::Real(_, _) => "x".to_string(),
+        WaveFormat::Text => format!("\"{}\"", format_text(value)),
         WaveFormat::Comment => "".to_string(),
+        WaveFormat::Enum(size, id) => format_enum(value, size, enums.get(id)),
+    }
+}
+
+/// Decimal number. Values with X or Z bits are shown as `x`.
+fn format_number(value: &LogicValue, size: u32, numeric: Numeric) -> String {
+    if !value.is_known() {
+        return "x".to_string();
+    }
+
+    match numeric {
+        Numeric::Unsigned => value.value().to_string(),
+        Numeric::Signed => to_signed(value, size).to_string(),
+        Numeric::Fixed(_) => format!("{}", numeric.to_f64(value, size)),
+        Numeric::Float if size == 32 => format!("{}", numeric.to_f64(value, size) as f32),
+        Numeric::Float => format!("{}", numeric.to_f64(value, size)),
+    }
+}
+
+/// Glyph filling segments of values, that have no name in an enumeration
+const UNNAMED_GLYPH: char = '░';
+
+/// Name of the value, or `!` followed by the value in hex, if it has none. Values with X or Z
+/// bits are shown in hex without `!`.
+fn format_enum(value: &LogicValue, size: u32, names: Option<&EnumNames>) -> String {
+    match names.and_then(|names| names.name(value)) {
+        Some(name) => name.to_string(),
+        None if value.is_known() => format!("!0x{}", format_digits(value, 4, size)),
+        None => format!("0x{}", format_digits(value, 4, size)),
     }
 }
 
+fn format_text(value: &LogicValue) -> String {
+    if value.is_known() {
+        String::from_utf8_lossy(&value.value().to_digits::<u8>(Order::Msf)).into_owned()
+    } else {
+        "x".to_string()
+    }
+}
+
+/// Format value with `bits_per_digit` bits in each digit.
+///
+/// The result has at least as many digits as needed for `size` bits. Digits are shown as `x` or
+/// `z`, when all their bits are in that state, and as `X` or `Z`, when only some of them are.
+fn format_digits(value: &LogicValue, bits_per_digit: u32, size: u32) -> String {
+    let size = std::cmp::max(std::cmp::max(size, value.significant_bits()), 1);
+    let num_digits = size.div_ceil(bits_per_digit);
+
+    (0..num_digits)
+        .rev()
+        .map(|digit| {
+            let lsb = digit * bits_per_digit;
+            let msb = std::cmp::min(lsb + bits_per_digit, size);
+            let mut num = 0;
+            let mut num_x = 0;
+            let mut num_z = 0;
+
+            for i in lsb..msb {
+                match value.bit(i) {
+                    LogicBit::Zero => (),
+                    LogicBit::One => num |= 1 << (i - lsb),
+                    LogicBit::X => num_x += 1,
+                    LogicBit::Z => num_z += 1,
+                }
+            }
+
+            if num_x == msb - lsb {
+                'x'
+            } else if num_x > 0 {
+                'X'
+            } else if num_z == msb - lsb {
+                'z'
+            } else if num_z > 0 {
+                'Z'
+            } else {
+                std::char::from_digit(num, 1 << bits_per_digit).unwrap_or('?')
+            }
+        })
+        .collect()
+}
+
 
-fn format_bit(value: &Integer) -> char {
-    if *value == 0 {
+fn format_bit(value: &LogicValue) -> char {
+    if value.has_x() {
+        '▒'
+    } else if value.has_z() {
+        '─'
+    } else if *value.value() == 0 {
         '▁'
     } else {
         '▇'
     }
 }
 
-fn format_analog(value: &Integer, val_min: f64, val_max: f64) -> char {
+fn format_analog(value: &LogicValue, val_min: f64, val_max: f64, to_f64: &dyn Fn(&LogicValue) -> f64) -> char {
     const SCALE: f64 = 8.0;
     const SYMBOLS: &'static [char] = &[ '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█' ];
 
+    if !value.is_known() {
+        return format_bit(value);
+    }
+
     let norm = val_max - val_min;
-    let x = (((value.to_f64() - val_min) / norm) * SCALE).round();
+    let x = (((to_f64(value) - val_min) / norm) * SCALE).round();
 
     if x < 0.0 {
         SYMBOLS[0]
@@ -131,7 +586,9 @@ fn format_analog(value: &Integer, val_min: f64, val_max: f64) -> char {
 }
 
 struct FormatAcc {
-    last: Option<Integer>,
+    last: Option<LogicValue>,
+    /// Number of characters in the label of `last`
+    last_len: usize,
     cnt: usize,
     msg: String,
 }
@@ -140,40 +597,55 @@ impl FormatAcc {
     fn new() -> Self {
         Self {
             last: None,
+            last_len: 0,
             cnt: 0,
             msg: String::from("")
         }
     }
 }
 
-fn format_vec(acc: FormatAcc, value: &Integer) -> FormatAcc {
-    format_folder(acc, value, WaveFormat::Vector(0))
+fn format_vec(acc: FormatAcc, value: &LogicValue) -> FormatAcc {
+    format_folder(acc, value, WaveFormat::Vector(0), &EnumTables::default())
+}
+
+fn format_bitvec(acc: FormatAcc, value: &LogicValue) -> FormatAcc {
+    format_folder(acc, value, WaveFormat::BitVector(0), &EnumTables::default())
 }
 
-fn format_bitvec(acc: FormatAcc, value: &Integer) -> FormatAcc {
-    format_folder(acc, value, WaveFormat::BitVector(0))
+fn format_text_folder(acc: FormatAcc, value: &LogicValue) -> FormatAcc {
+    format_folder(acc, value, WaveFormat::Text, &EnumTables::default())
 }
 
-fn format_folder(mut acc: FormatAcc, value: &Integer, format: WaveFormat) -> FormatAcc {
+fn format_folder(mut acc: FormatAcc, value: &LogicValue, format: WaveFormat, enums: &EnumTables) -> FormatAcc {
     let emit;
 
     let val = match format {
-        WaveFormat::BitVector(_) => format!("{:b}", *value),
-        _ => format!("{:x}", *value)
+        WaveFormat::BitVector(_) => format_digits(value, 1, 0),
+        WaveFormat::Text => format_text(value),
+        WaveFormat::Octal(_) => format_digits(value, 3, 0),
+        WaveFormat::Number(size, numeric) => format_number(value, size, numeric),
+        WaveFormat::Enum(_, id) => format_enum(value, 0, enums.get(id)),
+        _ => format_digits(value, 4, 0)
+    };
+    // fill remainder of unknown values with the same glyphs as bits
+    let fill = match format {
+        _ if !value.is_known() => format_bit(value),
+        WaveFormat::Enum(_, id) if enums.get(id).and_then(|names| names.name(value)).is_none() => UNNAMED_GLYPH,
+        _ => ' ',
     };
     let val_len = val.chars().count();
 
     if let Some(last) = acc.last {
         if last == *value {
             if acc.cnt >= val_len {
-                emit = ' ';
+                emit = fill;
             } else {
                 emit = val.chars().nth(acc.cnt).unwrap();
             }
 
             acc.cnt += 1;
         } else {
-            if (acc.cnt < val_len) && (acc.cnt > 0) {
+            if (acc.cnt < acc.last_len) && (acc.cnt > 0) {
                 acc.msg.pop();
                 acc.msg.push('…');
             }
@@ -185,7 +657,172 @@ fn format_folder(mut acc: FormatAcc, value: &Integer, format: WaveFormat) -> For
     }
 
     acc.last = Some(value.clone());
+    acc.last_len = val_len;
     acc.msg.push(emit);
 
     acc
 }
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_format_four_state() {
+        let enums = EnumTables::default();
+        use LogicBit::*;
+
+        let mut value = LogicValue::new();
+        value.assign_bits(vec![X, X, X, X, Z, Z, Z, Z, One, Zero, X, One]);
+
+        assert_eq!("0xxzX", format_value(&value, WaveFormat::Vector(12), &enums));
+        assert_eq!("0b0000xxxxzzzz10x1", format_value(&value, WaveFormat::BitVector(16), &enums));
+        assert_eq!("0x0a5", format_value(&LogicValue::from(0xa5), WaveFormat::Vector(12), &enums));
+
+        let mut bit = LogicValue::new();
+        bit.set_bit(0, Z);
+        assert_eq!("z", format_value(&bit, WaveFormat::Bit, &enums));
+        assert_eq!("▁▇─", build_waveform(
+            [LogicValue::from(0), LogicValue::from(1), bit].iter(), WaveFormat::Bit, 1, &enums));
+    }
+
+    #[test]
+    fn test_format_text_and_real() {
+        let enums = EnumTables::default();
+        let text = LogicValue::from(rug::Integer::from_digits(b"IDLE", Order::Msf));
+        assert_eq!("\"IDLE\"", format_value(&text, WaveFormat::Text, &enums));
+        let other = LogicValue::from(rug::Integer::from_digits(b"OK", Order::Msf));
+        let line = vec![&text, &text, &text, &text, &text, &text, &other, &other];
+        assert_eq!("╳IDLE ╳O", build_waveform(line.into_iter(), WaveFormat::Text, 1, &enums));
+
+        let mut glitch = other.clone();
+        glitch.set_glitch(true);
+        let line = vec![&text, &text, &glitch, &other];
+        assert_eq!("╳…↯O", build_waveform(line.into_iter(), WaveFormat::Text, 1, &enums));
+        let line = vec![&text, &glitch];
+        assert_eq!("╳…↯O", build_waveform(line.into_iter(), WaveFormat::Text, 2, &enums));
+
+        let real = LogicValue::from(2.5f64.to_bits());
+        assert_eq!("2.5", format_value(&real, WaveFormat::Real(0.0, 1.0), &enums));
+        assert_eq!("▁▅█", build_waveform(
+            [LogicValue::from(0f64.to_bits()), LogicValue::from(0.5f64.to_bits()), real].iter(),
+            WaveFormat::Real(0.0, 1.0), 1, &enums));
+    }
+
+    #[test]
+    fn test_format_enum() {
+        let mut enums = EnumTables::default();
+        let names = enums.insert(EnumNames::new(vec![
+            (Integer::from(0), "IDLE".to_string()),
+            (Integer::from(1), "REQ".to_string()),
+        ]));
+        let format = WaveFormat::Enum(4, names);
+        let mut x = LogicValue::new();
+        x.assign_bits(vec![LogicBit::X; 4]);
+
+        assert_eq!("REQ", format_value(&LogicValue::from(1), format, &enums));
+        assert_eq!("!0x5", format_value(&LogicValue::from(5), format, &enums));
+        assert_eq!("0xx", format_value(&x, format, &enums));
+
+        let idle = LogicValue::from(0);
+        let illegal = LogicValue::from(0xa);
+        let line = vec![
+            &idle, &idle, &idle, &idle, &idle,
+            &illegal, &illegal, &illegal, &illegal, &illegal, &illegal,
+        ];
+        assert_eq!("╳IDLE╳!0xa░", build_waveform(line.into_iter(), format, 1, &enums));
+
+        // the same names get the same id
+        let again = EnumNames::new(vec![
+            (Integer::from(1), "REQ".to_string()),
+            (Integer::from(0), "IDLE".to_string()),
+        ]);
+        assert_eq!(names, again.id());
+        assert_ne!(names, EnumNames::new(vec![(Integer::from(0), "IDLE".to_string())]).id());
+
+        // without the table the raw value is shown
+        assert_eq!("!0x1", format_value(&LogicValue::from(1), format, &EnumTables::default()));
+    }
+
+    #[test]
+    fn test_format_numbers() {
+        let enums = EnumTables::default();
+        let value = LogicValue::from(0xf6);
+        assert_eq!("246", format_value(&value, WaveFormat::Number(8, Numeric::Unsigned), &enums));
+        assert_eq!("-10", format_value(&value, WaveFormat::Number(8, Numeric::Signed), &enums));
+        assert_eq!("246", format_value(&value, WaveFormat::Number(12, Numeric::Signed), &enums));
+        assert_eq!("-2.5", format_value(&value, WaveFormat::Number(8, Numeric::Fixed(2)), &enums));
+        assert_eq!("0o366", format_value(&value, WaveFormat::Octal(8), &enums));
+        let mut x = LogicValue::new();
+        x.set_bit(3, LogicBit::X);
+        assert_eq!("x", format_value(&x, WaveFormat::Number(8, Numeric::Signed), &enums));
+
+        let half = LogicValue::from(0xc100);
+        assert_eq!("-2.5", format_value(&half, WaveFormat::Number(16, Numeric::Float), &enums));
+        let single = LogicValue::from(0.1f32.to_bits());
+        assert_eq!("0.1", format_value(&single, WaveFormat::Number(32, Numeric::Float), &enums));
+        let double = LogicValue::from(0.1f64.to_bits());
+        assert_eq!("0.1", format_value(&double, WaveFormat::Number(64, Numeric::Float), &enums));
+
+        let minus_one = LogicValue::from(0xff);
+        let line = vec![&minus_one, &minus_one, &minus_one, &value];
+        let format = WaveFormat::Number(8, Numeric::Signed);
+        assert_eq!("╳-1╳", build_waveform(line.into_iter(), format, 1, &enums));
+
+        // signed values below zero are drawn at the bottom
+        let low = LogicValue::from(0xf0);
+        let zero = LogicValue::from(0);
+        let high = LogicValue::from(0x7f);
+        let line = vec![&low, &zero, &high];
+        let format = WaveFormat::Analog(8, Numeric::Signed, Scale::Fixed(-16.0, 16.0));
+        assert_eq!("▁▅█", build_waveform(line.into_iter(), format, 1, &enums));
+    }
+
+    #[test]
+    fn test_toggle() {
+        let mut format = WaveFormat::Vector(16);
+        let mut seen = vec![];
+        for _ in 0..7 {
+            format = format.toggle(WaveFormat::Vector(16));
+            seen.push(format);
+        }
+        assert!(seen[0] == WaveFormat::BitVector(16));
+        assert!(seen[1] == WaveFormat::Octal(16));
+        assert!(seen[4] == WaveFormat::Number(16, Numeric::Float));
+        assert!(seen[5] == WaveFormat::Vector(16));
+
+        let fixed = WaveFormat::Number(12, Numeric::Fixed(4));
+        assert!(fixed.toggle(fixed) == WaveFormat::Vector(12));
+        assert!(WaveFormat::Number(12, Numeric::Signed).toggle(fixed) == fixed);
+        assert!(WaveFormat::Bit.toggle(WaveFormat::Bit) == WaveFormat::Bit);
+
+        assert!(WaveFormat::from_name("q4", 12) == Some(fixed));
+        assert!(WaveFormat::from_name("float", 12).is_none());
+        assert!(WaveFormat::from_name("oct", 3) == Some(WaveFormat::Octal(3)));
+    }
+
+    #[test]
+    fn test_analog_range() {
+        let enums = EnumTables::default();
+        let values = [LogicValue::from(0xf8), LogicValue::from(0), LogicValue::from(4)];
+
+        let window = WaveFormat::Analog(8, Numeric::Signed, Scale::Window { symmetric: false });
+        assert_eq!(Some((-8.0, 4.0)), analog_range(values.iter(), window));
+        assert_eq!("▁▆█", build_waveform(values.iter(), window, 1, &enums));
+
+        let symmetric = WaveFormat::Analog(8, Numeric::Signed, Scale::Window { symmetric: true });
+        assert_eq!(Some((-8.0, 8.0)), analog_range(values.iter(), symmetric));
+        let fixed = WaveFormat::Analog(8, Numeric::Unsigned, Scale::Fixed(0.0, 1.0));
+        assert_eq!(Some((0.0, 1.0)), analog_range(values.iter(), fixed));
+        assert_eq!(None, analog_range(values.iter(), WaveFormat::Vector(8)));
+
+        // a constant or unknown trace still gets a range
+        assert_eq!((3.0, 5.0), value_range(values[2..].iter(), 8, Numeric::Unsigned, false));
+        assert_eq!((-1.0, 1.0), value_range([].iter(), 8, Numeric::Unsigned, true));
+
+        assert_eq!("[-1.5, 2]", format_range((-1.5, 2.0)));
+        assert_eq!("[0, 0.333]", format_range((-0.0001, 1.0 / 3.0)));
+    }
+}
@@ -9,13 +9,13 @@ pub mod viewer;
 pub mod wave;
 
 use config::Config;
-use data::{SimTime, SimTimeUnit};
+use data::{SimTime, SimTimeUnit, Sampling, SamplePolicy};
 use error::*;
-use load::{empty::EmptyLoader, vcd::VcdLoader, plugin::PluggedLoader};
+use load::{empty::EmptyLoader, vcd::{VcdLoader, CycleGrid, ClockEdge}, ghw::GhwLoader, fst::FstLoader, plugin::PluggedLoader, compressed};
+use pipeline::SrcBox;
 use scripts::{lua::LuaInterpreter, RunCommand, ScriptState};
 use viewer::*;
-use wave::Wave;
-use formatting::WaveFormat;
+use wave::{Wave, Follow};
 
 //use anyhow::Result;
 use clap::Parser;
@@ -31,10 +31,15 @@ use std::path::PathBuf;
 use std::rc::Rc;
 use std::collections::HashMap;
 use std::io::Write;
+use std::time::{Duration, Instant};
 
 
 pub type PluginMap = HashMap<String, FiletypeLoader_Ref>;
 
+/// Interval, at which a followed or watched file is checked for new data, even while keys are
+/// pressed
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
 pub struct Step {
     pub state: ScriptState,
     pub interpreter: LuaInterpreter,
@@ -197,6 +202,22 @@ fn event_step_normal(
             }
         }
 
+        // next divergence of a diff
+        Event::Key(KeyEvent { code: KeyCode::Char('d'), .. }) => {
+            match state.wv.cached_next_divergence(state.ui.get_cur_wave_col()) {
+                Some(next) => state.ui.set_cur_wave_col(next),
+                None => state.ui.set_message("No further divergence"),
+            }
+        }
+
+        // first divergence of a diff
+        Event::Key(KeyEvent { code: KeyCode::Char('D'), .. }) => {
+            match state.wv.cached_first_divergence() {
+                Some(first) => state.ui.set_cur_wave_col(first),
+                None => state.ui.set_message("No divergence"),
+            }
+        }
+
         // zoom in '+'
         Event::Key(KeyEvent {
             code: KeyCode::Char('+'),
@@ -282,6 +303,17 @@ fn event_step_normal(
                 .start_insert_mode(unfiltered, state.wv.get_names().clone(), insert_at);
         }
 
+        // reload from disk
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('r'),
+            ..
+        }) => {
+            match state.wv.reload_source() {
+                Ok(()) => state.ui.set_message("Reloaded from disk"),
+                Err(err) => state.ui.set_message(format!("Reload failed: {}", err)),
+            }
+        }
+
         // toggle type
         Event::Key(KeyEvent {
             code: KeyCode::Char('t'),
@@ -289,14 +321,7 @@ fn event_step_normal(
         }) => {
             if let Some(cur_row) = state.ui.get_cursor_row() {
                 let cur_fmt = state.wv.formatter(cur_row);
-                let next_fmt = match cur_fmt {
-                    WaveFormat::Vector(sz) => WaveFormat::BitVector(sz),
-                    WaveFormat::BitVector(sz) => WaveFormat::Vector(sz),
-                    //WaveFormat::BitVector(sz) => WaveFormat::Analog(sz, 0., 64.),//WaveFormat::Vector(sz),
-                    //WaveFormat::Analog(sz, _, _) => WaveFormat::Vector(sz),
-
-                    other => other
-                };
+                let next_fmt = cur_fmt.toggle(state.wv.default_formatter(cur_row));
 
                 state.wv.set_formatter(cur_row, next_fmt);
             }
@@ -309,7 +334,11 @@ fn event_step_normal(
     Ok(Step { state, interpreter, should_exit, should_clear })
 }
 
-pub fn event_step(step: Step, ev: Event) -> Result<Step> {
+pub fn event_step(mut step: Step, ev: Event) -> Result<Step> {
+    if let Event::Key(_) = ev {
+        step.state.ui.clear_message();
+    }
+
     if step.state.ui.in_insert_mode() {
         event_step_insert(ev, step)
     } else {
@@ -317,6 +346,32 @@ pub fn event_step(step: Step, ev: Event) -> Result<Step> {
     }
 }
 
+/// Load data appended to a followed file and move the cursor to the newest cycle, if pinned.
+/// Reload the source, if its files were replaced and auto reload is enabled.
+pub fn watch_step(step: &mut Step) -> Result<()> {
+    let state = &mut step.state;
+
+    if state.wv.files_changed() {
+        // a file, that is being rewritten, may fail to load until the simulation is done
+        match state.wv.reload_source() {
+            Ok(()) => state.ui.set_message("Files changed on disk, data was refreshed"),
+            Err(err) => state.ui.set_message(format!("Files changed on disk, reload failed: {}", err)),
+        }
+    } else {
+        // a followed file may be deleted or rotated while the simulation runs
+        match state.wv.refresh() {
+            Ok(true) if state.wv.follow() == Follow::Pinned => {
+                state.ui.data_size(state.wv.num_signals(), state.wv.num_cycles());
+                state.ui.set_cur_wave_col(state.wv.num_cycles());
+            }
+            Ok(_) => (),
+            Err(err) => state.ui.set_message(format!("Reading new data failed: {}", err)),
+        }
+    }
+
+    Ok(())
+}
+
 
 #[cfg(not(tarpaulin_include))]
 pub fn main_loop(stdout: std::io::Stdout, opts: Opts, config: Rc<Config>) -> Result<()> {
@@ -342,8 +397,19 @@ pub fn main_loop(stdout: std::io::Stdout, opts: Opts, config: Rc<Config>) -> Res
         .flush()?;
     enable_raw_mode()?;
 
+    let mut next_watch = Instant::now() + WATCH_INTERVAL;
     loop {
         step = render_step(&mut terminal, step)?;
+
+        if step.state.wv.is_watching() {
+            let timeout = next_watch.saturating_duration_since(Instant::now());
+            if timeout.is_zero() || !event::poll(timeout)? {
+                watch_step(&mut step)?;
+                next_watch = Instant::now() + WATCH_INTERVAL;
+                continue;
+            }
+        }
+
         step = event_step(step, event::read()?)?;
 
         if step.should_exit {
@@ -443,7 +509,7 @@ fn load_plugins(opts: &Opts, config: &Rc<Config>) -> Result<PluginMap> {
 pub fn setup(opts: Opts, config: Rc<Config>) -> Result<Step> {
     let plugins = load_plugins(&opts, &config)?;
 
-    if opts.input.ends_with(".vcd") {
+    if opts.input.ends_with(".vcd") || opts.input.ends_with(".fst") || compressed::is_compressed_vcd(&opts.input)? {
         //let cycle_step = opts.cycle_step.ok_or(Error::MissingArgument(
             //"--clock-period".into(),
             //"Required to load a vcd file".into(),
@@ -451,8 +517,39 @@ pub fn setup(opts: Opts, config: Rc<Config>) -> Result<Step> {
         let timeunits = SimTimeUnit::from_string(opts.timeunits.trim().to_lowercase())?;
         let cycle_time = opts.cycle_step
             .map(|cs| SimTime::new(cs, timeunits));
-        let loader = Box::new(VcdLoader::new(PathBuf::from(opts.input), cycle_time)?);
-        let wave = Wave::load(loader/*, &config*/)?;
+        let sampling = Sampling::new(SamplePolicy::from_string(opts.sample_at.trim().to_lowercase())?,
+            opts.sample_offset, timeunits);
+        let grid = match opts.clock.as_ref() {
+            Some(clock) => CycleGrid::Clock(clock.clone(), ClockEdge::from_string(opts.edge.trim().to_lowercase())?),
+            None => CycleGrid::Period(cycle_time),
+        };
+        let follow = match (opts.follow, opts.pin) {
+            (_, true) => Follow::Pinned,
+            (true, false) => Follow::On,
+            (false, false) => Follow::Off,
+        };
+        let loader: SrcBox = if opts.input.ends_with(".fst") {
+            check_no_clock(&opts)?;
+            check_no_sampling(&opts, "FST")?;
+            if follow != Follow::Off {
+                return Err(Error::InvalidArgument("--follow and --pin are only supported for VCD files".into()));
+            }
+            Box::new(FstLoader::new(PathBuf::from(opts.input), cycle_time)?)
+        } else {
+            let mut loader = if follow != Follow::Off {
+                // the index would be outdated as soon as the file grows
+                VcdLoader::follow(PathBuf::from(opts.input), grid)?
+            } else if let Some(index_dir) = config.get_index_dir() {
+                VcdLoader::with_index(PathBuf::from(opts.input), grid, index_dir)?
+            } else {
+                VcdLoader::with_grid(PathBuf::from(opts.input), grid)?
+            };
+            loader.set_sampling(sampling);
+            Box::new(loader)
+        };
+        let mut wave = Wave::load(loader/*, &config*/)?;
+        wave.set_follow(follow);
+        wave.set_auto_reload(opts.auto_reload);
 
         //let mut interpreter = LuaInterpreter::new(state, wave);
         let state = ScriptState {
@@ -462,6 +559,29 @@ pub fn setup(opts: Opts, config: Rc<Config>) -> Result<Step> {
         };
         let interpreter = LuaInterpreter::new(&config, plugins)?;
 
+        let step = Step { state, interpreter, should_exit: false, should_clear: false };
+        Ok(step)
+    } else if opts.input.ends_with(".ghw") {
+        let timeunits = SimTimeUnit::from_string(opts.timeunits.trim().to_lowercase())?;
+        let cycle_time = opts.cycle_step
+            .map(|cs| SimTime::new(cs, timeunits))
+            .ok_or(Error::MissingArgument("cycle_step".into(), "Needed to load a ghw file".into()))?;
+        check_no_clock(&opts)?;
+        check_no_sampling(&opts, "GHW")?;
+        if opts.follow || opts.pin {
+            return Err(Error::InvalidArgument("--follow and --pin are only supported for VCD files".into()));
+        }
+        let loader = Box::new(GhwLoader::new(PathBuf::from(opts.input), cycle_time)?);
+        let mut wave = Wave::load(loader/*, &config*/)?;
+        wave.set_auto_reload(opts.auto_reload);
+
+        let state = ScriptState {
+            ui: State::new(&config)?,
+            wv: wave,
+            er: None,
+        };
+        let interpreter = LuaInterpreter::new(&config, plugins)?;
+
         let step = Step { state, interpreter, should_exit: false, should_clear: false };
         Ok(step)
     } else if opts.input.ends_with(".lua") {
@@ -474,7 +594,10 @@ pub fn setup(opts: Opts, config: Rc<Config>) -> Result<Step> {
             er: None,
         };
         let mut interpreter = LuaInterpreter::new(&config, plugins)?;
-        let state = interpreter.run_file(state, opts.input)?;
+        let mut state = interpreter.run_file(state, opts.input)?;
+        if opts.auto_reload {
+            state.wv.set_auto_reload(true);
+        }
 
         let step = Step { state, interpreter, should_exit: false, should_clear: false };
         Ok(step)
@@ -483,12 +606,19 @@ pub fn setup(opts: Opts, config: Rc<Config>) -> Result<Step> {
             .ok_or(Error::UnknownFileFormat(opts.input.clone()))?;
 
         if let Some(plugin) = plugins.get(suffix) {
+            check_no_clock(&opts)?;
+            if opts.follow || opts.pin {
+                return Err(Error::InvalidArgument("--follow and --pin are only supported for VCD files".into()));
+            }
             let timeunits = SimTimeUnit::from_string(opts.timeunits.trim().to_lowercase())?;
             let cycle_time = opts.cycle_step
                 .map(|cs| SimTime::new(cs, timeunits))
                 .ok_or(Error::MissingArgument("cycle_step".into(), "Needed for plugin load".into()))?;
-            let loader = Box::new(PluggedLoader::new(plugin.clone(), opts.input.as_str(), cycle_time)?);
-            let wave = Wave::load(loader/*, &config*/)?;
+            let sampling = Sampling::new(SamplePolicy::from_string(opts.sample_at.trim().to_lowercase())?,
+                opts.sample_offset, timeunits);
+            let loader = Box::new(PluggedLoader::with_sampling(plugin.clone(), opts.input.as_str(), cycle_time, sampling)?);
+            let mut wave = Wave::load(loader/*, &config*/)?;
+            wave.set_auto_reload(opts.auto_reload);
 
             let state = ScriptState {
                 ui: State::new(&config)?,
@@ -506,6 +636,24 @@ pub fn setup(opts: Opts, config: Rc<Config>) -> Result<Step> {
     }
 }
 
+/// Cycles start at clock edges only in VCD files.
+fn check_no_clock(opts: &Opts) -> Result<()> {
+    if opts.clock.is_some() || ClockEdge::from_string(opts.edge.trim().to_lowercase())? != ClockEdge::Rising {
+        return Err(Error::InvalidArgument("--clock and --edge are only supported for VCD files".into()));
+    }
+
+    Ok(())
+}
+
+/// Files in `format` are sampled at the end of each cycle only.
+fn check_no_sampling(opts: &Opts, format: &str) -> Result<()> {
+    if SamplePolicy::from_string(opts.sample_at.trim().to_lowercase())? != SamplePolicy::End || opts.sample_offset != 0 {
+        return Err(Error::InvalidArgument(format!("--sample-at and --sample-offset are not supported for {} files", format)));
+    }
+
+    Ok(())
+}
+
 /// Display a wave file in the console.
 #[derive(Parser)]
 pub struct Opts {
@@ -516,6 +664,34 @@ pub struct Opts {
     #[clap(short, long)]
     cycle_step: Option<u64>,
 
+    /// Start cycles at edges of this signal instead of using a fixed cycle step (VCD only)
+    #[clap(long)]
+    clock: Option<String>,
+
+    /// Clock edge that starts a cycle, 'rising' or 'falling' (VCD only)
+    #[clap(long, default_value = "rising")]
+    edge: String,
+
+    /// Shift the sample point of each cycle by this many timeunits, negative values sample earlier
+    #[clap(long, default_value = "0", allow_hyphen_values = true)]
+    sample_offset: i64,
+
+    /// Value shown for each cycle, 'start' or 'end' of the cycle
+    #[clap(long, default_value = "end")]
+    sample_at: String,
+
+    /// Watch the file for data appended by a running simulation (VCD only)
+    #[clap(long)]
+    follow: bool,
+
+    /// Like --follow, but keep the cursor at the newest cycle
+    #[clap(long)]
+    pin: bool,
+
+    /// Reload the file, when a new simulation run replaces it
+    #[clap(long)]
+    auto_reload: bool,
+
     /// Timeunits to use to interpret times given in arguments
     #[clap(short, long, default_value = "ps")]
     timeunits: String,
@@ -1,3 +1,9 @@
 pub mod vcd;
+pub mod ghw;
+pub mod fst;
 pub mod empty;
 pub mod plugin;
+pub mod lua;
+pub mod compressed;
+pub mod merge;
+pub mod concat;
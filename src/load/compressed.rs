@@ -0,0 +1,203 @@
+//! Transparent access to compressed waveform files
+//!
+//! The compression is detected by the magic bytes at the start of the file. Compressed streams
+//! can not be seeked. So a compressed file is decompressed once into a temporary file, which is
+//! read at any position like an uncompressed file. The temporary file takes as much disk space
+//! as the uncompressed waveform. It is kept in the directory returned by `std::env::temp_dir`,
+//! which is taken from `TMPDIR` on Unix, so it can be moved to a disk with enough space.
+
+use crate::error::*;
+
+use flate2::read::MultiGzDecoder;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const XZ_MAGIC: &[u8] = &[0xfd, b'7', b'z', b'X', b'Z', 0x00];
+
+/// Determine compression of file from its first bytes.
+pub fn detect_compression(path: impl AsRef<Path>) -> Result<Compression> {
+    let mut file = File::open(path.as_ref())?;
+    let mut magic = Vec::with_capacity(XZ_MAGIC.len());
+    (&mut file).take(XZ_MAGIC.len() as u64).read_to_end(&mut magic)?;
+
+    if magic.starts_with(GZIP_MAGIC) {
+        Ok(Compression::Gzip)
+    } else if magic.starts_with(ZSTD_MAGIC) {
+        Ok(Compression::Zstd)
+    } else if magic.starts_with(XZ_MAGIC) {
+        Ok(Compression::Xz)
+    } else {
+        Ok(Compression::None)
+    }
+}
+
+/// Check if file exists and is compressed in one of the supported formats.
+pub fn is_compressed(path: impl AsRef<Path>) -> bool {
+    matches!(detect_compression(path), Ok(c) if c != Compression::None)
+}
+
+/// Check if file is a compressed VCD file. Only VCD files can be read compressed, so any other
+/// compressed file is an error.
+///
+/// A VCD file starts with a `$` keyword like `$date` or `$timescale`, after optional white space.
+/// This is checked in the decompressed data, so the name of the file does not matter.
+pub fn is_compressed_vcd(path: impl AsRef<Path>) -> Result<bool> {
+    const HEAD_SIZE: u64 = 4096;
+
+    let path = path.as_ref();
+    let mut decoder = match open_decoder(path)? {
+        Some(decoder) => decoder,
+        None => return Ok(false),
+    };
+
+    let mut head = Vec::with_capacity(HEAD_SIZE as usize);
+    (&mut decoder).take(HEAD_SIZE).read_to_end(&mut head)?;
+
+    match head.iter().find(|b| !b.is_ascii_whitespace()) {
+        Some(b'$') => Ok(true),
+        _ => Err(Error::InvalidArgument(format!(
+            "Compressed file '{}' is not supported. Only VCD files can be read compressed.",
+            path.display()))),
+    }
+}
+
+/// Open decoder for the content of a compressed file. Returns `None` for a file, that is not
+/// compressed.
+fn open_decoder(path: &Path) -> Result<Option<Box<dyn Read>>> {
+    let compression = detect_compression(path)?;
+    let file = File::open(path)?;
+
+    let decoder: Box<dyn Read> = match compression {
+        Compression::None => return Ok(None),
+        Compression::Gzip => Box::new(MultiGzDecoder::new(BufReader::new(file))),
+        Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(file)?),
+        Compression::Xz => Box::new(xz2::read::XzDecoder::new_multi_decoder(BufReader::new(file))),
+    };
+
+    Ok(Some(decoder))
+}
+
+/// Decompressed content of a file, kept in a temporary file, that is removed when dropped
+pub struct Decompressed {
+    path: PathBuf,
+}
+
+impl Decompressed {
+    /// Temporary file holding the decompressed content
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for Decompressed {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Decompress file into a temporary file in `std::env::temp_dir`. Returns `None` for a file, that
+/// is not compressed.
+pub fn decompress(path: impl AsRef<Path>) -> Result<Option<Decompressed>> {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    let mut decoder = match open_decoder(path.as_ref())? {
+        Some(decoder) => decoder,
+        None => return Ok(None),
+    };
+
+    let name = format!("viow-{}-{}.tmp", std::process::id(), COUNT.fetch_add(1, Ordering::Relaxed));
+    let rv = Decompressed {
+        path: std::env::temp_dir().join(name),
+    };
+    let mut out = BufWriter::new(OpenOptions::new().write(true).create_new(true).open(&rv.path)?);
+    std::io::copy(&mut decoder, &mut out)?;
+    out.flush()?;
+
+    Ok(Some(rv))
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use tempdir::TempDir;
+
+    const CONTENT: &[u8] = b"$timescale 1 ps $end\n#0\n1!\n#1\n0!\n";
+
+    #[test]
+    fn test_decompress() {
+        let tmpd = TempDir::new("compressed").unwrap();
+
+        let plain = tmpd.path().join("plain.vcd");
+        File::create(&plain).unwrap().write_all(CONTENT).unwrap();
+
+        let gz = tmpd.path().join("dump.vcd.gz");
+        let mut encoder = GzEncoder::new(File::create(&gz).unwrap(), flate2::Compression::default());
+        encoder.write_all(CONTENT).unwrap();
+        encoder.finish().unwrap();
+
+        let zst = tmpd.path().join("dump.vcd.zst");
+        let compressed = zstd::stream::encode_all(CONTENT, 0).unwrap();
+        File::create(&zst).unwrap().write_all(&compressed).unwrap();
+
+        let xz = tmpd.path().join("dump.vcd.xz");
+        let mut encoder = xz2::write::XzEncoder::new(File::create(&xz).unwrap(), 6);
+        encoder.write_all(CONTENT).unwrap();
+        encoder.finish().unwrap();
+
+        assert_eq!(Compression::None, detect_compression(&plain).unwrap());
+        assert_eq!(Compression::Gzip, detect_compression(&gz).unwrap());
+        assert_eq!(Compression::Zstd, detect_compression(&zst).unwrap());
+        assert_eq!(Compression::Xz, detect_compression(&xz).unwrap());
+
+        assert!(decompress(&plain).unwrap().is_none());
+        for path in [&gz, &zst, &xz].iter() {
+            let decompressed = decompress(path).unwrap().unwrap();
+            assert_eq!(CONTENT, &std::fs::read(decompressed.path()).unwrap()[..]);
+
+            // removed with the last user
+            let tmp_path = decompressed.path().to_path_buf();
+            drop(decompressed);
+            assert!(!tmp_path.exists());
+        }
+    }
+
+    #[test]
+    fn test_compressed_vcd() {
+        let tmpd = TempDir::new("compressed").unwrap();
+        let compressed = zstd::stream::encode_all(CONTENT, 0).unwrap();
+        let not_vcd = zstd::stream::encode_all(&b"\0\0FST"[..], 0).unwrap();
+
+        // detected by the content, not by the name
+        let vcd = tmpd.path().join("dump.vcd.zst");
+        let gz = tmpd.path().join("dump.gz");
+        let fst = tmpd.path().join("dump.fst.zst");
+        let plain = tmpd.path().join("dump.vcd");
+        File::create(&vcd).unwrap().write_all(&compressed).unwrap();
+        let mut encoder = GzEncoder::new(File::create(&gz).unwrap(), flate2::Compression::default());
+        encoder.write_all(b"\n  ").unwrap();
+        encoder.write_all(CONTENT).unwrap();
+        encoder.finish().unwrap();
+        File::create(&fst).unwrap().write_all(&not_vcd).unwrap();
+        File::create(&plain).unwrap().write_all(CONTENT).unwrap();
+
+        assert!(is_compressed_vcd(&vcd).unwrap());
+        assert!(is_compressed_vcd(&gz).unwrap());
+        assert!(!is_compressed_vcd(&plain).unwrap());
+        assert!(is_compressed_vcd(&fst).is_err());
+    }
+}
@@ -0,0 +1,381 @@
+use crate::error::*;
+use crate::data::*;
+use crate::pipeline::SrcBox;
+use crate::formatting::EnumTables;
+
+use ndarray::prelude::*;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// One of the concatenated sources
+struct Part {
+    source: SrcBox,
+    /// Ids of the signals in this source by name
+    ids: HashMap<String, String>,
+    /// First cycle of this source in the concatenated trace
+    first_cycle: usize,
+    /// Time, at which this source starts in the concatenated trace
+    start_time: SimTime,
+}
+
+impl Part {
+    fn time(&self, cycle: usize) -> SimTime {
+        self.start_time + (self.source.query_time(cycle) - self.source.query_time(0))
+    }
+}
+
+/// Presents several sources with the same signals one after another as one trace
+///
+/// This joins dumps of a long run, that was split into several files, e.g. by checkpoints. Each
+/// source starts, where the previous one ends, no matter which time its own cycles start at. The
+/// signals are those of the first source. All other sources must have them as well.
+pub struct ConcatSource {
+    parts: Vec<Part>,
+    signals: Vec<Signal<String>>,
+    signal_names: HashMap<String, usize>,
+}
+
+impl ConcatSource {
+    pub fn new(sources: Vec<SrcBox>) -> Result<Self> {
+        if sources.is_empty() {
+            return Err(Error::InvalidArgument("Concatenation needs at least one source".to_string()));
+        }
+
+        let parts = sources.into_iter()
+            .map(|source| Part {
+                source,
+                ids: HashMap::new(),
+                first_cycle: 0,
+                start_time: SimTime::zero(),
+            })
+            .collect();
+        let mut rv = Self {
+            parts,
+            signals: vec![],
+            signal_names: HashMap::new(),
+        };
+
+        rv.init()?;
+        Ok(rv)
+    }
+
+    /// Match the signals of all sources
+    fn init(&mut self) -> Result<()> {
+        let signals = self.parts[0].source.query_signals()?;
+
+        for (i, part) in self.parts.iter_mut().enumerate() {
+            let ids: HashMap<String, String> = part.source.query_signals()?
+                .into_iter()
+                .map(|signal| (signal.name, signal.id))
+                .collect();
+
+            if let Some(missing) = signals.iter().find(|signal| !ids.contains_key(&signal.name)) {
+                let msg = format!("Signal '{}' is missing in part {} of the trace", missing.name, i + 1);
+                return Err(Error::InvalidArgument(msg));
+            }
+            part.ids = ids;
+        }
+
+        self.signal_names = signals.iter()
+            .enumerate()
+            .map(|(i, signal)| (signal.name.clone(), i))
+            .collect();
+        self.signals = signals.into_iter()
+            .map(|signal| Signal { id: signal.name.clone(), ..signal })
+            .collect();
+        self.place_parts();
+
+        Ok(())
+    }
+
+    /// Place the sources one after another, e.g. after their number of cycles changed
+    fn place_parts(&mut self) {
+        let mut first_cycle = 0;
+        let mut start_time = self.parts[0].source.query_time(0);
+
+        for part in self.parts.iter_mut() {
+            part.first_cycle = first_cycle;
+            part.start_time = start_time;
+
+            let count = part.source.query_cycle_count();
+            first_cycle += count;
+            start_time = part.time(count);
+        }
+    }
+
+    /// Index of the source, that holds `cycle`. The end of the trace belongs to the last source.
+    fn part_of(&self, cycle: usize) -> usize {
+        self.parts.partition_point(|part| part.first_cycle <= cycle)
+            .saturating_sub(1)
+    }
+}
+
+
+impl QuerySource for ConcatSource {
+    type Id = String;
+    type IntoSignalIter = Vec<Signal<Self::Id>>;
+
+    fn query_signals(&self) -> Result<Self::IntoSignalIter> {
+        let rv = self.signals.iter()
+            .map(|signal| Signal {
+                id: signal.id.clone(),
+                name: signal.name.clone(),
+                format: signal.format,
+            })
+            .collect();
+
+        Ok(rv)
+    }
+
+    fn query_time_range(&self) -> Result<SimTimeRange> {
+        let start = self.query_time(0);
+        let stop = self.query_time(self.query_cycle_count());
+
+        Ok(SimTimeRange(start, stop))
+    }
+
+    fn query_time(&self, cycle: usize) -> SimTime {
+        let part = &self.parts[self.part_of(cycle)];
+        part.time(cycle - part.first_cycle)
+    }
+
+    fn query_cycle_count(&self) -> usize {
+        let last = &self.parts[self.parts.len() - 1];
+        last.first_cycle + last.source.query_cycle_count()
+    }
+
+    /// Only the last source is expected to grow, but all are asked.
+    fn query_refresh(&mut self) -> Result<bool> {
+        let mut changed = false;
+        for part in self.parts.iter_mut() {
+            changed |= part.source.query_refresh()?;
+        }
+
+        if changed {
+            self.place_parts();
+        }
+        Ok(changed)
+    }
+
+    fn query_reload(&mut self) -> Result<()> {
+        for part in self.parts.iter_mut() {
+            part.source.query_reload()?;
+        }
+
+        self.init()
+    }
+
+    fn query_files(&self) -> Vec<PathBuf> {
+        self.parts.iter()
+            .flat_map(|part| part.source.query_files())
+            .collect()
+    }
+
+    fn query_enums(&self) -> EnumTables {
+        let mut enums = EnumTables::default();
+        for part in self.parts.iter() {
+            enums.extend(part.source.query_enums());
+        }
+
+        enums
+    }
+}
+
+impl LookupId for ConcatSource {
+    type FromId = String;
+    type ToId = usize;
+
+    fn lookup_id(&self, id: &Self::FromId) -> Result<Self::ToId> {
+        self.signal_names.get(id)
+            .copied()
+            .ok_or_else(|| Error::NotFound(id.clone()))
+    }
+
+    fn rev_lookup_id(&self, id: &Self::ToId) -> Result<Self::FromId> {
+        if *id < self.signals.len() {
+            Ok(self.signals[*id].id.clone())
+        } else {
+            Err(Error::IdOutOfRange(*id, 0..self.signals.len()))
+        }
+    }
+}
+
+impl Sample for ConcatSource {
+    type Id = String;
+    type Value = LogicValue;
+
+    /// Each source is sampled for the cycles it holds, with its own times.
+    fn sample(
+        &mut self,
+        ids: &Vec<Self::Id>,
+        times: &SimTimeRange,
+    ) -> Result<CycleValues<Self::Value>> {
+        let first = self.query_cycle(times.0);
+        let last = self.query_cycle(times.1).max(first);
+
+        let mut data = Array2::default((last - first, ids.len()));
+        for part in self.parts.iter_mut() {
+            let count = part.source.query_cycle_count();
+            let part_first = first.clamp(part.first_cycle, part.first_cycle + count);
+            let part_last = last.clamp(part.first_cycle, part.first_cycle + count);
+            if part_first == part_last {
+                continue;
+            }
+
+            let part_ids = ids.iter()
+                .map(|id| part.ids.get(id).cloned().ok_or_else(|| Error::NotFound(id.clone())))
+                .collect::<Result<Vec<_>>>()?;
+            let part_times = SimTimeRange(
+                part.source.query_time(part_first - part.first_cycle),
+                part.source.query_time(part_last - part.first_cycle));
+            let values = part.source.sample(&part_ids, &part_times)?;
+
+            data.slice_mut(s![part_first - first..part_last - first, ..])
+                .assign(&values);
+        }
+
+        Ok(data)
+    }
+}
+
+impl Source<String, usize, LogicValue> for ConcatSource {}
+
+
+/// Files matching `pattern`, sorted by name, with numbers compared by value
+///
+/// The wildcards `*` and `?` are supported in the file name, but not in the directories.
+pub fn glob(pattern: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+    let pattern = pattern.as_ref();
+    let not_found = || Error::NotFound(pattern.to_string_lossy().to_string());
+    let name_pattern: Vec<char> = pattern.file_name()
+        .ok_or_else(not_found)?
+        .to_string_lossy()
+        .chars()
+        .collect();
+    let dir = match pattern.parent() {
+        Some(dir) if dir != Path::new("") => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(&dir)? {
+        let path = entry?.path();
+        let name: Vec<char> = match path.file_name() {
+            Some(name) => name.to_string_lossy().chars().collect(),
+            None => continue,
+        };
+
+        if path.is_file() && wildcard_match(&name_pattern, &name) {
+            files.push(path);
+        }
+    }
+
+    if files.is_empty() {
+        return Err(not_found());
+    }
+    files.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()).then_with(|| a.cmp(b)));
+
+    Ok(files)
+}
+
+/// Compare names by the values of the numbers in them, e.g. `run_2` before `run_10`
+fn natural_cmp(mut a: &str, mut b: &str) -> Ordering {
+    while let (Some(x), Some(y)) = (a.chars().next(), b.chars().next()) {
+        let ord = if x.is_ascii_digit() && y.is_ascii_digit() {
+            let (x, rest_a) = split_number(a);
+            let (y, rest_b) = split_number(b);
+            a = rest_a;
+            b = rest_b;
+
+            let (x, y) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+            x.len().cmp(&y.len()).then_with(|| x.cmp(y))
+        } else {
+            a = &a[x.len_utf8()..];
+            b = &b[y.len_utf8()..];
+            x.cmp(&y)
+        };
+
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+
+    a.len().cmp(&b.len())
+}
+
+/// Leading digits of `s` and the rest
+fn split_number(s: &str) -> (&str, &str) {
+    s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()))
+}
+
+fn wildcard_match(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skip| wildcard_match(rest, &name[skip..])),
+        Some(('?', rest)) => !name.is_empty() && wildcard_match(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && wildcard_match(rest, &name[1..]),
+    }
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::load::vcd::VcdLoader;
+    use tempdir::TempDir;
+
+    const HEADER: &str = "$timescale 1ns $end\n\
+        $scope module tb $end\n\
+        $var wire 4 ! count $end\n\
+        $upscope $end\n\
+        $enddefinitions $end\n";
+
+    #[test]
+    fn test_concat() {
+        let dir = TempDir::new("viow-test").unwrap();
+        let bodies = [
+            "#0\nb0 !\n#10\nb1 !\n#20\nb10 !\n#30\n",
+            "#0\nb11 !\n#10\nb100 !\n#20\n",
+        ];
+        for (i, body) in bodies.iter().enumerate() {
+            let path = dir.path().join(format!("run_{:03}.vcd", i));
+            std::fs::write(&path, format!("{}{}", HEADER, body)).unwrap();
+        }
+        std::fs::write(dir.path().join("other.vcd"), "").unwrap();
+
+        let files = glob(dir.path().join("run_*.vcd")).unwrap();
+        assert_eq!(2, files.len());
+        assert!(files[0].ends_with("run_000.vcd"));
+        assert!(glob(dir.path().join("run_?.vcd")).is_err());
+
+        for name in ["run_10.vcd", "run_2.vcd"] {
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+        let names: Vec<PathBuf> = glob(dir.path().join("run_?*.vcd")).unwrap()
+            .iter()
+            .map(|path| path.strip_prefix(dir.path()).unwrap().to_path_buf())
+            .collect();
+        let expected: Vec<PathBuf> = ["run_000.vcd", "run_001.vcd", "run_2.vcd", "run_10.vcd"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        assert_eq!(expected, names);
+
+        let sources = files.into_iter()
+            .map(|path| -> SrcBox { Box::new(VcdLoader::new(path, Some(SimTime::from_ns(10))).unwrap()) })
+            .collect();
+        let mut source = ConcatSource::new(sources).unwrap();
+        assert_eq!(5, source.query_cycle_count());
+        assert_eq!(Some(40_000), source.query_time(4).as_ps());
+        assert_eq!(Some(50_000), source.query_time(5).as_ps());
+
+        let ids = vec!["tb.count".to_string()];
+        let times = SimTimeRange(SimTime::from_ns(10), SimTime::from_ns(50));
+        let data = source.sample(&ids, &times).unwrap();
+        let values: Vec<LogicValue> = data.column(0).iter().cloned().collect();
+        let expected: Vec<LogicValue> = (1..5u32).map(LogicValue::from).collect();
+        assert_eq!(expected, values);
+    }
+}
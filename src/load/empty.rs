@@ -1,7 +1,6 @@
 use crate::error::*;
 use crate::data::*;
 
-use rug::Integer;
 use ndarray::prelude::*;
 
 pub struct EmptyLoader {}
@@ -52,7 +51,7 @@ impl LookupId for EmptyLoader {
 
 impl Sample for EmptyLoader {
     type Id = String;
-    type Value = Integer;
+    type Value = LogicValue;
 
     fn sample(&mut self, _ids: &Vec<Self::Id>, _times: &SimTimeRange) -> Result<CycleValues<Self::Value>> {
         let data = Array2::default((0, 0));
@@ -60,4 +59,4 @@ impl Sample for EmptyLoader {
     }
 }
 
-impl Source<String, usize, Integer> for EmptyLoader {}
+impl Source<String, usize, LogicValue> for EmptyLoader {}
@@ -0,0 +1,363 @@
+use crate::data::*;
+use crate::formatting::WaveFormat;
+use crate::error::*;
+
+use rug::Assign;
+
+use fst_reader::{FstFilter, FstHierarchyEntry, FstReader, FstSignalHandle, FstSignalValue};
+use ndarray::prelude::*;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path,PathBuf};
+
+#[derive(Clone)]
+struct SignalDeclaration {
+    pub name: String,
+    pub format: WaveFormat,
+    /// Index of the FST signal handle. Aliases share the same handle. Comments have none.
+    handle: Option<usize>,
+}
+
+/// Loads waveforms from FST files, as written by e.g. Verilator, Icarus or GTKWave.
+///
+/// FST files store value changes in independently compressed blocks, each covering a time
+/// interval. Sampling only reads the blocks that overlap with the requested time range.
+///
+/// Values are never flagged as glitches. Cycles of FST files always have a fixed period, and
+/// like for a `VcdLoader` with such cycles, a clock would look like it glitched in every cycle.
+pub struct FstLoader {
+    filename: PathBuf,
+    signals: Vec<SignalDeclaration>,
+    signal_names: HashMap<String, usize>,
+    num_cycles: usize,
+    cycle_time: SimTime,
+    timescale: SimTime,
+}
+
+fn fst_error(err: fst_reader::ReaderError) -> Error {
+    Error::MalformedData(format!("FST: {}", err))
+}
+
+impl FstLoader {
+    pub fn new(filename: impl AsRef<Path>, cycle_time: Option<SimTime>) -> Result<Self> {
+        let mut reader = Self::open(filename.as_ref())?;
+
+        let header = reader.get_header();
+        let timescale = Self::timescale_to_simtime(header.timescale_exponent)?;
+        let cycle_time = cycle_time.unwrap_or(timescale);
+        let mut signals = Self::load_hierarchy(&mut reader)?;
+        Self::show_real_ranges(&mut reader, &mut signals)?;
+
+        let mut signal_names = HashMap::with_capacity(signals.len());
+        for (i, signal) in signals.iter().enumerate() {
+            signal_names.insert(signal.name.clone(), i);
+        }
+
+        let end_time = timescale * header.end_time;
+        let num_cycles = (end_time / cycle_time) as usize;
+
+        Ok(Self {
+            filename: filename.as_ref().into(),
+            signals,
+            signal_names,
+            num_cycles,
+            cycle_time,
+            timescale,
+        })
+    }
+
+    fn open(filename: &Path) -> Result<FstReader<BufReader<File>>> {
+        let file = File::open(filename)?;
+        let reader = BufReader::new(file);
+
+        FstReader::open(reader)
+            .map_err(fst_error)
+    }
+
+    /// Timescale is given as exponent to base 10 in seconds
+    fn timescale_to_simtime(exponent: i8) -> Result<SimTime> {
+        let (unit, unit_exponent) = match exponent {
+            -15..=-13 => (SimTimeUnit::Fs, -15),
+            -12..=-10 => (SimTimeUnit::Ps, -12),
+            -9..=-7 => (SimTimeUnit::Ns, -9),
+            -6..=-4 => (SimTimeUnit::Us, -6),
+            -3..=-1 => (SimTimeUnit::Ms, -3),
+            0..=2 => (SimTimeUnit::S, 0),
+            _ => return Err(Error::MalformedData(format!("FST: unsupported timescale 1e{} s", exponent))),
+        };
+
+        Ok(SimTime::new(10u64.pow((exponent - unit_exponent) as u32), unit))
+    }
+
+    fn load_hierarchy(reader: &mut FstReader<BufReader<File>>) -> Result<Vec<SignalDeclaration>> {
+        let mut rv = vec![];
+        let mut scopes: Vec<String> = vec![];
+
+        reader.read_hierarchy(|entry| {
+            match entry {
+                FstHierarchyEntry::Scope { name, .. } => {
+                    scopes.push(name);
+                }
+
+                FstHierarchyEntry::UpScope => {
+                    scopes.pop();
+                }
+
+                FstHierarchyEntry::Var { tpe, name, length, handle, .. } => {
+                    // Some writers append the bit range to the name, e.g. 'data [7:0]'.
+                    let name = name.split(' ').next().unwrap_or("");
+                    let mut full_name = scopes.join(".");
+                    if !full_name.is_empty() {
+                        full_name.push('.');
+                    }
+                    full_name.push_str(name);
+
+                    let format = if tpe.is_real() {
+                        WaveFormat::Real(0.0, 1.0)
+                    } else if length == 1 {
+                        WaveFormat::Bit
+                    } else {
+                        WaveFormat::Vector(length)
+                    };
+
+                    rv.push(SignalDeclaration {
+                        name: full_name,
+                        format,
+                        handle: Some(handle.get_index()),
+                    });
+                }
+
+                FstHierarchyEntry::Comment { string } => {
+                    rv.push(SignalDeclaration {
+                        name: format!("-- {}: {}", scopes.join("."), string),
+                        format: WaveFormat::Comment,
+                        handle: None,
+                    });
+                }
+
+                _ => (),
+            }
+        }).map_err(fst_error)?;
+
+        Ok(rv)
+    }
+
+    /// Show reals over the whole range of their values.
+    fn show_real_ranges(reader: &mut FstReader<BufReader<File>>, signals: &mut [SignalDeclaration]) -> Result<()> {
+        let handles: Vec<FstSignalHandle> = signals.iter()
+            .filter(|decl| matches!(decl.format, WaveFormat::Real(..)))
+            .filter_map(|decl| decl.handle)
+            .map(FstSignalHandle::from_index)
+            .collect();
+        if handles.is_empty() {
+            return Ok(());
+        }
+
+        let mut ranges: HashMap<usize, (f64, f64)> = HashMap::new();
+        reader.read_signals(&FstFilter::filter_signals(handles), |_, handle, value| {
+            if let FstSignalValue::Real(x) = value {
+                let range = ranges.entry(handle.get_index()).or_insert((x, x));
+                range.0 = range.0.min(x);
+                range.1 = range.1.max(x);
+            }
+        }).map_err(fst_error)?;
+
+        for decl in signals.iter_mut() {
+            if let (WaveFormat::Real(..), Some((min, max))) = (decl.format, decl.handle.and_then(|handle| ranges.get(&handle))) {
+                decl.format = WaveFormat::Real(*min, *max);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn map_value_to_int(target: &mut LogicValue, value: &FstSignalValue) {
+        match value {
+            FstSignalValue::String(bits) => {
+                target.assign_bits(bits.iter().map(|bit| LogicBit::from_ascii(*bit)));
+            }
+
+            FstSignalValue::Real(x) => {
+                target.assign(x.to_bits());
+            }
+        }
+    }
+}
+
+impl QuerySource for FstLoader {
+    type Id = String;
+    type IntoSignalIter = Vec<Signal<Self::Id>>;
+
+    fn query_signals(&self) -> Result<Self::IntoSignalIter> {
+        let rv: Self::IntoSignalIter = self
+            .signals
+            .iter()
+            .map(|decl| Signal {
+                id: decl.name.clone(),
+                name: decl.name.clone(),
+                format: decl.format,
+            })
+            .collect();
+
+        Ok(rv)
+    }
+
+    fn query_time_range(&self) -> Result<SimTimeRange> {
+        let start = SimTime::zero();
+        let stop = self.cycle_time * (self.num_cycles as u64);
+
+        Ok(SimTimeRange(start, stop))
+    }
+
+    fn query_time(&self, cycle: usize) -> SimTime {
+        self.cycle_time * (cycle as u64)
+    }
+
+    fn query_cycle_count(&self) -> usize {
+        self.num_cycles
+    }
+
+    fn query_reload(&mut self) -> Result<()> {
+        *self = Self::new(&self.filename, Some(self.cycle_time))?;
+        Ok(())
+    }
+
+    fn query_files(&self) -> Vec<PathBuf> {
+        vec![self.filename.clone()]
+    }
+}
+
+impl LookupId for FstLoader {
+    type FromId = String;
+    type ToId = usize;
+
+    fn lookup_id(&self, id: &Self::FromId) -> Result<Self::ToId> {
+        self.signal_names.get(id)
+            .copied()
+            .ok_or_else(|| Error::NotFound(id.clone()))
+    }
+
+    fn rev_lookup_id(&self, id: &Self::ToId) -> Result<Self::FromId> {
+        if *id < self.signals.len() {
+            Ok(self.signals[*id].name.clone())
+        } else {
+            Err(Error::IdOutOfRange(*id, 0..self.signals.len()))
+        }
+    }
+}
+
+impl Sample for FstLoader {
+    type Id = String;
+    type Value = LogicValue;
+
+    fn sample(
+        &mut self,
+        ids: &Vec<Self::Id>,
+        times: &SimTimeRange,
+    ) -> Result<CycleValues<Self::Value>> {
+        let start_cycle = times.0 / self.cycle_time;
+        let stop_cycle = times.1 / self.cycle_time;
+        let num_cycles = stop_cycle.saturating_sub(start_cycle) as usize;
+        let cycle_time_ts = std::cmp::max(self.cycle_time / self.timescale, 1);
+
+        // columns, that show each FST signal
+        let mut columns: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (col, id) in ids.iter().enumerate() {
+            let handle = self.signal_names.get(id)
+                .and_then(|i| self.signals[*i].handle);
+
+            if let Some(handle) = handle {
+                columns.entry(handle).or_default().push(col);
+            }
+        }
+
+        let mut data = Array2::default((num_cycles, ids.len()));
+        if num_cycles == 0 || columns.is_empty() {
+            return Ok(data);
+        }
+
+        let mut cur: Array1<LogicValue> = Array1::default(ids.len());
+        let mut cur_cycle = start_cycle;
+        let mut cur_t = start_cycle * cycle_time_ts;
+
+        // Only the blocks overlapping with the requested range are decompressed. The reader
+        // starts with the initial values of the first block, so changes before the range are
+        // seen as well.
+        let handles = columns.keys()
+            .map(|handle| FstSignalHandle::from_index(*handle))
+            .collect();
+        let filter = FstFilter::new(start_cycle * cycle_time_ts, stop_cycle * cycle_time_ts - 1, handles);
+
+        let mut reader = Self::open(&self.filename)?;
+        reader.read_signals(&filter, |t, handle, value| {
+            while t >= cur_t + cycle_time_ts && cur_cycle < stop_cycle {
+                data.row_mut((cur_cycle - start_cycle) as usize).assign(&cur);
+                cur_t += cycle_time_ts;
+                cur_cycle += 1;
+            }
+
+            if let Some(cols) = columns.get(&handle.get_index()) {
+                for col in cols.iter() {
+                    Self::map_value_to_int(&mut cur[*col], &value);
+                }
+            }
+        }).map_err(fst_error)?;
+
+        // values remain until the end of the range
+        while cur_cycle < stop_cycle {
+            data.row_mut((cur_cycle - start_cycle) as usize).assign(&cur);
+            cur_cycle += 1;
+        }
+
+        Ok(data)
+    }
+}
+
+impl Source<String, usize, LogicValue> for FstLoader {}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_timescale() {
+        let ts = FstLoader::timescale_to_simtime(-9).unwrap();
+        assert_eq!(1, ts / SimTime::from_ns(1));
+
+        let ts = FstLoader::timescale_to_simtime(-10).unwrap();
+        assert_eq!(100, ts / SimTime::from_ps(1));
+
+        let ts = FstLoader::timescale_to_simtime(-15).unwrap();
+        assert_eq!(1, ts / SimTime::from_fs(1));
+
+        assert!(FstLoader::timescale_to_simtime(-18).is_err());
+    }
+
+    #[test]
+    fn test_sample_reversed() {
+        let mut loader = FstLoader::new("examples/counter.fst", Some(SimTime::from_ns(10))).unwrap();
+        let ids = vec!["tb.cnt".to_string()];
+
+        let data = loader.sample(&ids, &SimTimeRange(SimTime::from_ns(50), SimTime::from_ns(20))).unwrap();
+        assert_eq!((0, 1), data.dim());
+    }
+
+    #[test]
+    fn test_map_value() {
+        let mut x = LogicValue::new();
+
+        FstLoader::map_value_to_int(&mut x, &FstSignalValue::String(b"1011"));
+        assert_eq!(LogicValue::from(11), x);
+
+        FstLoader::map_value_to_int(&mut x, &FstSignalValue::String(b"1x0z"));
+        assert_eq!(rug::Integer::from(8), *x.value());
+        assert_eq!(LogicBit::X, x.bit(2));
+        assert_eq!(LogicBit::Z, x.bit(0));
+
+        FstLoader::map_value_to_int(&mut x, &FstSignalValue::Real(1.5));
+        assert_eq!(LogicValue::from(1.5f64.to_bits()), x);
+    }
+}
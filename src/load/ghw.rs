@@ -0,0 +1,1437 @@
+use crate::data::*;
+use crate::formatting::{EnumNames, EnumTables, WaveFormat};
+use crate::error::*;
+
+use rug::{Assign, Integer};
+
+use ndarray::prelude::*;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path,PathBuf};
+
+const GHW_MAGIC: &[u8] = b"GHDLwave\n";
+
+/// Number of cycles between two checkpoints
+const CHECKPOINT_INTERVAL: u64 = 4096;
+
+// Kinds of GHDL run-time type information (ghdl_rtik) found in the type table
+const RTIK_TYPE_B2: u8 = 24;
+const RTIK_TYPE_E8: u8 = 25;
+const RTIK_TYPE_E32: u8 = 26;
+const RTIK_TYPE_I32: u8 = 27;
+const RTIK_TYPE_I64: u8 = 28;
+const RTIK_TYPE_F64: u8 = 29;
+const RTIK_TYPE_P32: u8 = 30;
+const RTIK_TYPE_P64: u8 = 31;
+const RTIK_TYPE_ARRAY: u8 = 33;
+const RTIK_TYPE_RECORD: u8 = 34;
+const RTIK_SUBTYPE_SCALAR: u8 = 36;
+const RTIK_SUBTYPE_ARRAY: u8 = 37;
+const RTIK_SUBTYPE_RECORD: u8 = 40;
+
+// Kinds of entries in the design hierarchy
+const HIE_EOH: u8 = 0;
+const HIE_BLOCK: u8 = 3;
+const HIE_GENERATE_IF: u8 = 4;
+const HIE_GENERATE_FOR: u8 = 5;
+const HIE_INSTANCE: u8 = 6;
+const HIE_PACKAGE: u8 = 7;
+const HIE_PROCESS: u8 = 13;
+const HIE_GENERIC: u8 = 14;
+const HIE_EOS: u8 = 15;
+const HIE_SIGNAL: u8 = 16;
+const HIE_PORT_LINKAGE: u8 = 21;
+
+// Well known types
+const WKT_BOOLEAN: u8 = 1;
+const WKT_BIT: u8 = 2;
+const WKT_STD_ULOGIC: u8 = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ScalarKind {
+    B2, E8, I32, I64, F64, P32, P64,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct GhwRange {
+    downto: bool,
+    left: i64,
+    right: i64,
+    discrete: bool,
+}
+
+impl GhwRange {
+    fn len(&self) -> usize {
+        if !self.discrete {
+            0
+        } else if self.downto {
+            std::cmp::max(self.left - self.right + 1, 0) as usize
+        } else {
+            std::cmp::max(self.right - self.left + 1, 0) as usize
+        }
+    }
+
+    fn index(&self, offset: usize) -> i64 {
+        if self.downto {
+            self.left - offset as i64
+        } else {
+            self.left + offset as i64
+        }
+    }
+}
+
+/// Entry in the type table
+///
+/// Types refer to each other by their index in the table. Anonymous subtypes, that GHW only
+/// declares inline, get their own entries.
+enum GhwType {
+    Enum { kind: ScalarKind, wkt: u8, literals: Vec<String> },
+    Scalar { kind: ScalarKind },
+    SubtypeScalar { base: usize },
+    Array { element: usize, dims: Vec<usize> },
+    SubtypeArray { base: usize, ranges: Vec<GhwRange>, element: usize, num_scalars: usize },
+    Record { fields: Vec<(String, usize)>, num_scalars: Option<usize> },
+    SubtypeRecord { fields: Vec<(String, usize)>, num_scalars: usize },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum GhwValue {
+    Enum(u8),
+    Int(i64),
+    Real(f64),
+}
+
+impl std::fmt::Display for GhwValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GhwValue::Enum(x) => write!(f, "{}", x),
+            GhwValue::Int(x) => write!(f, "{}", x),
+            GhwValue::Real(x) => write!(f, "{}", x),
+        }
+    }
+}
+
+/// How values of basic GHW signals are combined into a single value for display
+#[derive(Clone, Copy)]
+enum Encoding {
+    /// Elements of a well known logic type, most significant first
+    Logic(u8),
+    /// Position of an enumeration literal
+    Enum,
+    /// Two's complement integer of given width
+    Int(u32),
+    /// IEEE-754 double precision bit pattern
+    Real,
+}
+
+#[derive(Clone)]
+struct SignalDeclaration {
+    pub name: String,
+    pub format: WaveFormat,
+    encoding: Encoding,
+    sigs: Vec<usize>,
+}
+
+impl SignalDeclaration {
+    fn assign_value(&self, values: &[GhwValue], target: &mut LogicValue) {
+        match self.encoding {
+            Encoding::Logic(wkt) => {
+                target.assign_bits(self.sigs.iter().map(|sig| logic_bit(wkt, values[*sig])));
+            }
+
+            Encoding::Enum => {
+                match values[self.sigs[0]] {
+                    GhwValue::Enum(x) => target.assign(x),
+                    GhwValue::Int(x) => target.assign(x),
+                    GhwValue::Real(_) => target.assign(0),
+                }
+            }
+
+            Encoding::Int(32) => {
+                match values[self.sigs[0]] {
+                    GhwValue::Int(x) => target.assign(x as i32 as u32),
+                    _ => target.assign(0),
+                }
+            }
+
+            Encoding::Int(_) => {
+                match values[self.sigs[0]] {
+                    GhwValue::Int(x) => target.assign(x as u64),
+                    _ => target.assign(0),
+                }
+            }
+
+            Encoding::Real => {
+                match values[self.sigs[0]] {
+                    GhwValue::Real(x) => target.assign(x.to_bits()),
+                    _ => target.assign(0),
+                }
+            }
+        }
+    }
+}
+
+/// Signal declaration as found in the design hierarchy, possibly of composite type
+struct HierarchySignal {
+    name: String,
+    type_id: usize,
+    sigs: Vec<usize>,
+}
+
+/// Minimum and maximum of each real signal by its number
+type RealRanges = HashMap<usize, (f64, f64)>;
+
+struct GhwHeader {
+    types: Vec<GhwType>,
+    signals: Vec<HierarchySignal>,
+    /// Scalar kind of each basic signal. Index 0 and unused indices are `None`.
+    sig_kinds: Vec<Option<ScalarKind>>,
+}
+
+fn logic_bit(wkt: u8, value: GhwValue) -> LogicBit {
+    match (wkt, value) {
+        // std_ulogic literals are 'U', 'X', '0', '1', 'Z', 'W', 'L', 'H', '-'
+        (WKT_STD_ULOGIC, GhwValue::Enum(x)) => match x {
+            2 | 6 => LogicBit::Zero,
+            3 | 7 => LogicBit::One,
+            4 => LogicBit::Z,
+            _ => LogicBit::X,
+        },
+        (_, GhwValue::Enum(1)) => LogicBit::One,
+        _ => LogicBit::Zero,
+    }
+}
+
+fn is_logic(wkt: u8) -> bool {
+    wkt == WKT_BOOLEAN || wkt == WKT_BIT || wkt == WKT_STD_ULOGIC
+}
+
+fn malformed(msg: impl Into<String>) -> Error {
+    Error::MalformedData(format!("GHW: {}", msg.into()))
+}
+
+
+/// Low-level access to the binary encoding of a GHW file
+struct GhwReader<R> {
+    inner: R,
+    big_endian: bool,
+    version: u8,
+    /// Byte offset in the file of the next byte to read
+    position: u64,
+}
+
+impl<R: Read> GhwReader<R> {
+    fn read_exact(&mut self, buf: &mut [u8]) -> std::io::Result<()> {
+        self.inner.read_exact(buf)?;
+        self.position += buf.len() as u64;
+        Ok(())
+    }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        let mut buf = [0u8; 1];
+        self.read_exact(&mut buf)?;
+        Ok(buf[0])
+    }
+
+    fn skip(&mut self, num_bytes: usize) -> Result<()> {
+        let skipped = std::io::copy(&mut (&mut self.inner).take(num_bytes as u64), &mut std::io::sink())?;
+        self.position += skipped;
+        if skipped as usize == num_bytes {
+            Ok(())
+        } else {
+            Err(malformed("unexpected end of file"))
+        }
+    }
+
+    /// Read tag of next section. Returns `None` at end of file.
+    fn read_tag(&mut self) -> Result<Option<[u8; 4]>> {
+        let mut tag = [0u8; 4];
+
+        match self.read_exact(&mut tag) {
+            Ok(()) => Ok(Some(tag)),
+            Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn expect_tag(&mut self, expected: &[u8; 4]) -> Result<()> {
+        let mut tag = [0u8; 4];
+        self.read_exact(&mut tag)?;
+
+        if tag == *expected {
+            Ok(())
+        } else {
+            Err(malformed(format!("expected '{}' marker", String::from_utf8_lossy(&expected[..3]))))
+        }
+    }
+
+    fn read_i32(&mut self) -> Result<i32> {
+        let mut buf = [0u8; 4];
+        self.read_exact(&mut buf)?;
+
+        if self.big_endian {
+            Ok(i32::from_be_bytes(buf))
+        } else {
+            Ok(i32::from_le_bytes(buf))
+        }
+    }
+
+    fn read_i64(&mut self) -> Result<i64> {
+        let mut buf = [0u8; 8];
+        self.read_exact(&mut buf)?;
+
+        if self.big_endian {
+            Ok(i64::from_be_bytes(buf))
+        } else {
+            Ok(i64::from_le_bytes(buf))
+        }
+    }
+
+    fn read_f64(&mut self) -> Result<f64> {
+        let mut buf = [0u8; 8];
+        self.read_exact(&mut buf)?;
+
+        if self.big_endian {
+            Ok(f64::from_be_bytes(buf))
+        } else {
+            Ok(f64::from_le_bytes(buf))
+        }
+    }
+
+    fn read_uleb128(&mut self) -> Result<u32> {
+        let mut rv: u32 = 0;
+        let mut shift = 0;
+
+        loop {
+            let byte = self.read_u8()?;
+            if shift < 32 {
+                rv |= ((byte & 0x7f) as u32) << shift;
+            }
+
+            if byte & 0x80 == 0 {
+                break;
+            }
+            shift += 7;
+        }
+
+        Ok(rv)
+    }
+
+    fn read_sleb128(&mut self) -> Result<i64> {
+        let mut rv: i64 = 0;
+        let mut shift = 0;
+
+        loop {
+            let byte = self.read_u8()?;
+            if shift < 64 {
+                rv |= ((byte & 0x7f) as i64) << shift;
+            }
+            shift += 7;
+
+            if byte & 0x80 == 0 {
+                if (byte & 0x40) != 0 && shift < 64 {
+                    rv |= -1i64 << shift;
+                }
+                break;
+            }
+        }
+
+        Ok(rv)
+    }
+
+    fn read_value(&mut self, kind: ScalarKind) -> Result<GhwValue> {
+        use ScalarKind::*;
+
+        match kind {
+            B2 | E8 => Ok(GhwValue::Enum(self.read_u8()?)),
+            I32 | I64 | P32 | P64 => Ok(GhwValue::Int(self.read_sleb128()?)),
+            F64 => Ok(GhwValue::Real(self.read_f64()?)),
+        }
+    }
+
+    fn read_string<'a>(&mut self, strings: &'a [String]) -> Result<&'a str> {
+        let id = self.read_uleb128()? as usize;
+        strings.get(id)
+            .map(|s| s.as_str())
+            .ok_or_else(|| malformed(format!("string id {} is undefined", id)))
+    }
+
+    fn read_type_id(&mut self, type_ids: &[usize]) -> Result<usize> {
+        let id = self.read_uleb128()? as usize;
+        id.checked_sub(1)
+            .and_then(|i| type_ids.get(i))
+            .copied()
+            .ok_or_else(|| malformed(format!("type id {} is undefined", id)))
+    }
+
+    fn read_range(&mut self) -> Result<GhwRange> {
+        let kind = self.read_u8()?;
+        let downto = (kind & 0x80) != 0;
+
+        match kind & 0x7f {
+            RTIK_TYPE_B2 | RTIK_TYPE_E8 => {
+                let left = self.read_u8()? as i64;
+                let right = self.read_u8()? as i64;
+                Ok(GhwRange { downto, left, right, discrete: true })
+            }
+
+            RTIK_TYPE_E32 | RTIK_TYPE_I32 | RTIK_TYPE_P32 | RTIK_TYPE_I64 | RTIK_TYPE_P64 => {
+                let left = self.read_sleb128()?;
+                let right = self.read_sleb128()?;
+                Ok(GhwRange { downto, left, right, discrete: true })
+            }
+
+            RTIK_TYPE_F64 => {
+                self.read_f64()?;
+                self.read_f64()?;
+                Ok(GhwRange { downto, left: 0, right: 0, discrete: false })
+            }
+
+            other => Err(malformed(format!("unexpected range of kind {}", other))),
+        }
+    }
+}
+
+
+//
+// Type table
+//
+
+fn scalar_base(types: &[GhwType], t: usize) -> usize {
+    match types[t] {
+        GhwType::SubtypeScalar { base } => scalar_base(types, base),
+        _ => t,
+    }
+}
+
+fn scalar_kind(types: &[GhwType], t: usize) -> Option<ScalarKind> {
+    match types[scalar_base(types, t)] {
+        GhwType::Enum { kind, .. } | GhwType::Scalar { kind } => Some(kind),
+        _ => None,
+    }
+}
+
+fn is_logic_type(types: &[GhwType], t: usize) -> bool {
+    match types[scalar_base(types, t)] {
+        GhwType::Enum { wkt, .. } => is_logic(wkt),
+        _ => false,
+    }
+}
+
+/// Number of basic signals in a type, or `None` if the type is unbounded.
+fn num_elements(types: &[GhwType], t: usize) -> Option<usize> {
+    match types[t] {
+        GhwType::Enum { .. } | GhwType::Scalar { .. } | GhwType::SubtypeScalar { .. } => Some(1),
+        GhwType::Array { .. } => None,
+        GhwType::SubtypeArray { num_scalars, .. } => Some(num_scalars),
+        GhwType::Record { num_scalars, .. } => num_scalars,
+        GhwType::SubtypeRecord { num_scalars, .. } => Some(num_scalars),
+    }
+}
+
+fn read_array_subtype<R: Read>(rd: &mut GhwReader<R>, types: &mut Vec<GhwType>, base: usize) -> Result<usize> {
+    let array = match types[base] {
+        GhwType::SubtypeArray { base, .. } => base,
+        _ => base,
+    };
+    let (element, num_dims) = match types[array] {
+        GhwType::Array { element, ref dims } => (element, dims.len()),
+        _ => return Err(malformed("array subtype of non-array type")),
+    };
+
+    let mut ranges = Vec::with_capacity(num_dims);
+    let mut num_scalars = 1;
+    for _ in 0..num_dims {
+        let range = rd.read_range()?;
+        num_scalars *= range.len();
+        ranges.push(range);
+    }
+
+    // unbounded element types are constrained right here
+    let element = match num_elements(types, element) {
+        Some(_) => element,
+        None => read_type_bounds(rd, types, element)?,
+    };
+    let num_scalars = num_scalars * num_elements(types, element).unwrap_or(0);
+
+    types.push(GhwType::SubtypeArray { base, ranges, element, num_scalars });
+    Ok(types.len() - 1)
+}
+
+fn read_record_subtype<R: Read>(rd: &mut GhwReader<R>, types: &mut Vec<GhwType>, base: usize) -> Result<usize> {
+    let (fields, num_scalars) = match types[base] {
+        GhwType::Record { ref fields, num_scalars } => (fields.clone(), num_scalars),
+        GhwType::SubtypeRecord { ref fields, num_scalars } => (fields.clone(), Some(num_scalars)),
+        _ => return Err(malformed("record subtype of non-record type")),
+    };
+
+    let subtype = match num_scalars {
+        Some(num_scalars) => GhwType::SubtypeRecord { fields, num_scalars },
+
+        None => {
+            let mut num_scalars = 0;
+            let mut bounded_fields = Vec::with_capacity(fields.len());
+
+            for (name, field_type) in fields {
+                let field_type = match num_elements(types, field_type) {
+                    Some(_) => field_type,
+                    None => read_type_bounds(rd, types, field_type)?,
+                };
+                num_scalars += num_elements(types, field_type).unwrap_or(0);
+                bounded_fields.push((name, field_type));
+            }
+
+            GhwType::SubtypeRecord { fields: bounded_fields, num_scalars }
+        }
+    };
+
+    types.push(subtype);
+    Ok(types.len() - 1)
+}
+
+fn read_type_bounds<R: Read>(rd: &mut GhwReader<R>, types: &mut Vec<GhwType>, base: usize) -> Result<usize> {
+    match types[base] {
+        GhwType::Array { .. } | GhwType::SubtypeArray { .. } => read_array_subtype(rd, types, base),
+        GhwType::Record { .. } | GhwType::SubtypeRecord { .. } => read_record_subtype(rd, types, base),
+        _ => Err(malformed("bounds for scalar type")),
+    }
+}
+
+
+//
+// Header sections
+//
+
+fn read_strings<R: Read>(rd: &mut GhwReader<R>) -> Result<Vec<String>> {
+    rd.skip(4)?;
+    let num_strings = rd.read_i32()? as usize;
+    let _size = rd.read_i32()?;
+
+    // Each string shares a prefix of given length with its predecessor.
+    let mut raw: Vec<Vec<u8>> = Vec::with_capacity(num_strings + 1);
+    raw.push(b"<anon>".to_vec());
+    let mut prefix_len = 0;
+
+    for _ in 0..num_strings {
+        let mut s = raw.last().unwrap()[..prefix_len].to_vec();
+
+        let mut c = loop {
+            let c = rd.read_u8()?;
+            if c <= 31 || (128..=159).contains(&c) {
+                break c;
+            }
+            s.push(c);
+        };
+
+        prefix_len = (c & 0x1f) as usize;
+        let mut shift = 5;
+        while c >= 128 {
+            c = rd.read_u8()?;
+            prefix_len |= ((c & 0x1f) as usize) << shift;
+            shift += 5;
+        }
+
+        raw.push(s);
+    }
+
+    rd.expect_tag(b"EOS\0")?;
+
+    Ok(raw.into_iter()
+        .map(|s| String::from_utf8_lossy(&s).into_owned())
+        .collect())
+}
+
+fn read_types<R: Read>(rd: &mut GhwReader<R>, strings: &[String]) -> Result<(Vec<GhwType>, Vec<usize>)> {
+    rd.skip(4)?;
+    let num_types = rd.read_i32()? as usize;
+    let mut types = Vec::with_capacity(num_types);
+    let mut type_ids = Vec::with_capacity(num_types);
+
+    for _ in 0..num_types {
+        let kind = rd.read_u8()?;
+        let _name = rd.read_string(strings)?;
+
+        let index = match kind {
+            RTIK_TYPE_B2 | RTIK_TYPE_E8 => {
+                let num_literals = rd.read_uleb128()?;
+                let mut literals = Vec::with_capacity(num_literals as usize);
+                for _ in 0..num_literals {
+                    literals.push(rd.read_string(strings)?.to_string());
+                }
+
+                let kind = if kind == RTIK_TYPE_B2 { ScalarKind::B2 } else { ScalarKind::E8 };
+                types.push(GhwType::Enum { kind, wkt: 0, literals });
+                types.len() - 1
+            }
+
+            RTIK_TYPE_I32 | RTIK_TYPE_I64 | RTIK_TYPE_F64 | RTIK_TYPE_P32 | RTIK_TYPE_P64 => {
+                let kind = match kind {
+                    RTIK_TYPE_I32 => ScalarKind::I32,
+                    RTIK_TYPE_I64 => ScalarKind::I64,
+                    RTIK_TYPE_F64 => ScalarKind::F64,
+                    RTIK_TYPE_P32 => ScalarKind::P32,
+                    _ => ScalarKind::P64,
+                };
+
+                // physical units, not used for display
+                if (kind == ScalarKind::P32 || kind == ScalarKind::P64) && rd.version > 0 {
+                    let num_units = rd.read_uleb128()?;
+                    for _ in 0..num_units {
+                        rd.read_string(strings)?;
+                        rd.read_sleb128()?;
+                    }
+                }
+
+                types.push(GhwType::Scalar { kind });
+                types.len() - 1
+            }
+
+            RTIK_SUBTYPE_SCALAR => {
+                let base = rd.read_type_id(&type_ids)?;
+                rd.read_range()?;
+                types.push(GhwType::SubtypeScalar { base });
+                types.len() - 1
+            }
+
+            RTIK_TYPE_ARRAY => {
+                let element = rd.read_type_id(&type_ids)?;
+                let num_dims = rd.read_uleb128()?;
+                let mut dims = Vec::with_capacity(num_dims as usize);
+                for _ in 0..num_dims {
+                    dims.push(rd.read_type_id(&type_ids)?);
+                }
+
+                types.push(GhwType::Array { element, dims });
+                types.len() - 1
+            }
+
+            RTIK_SUBTYPE_ARRAY => {
+                let base = rd.read_type_id(&type_ids)?;
+                read_array_subtype(rd, &mut types, base)?
+            }
+
+            RTIK_TYPE_RECORD => {
+                let num_fields = rd.read_uleb128()?;
+                let mut fields = Vec::with_capacity(num_fields as usize);
+                let mut num_scalars = Some(0);
+                for _ in 0..num_fields {
+                    let name = rd.read_string(strings)?.to_string();
+                    let field_type = rd.read_type_id(&type_ids)?;
+                    num_scalars = num_scalars
+                        .and_then(|n| num_elements(&types, field_type).map(|m| n + m));
+                    fields.push((name, field_type));
+                }
+
+                types.push(GhwType::Record { fields, num_scalars });
+                types.len() - 1
+            }
+
+            RTIK_SUBTYPE_RECORD => {
+                let base = rd.read_type_id(&type_ids)?;
+                read_record_subtype(rd, &mut types, base)?
+            }
+
+            other => return Err(malformed(format!("unsupported type of kind {}", other))),
+        };
+
+        type_ids.push(index);
+    }
+
+    rd.expect_tag(b"EOT\0")?;
+
+    Ok((types, type_ids))
+}
+
+fn read_well_known_types<R: Read>(rd: &mut GhwReader<R>, types: &mut [GhwType], type_ids: &[usize]) -> Result<()> {
+    rd.skip(4)?;
+
+    loop {
+        let wkt = rd.read_u8()?;
+        if wkt == 0 {
+            break;
+        }
+
+        let t = rd.read_type_id(type_ids)?;
+        if let GhwType::Enum { wkt: ref mut known, .. } = types[t] {
+            *known = wkt;
+        }
+    }
+
+    rd.expect_tag(b"EOW\0")
+}
+
+fn read_signal<R: Read>(
+    rd: &mut GhwReader<R>,
+    types: &[GhwType],
+    t: usize,
+    sigs: &mut Vec<usize>,
+    sig_kinds: &mut [Option<ScalarKind>],
+) -> Result<()> {
+    match types[t] {
+        GhwType::Enum { .. } | GhwType::Scalar { .. } | GhwType::SubtypeScalar { .. } => {
+            let sig = rd.read_uleb128()? as usize;
+            if sig == 0 || sig >= sig_kinds.len() {
+                return Err(malformed(format!("signal number {} out of range", sig)));
+            }
+
+            if sig_kinds[sig].is_none() {
+                sig_kinds[sig] = scalar_kind(types, t);
+            }
+            sigs.push(sig);
+        }
+
+        GhwType::SubtypeArray { element, num_scalars, .. } => {
+            let stride = std::cmp::max(num_elements(types, element).unwrap_or(1), 1);
+            for _ in 0..(num_scalars / stride) {
+                read_signal(rd, types, element, sigs, sig_kinds)?;
+            }
+        }
+
+        GhwType::Record { ref fields, .. } | GhwType::SubtypeRecord { ref fields, .. } => {
+            for (_, field_type) in fields.iter() {
+                read_signal(rd, types, *field_type, sigs, sig_kinds)?;
+            }
+        }
+
+        GhwType::Array { .. } => {
+            return Err(malformed("signal of unbounded array type"));
+        }
+    }
+
+    Ok(())
+}
+
+fn read_hierarchy<R: Read>(
+    rd: &mut GhwReader<R>,
+    strings: &[String],
+    types: &[GhwType],
+    type_ids: &[usize],
+) -> Result<(Vec<HierarchySignal>, Vec<Option<ScalarKind>>)> {
+    rd.skip(4)?;
+    let _num_scopes = rd.read_i32()?;
+    let _num_declared = rd.read_i32()?;
+    let num_sigs = rd.read_i32()? as usize;
+
+    // basic signals are numbered from 1
+    let mut sig_kinds = vec![None; num_sigs + 1];
+    let mut signals = vec![];
+    let mut path: Vec<String> = vec![];
+
+    loop {
+        let kind = rd.read_u8()?;
+
+        match kind {
+            HIE_EOH => break,
+
+            HIE_EOS => {
+                path.pop();
+            }
+
+            HIE_PROCESS => {
+                rd.read_string(strings)?;
+            }
+
+            HIE_BLOCK | HIE_GENERATE_IF | HIE_INSTANCE | HIE_GENERIC | HIE_PACKAGE => {
+                path.push(rd.read_string(strings)?.to_string());
+            }
+
+            HIE_GENERATE_FOR => {
+                let name = rd.read_string(strings)?.to_string();
+                let iter_type = rd.read_type_id(type_ids)?;
+                let iter_kind = scalar_kind(types, iter_type)
+                    .ok_or_else(|| malformed("generate iterator of composite type"))?;
+                let iter_value = rd.read_value(iter_kind)?;
+                path.push(format!("{}({})", name, iter_value));
+            }
+
+            HIE_SIGNAL..=HIE_PORT_LINKAGE => {
+                let name = rd.read_string(strings)?;
+                let type_id = rd.read_type_id(type_ids)?;
+                let num_sigs = num_elements(types, type_id)
+                    .ok_or_else(|| malformed(format!("signal '{}' is of unbounded type", name)))?;
+
+                let mut sigs = Vec::with_capacity(num_sigs);
+                read_signal(rd, types, type_id, &mut sigs, &mut sig_kinds)?;
+
+                let mut full_name = path.join(".");
+                if !full_name.is_empty() {
+                    full_name.push('.');
+                }
+                full_name.push_str(name);
+
+                signals.push(HierarchySignal { name: full_name, type_id, sigs });
+            }
+
+            other => return Err(malformed(format!("unknown hierarchy entry of kind {}", other))),
+        }
+    }
+
+    Ok((signals, sig_kinds))
+}
+
+fn parse_header<R: Read>(mut reader: R) -> Result<(GhwReader<R>, GhwHeader)> {
+    let mut hdr = [0u8; 16];
+    reader.read_exact(&mut hdr)?;
+
+    if &hdr[0..9] != GHW_MAGIC || hdr[9] != 16 || hdr[10] != 0 || hdr[15] != 0 {
+        return Err(malformed("not a GHW file"));
+    }
+
+    let version = hdr[11];
+    if version > 1 {
+        return Err(malformed(format!("unsupported version {}", version)));
+    }
+
+    let big_endian = match hdr[12] {
+        1 => false,
+        2 => true,
+        _ => return Err(malformed("unknown byte order")),
+    };
+
+    let mut rd = GhwReader {
+        inner: reader,
+        big_endian,
+        version,
+        position: hdr.len() as u64,
+    };
+
+    let mut strings = vec![];
+    let mut types = vec![];
+    let mut type_ids = vec![];
+    let mut signals = vec![];
+    let mut sig_kinds = vec![];
+
+    loop {
+        let tag = rd.read_tag()?
+            .ok_or_else(|| malformed("unexpected end of header"))?;
+
+        match &tag {
+            b"STR\0" => {
+                strings = read_strings(&mut rd)?;
+            }
+
+            b"TYP\0" => {
+                let (t, ids) = read_types(&mut rd, &strings)?;
+                types = t;
+                type_ids = ids;
+            }
+
+            b"WKT\0" => {
+                read_well_known_types(&mut rd, &mut types, &type_ids)?;
+            }
+
+            b"HIE\0" => {
+                let (s, k) = read_hierarchy(&mut rd, &strings, &types, &type_ids)?;
+                signals = s;
+                sig_kinds = k;
+            }
+
+            b"EOH\0" => break,
+
+            _ => return Err(malformed("unknown header section")),
+        }
+    }
+
+    let header = GhwHeader {
+        types,
+        signals,
+        sig_kinds,
+    };
+
+    Ok((rd, header))
+}
+
+
+//
+// Value changes
+//
+
+enum GhwEvent {
+    /// Simulation time in fs. All following changes happen at this time.
+    Time(u64),
+    /// New value of a basic signal
+    Change(usize, GhwValue),
+}
+
+#[derive(Clone, Copy)]
+enum StreamState {
+    Sections,
+    Snapshot(usize),
+    Cycle(usize),
+}
+
+/// State of the stream at a time step, from which reading can be resumed
+#[derive(Clone)]
+struct Checkpoint {
+    /// Byte offset in the file right after the time step
+    offset: u64,
+    state: StreamState,
+    /// Time of the time step itself
+    time: i64,
+    cycle: u64,
+    /// Start of `cycle` in fs
+    cycle_start: u64,
+    /// Values of all basic signals before the changes of the time step are applied
+    values: Vec<GhwValue>,
+}
+
+/// Stream of time steps and value changes following the header
+struct GhwEvents<'a, R> {
+    rd: GhwReader<R>,
+    sig_kinds: &'a [Option<ScalarKind>],
+    state: StreamState,
+    time: i64,
+}
+
+impl<'a, R: Read> GhwEvents<'a, R> {
+    fn new(rd: GhwReader<R>, sig_kinds: &'a [Option<ScalarKind>]) -> Self {
+        Self {
+            rd,
+            sig_kinds,
+            state: StreamState::Sections,
+            time: 0,
+        }
+    }
+
+    /// Continue reading at `checkpoint`. `rd` must be positioned at its offset.
+    fn resume(rd: GhwReader<R>, sig_kinds: &'a [Option<ScalarKind>], checkpoint: &Checkpoint) -> Self {
+        Self {
+            rd,
+            sig_kinds,
+            state: checkpoint.state,
+            time: checkpoint.time,
+        }
+    }
+
+    fn checkpoint(&self, cycle: u64, cycle_start: u64, values: &[GhwValue]) -> Checkpoint {
+        Checkpoint {
+            offset: self.rd.position,
+            state: self.state,
+            time: self.time,
+            cycle,
+            cycle_start,
+            values: values.to_vec(),
+        }
+    }
+
+    fn next_event(&mut self) -> Result<Option<GhwEvent>> {
+        let sig_kinds = self.sig_kinds;
+
+        loop {
+            match self.state {
+                StreamState::Sections => {
+                    let tag = match self.rd.read_tag()? {
+                        Some(tag) => tag,
+                        None => return Ok(None),
+                    };
+
+                    match &tag {
+                        b"SNP\0" => {
+                            self.rd.skip(4)?;
+                            self.time = self.rd.read_i64()?;
+                            self.state = StreamState::Snapshot(0);
+                            return Ok(Some(GhwEvent::Time(self.time as u64)));
+                        }
+
+                        b"CYC\0" => {
+                            self.time = self.rd.read_i64()?;
+                            self.state = StreamState::Cycle(0);
+                            return Ok(Some(GhwEvent::Time(self.time as u64)));
+                        }
+
+                        b"DIR\0" => {
+                            self.rd.skip(4)?;
+                            let num_entries = self.rd.read_i32()? as usize;
+                            self.rd.skip(8 * num_entries)?;
+                            self.rd.expect_tag(b"EOD\0")?;
+                        }
+
+                        b"TAI\0" => {
+                            self.rd.skip(12)?;
+                        }
+
+                        _ => return Err(malformed("unknown section")),
+                    }
+                }
+
+                StreamState::Snapshot(start) => {
+                    let next = (start..sig_kinds.len())
+                        .find(|i| sig_kinds[*i].is_some());
+
+                    if let Some(sig) = next {
+                        let value = self.rd.read_value(sig_kinds[sig].unwrap())?;
+                        self.state = StreamState::Snapshot(sig + 1);
+                        return Ok(Some(GhwEvent::Change(sig, value)));
+                    } else {
+                        self.rd.expect_tag(b"ESN\0")?;
+                        self.state = StreamState::Sections;
+                    }
+                }
+
+                StreamState::Cycle(last) => {
+                    let delta = self.rd.read_uleb128()? as usize;
+
+                    if delta == 0 {
+                        let delta_time = self.rd.read_sleb128()?;
+
+                        if delta_time == -1 {
+                            self.rd.expect_tag(b"ECY\0")?;
+                            self.state = StreamState::Sections;
+                        } else {
+                            self.time += delta_time;
+                            self.state = StreamState::Cycle(0);
+                            return Ok(Some(GhwEvent::Time(self.time as u64)));
+                        }
+                    } else {
+                        // delta counts signals that are actually dumped
+                        let sig = (last + 1..sig_kinds.len())
+                            .filter(|i| sig_kinds[*i].is_some())
+                            .nth(delta - 1)
+                            .ok_or_else(|| malformed("value change of unknown signal"))?;
+                        let value = self.rd.read_value(sig_kinds[sig].unwrap())?;
+                        self.state = StreamState::Cycle(sig);
+                        return Ok(Some(GhwEvent::Change(sig, value)));
+                    }
+                }
+            }
+        }
+    }
+}
+
+
+//
+// Flattening of composite signals
+//
+
+fn bits_for_literals(num_literals: usize) -> u32 {
+    let max = num_literals.saturating_sub(1) as u64;
+    std::cmp::max(1, 64 - max.leading_zeros())
+}
+
+fn array_index(ranges: &[GhwRange], mut offset: usize) -> String {
+    let mut indices = vec![0; ranges.len()];
+
+    for (dim, range) in ranges.iter().enumerate().rev() {
+        let len = std::cmp::max(range.len(), 1);
+        indices[dim] = range.index(offset % len);
+        offset /= len;
+    }
+
+    indices.iter()
+        .map(|i| i.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Split a hierarchy signal into signals, that can be displayed as a single value.
+///
+/// Arrays of logic elements become vectors. Other arrays and records are split into their
+/// elements. The literals of other enumerations are added to `enums`.
+fn flatten_signal(
+    types: &[GhwType],
+    name: String,
+    t: usize,
+    sigs: &[usize],
+    out: &mut Vec<SignalDeclaration>,
+    enums: &mut EnumTables,
+) {
+    match types[t] {
+        GhwType::Enum { .. } | GhwType::Scalar { .. } | GhwType::SubtypeScalar { .. } => {
+            let (format, encoding) = match types[scalar_base(types, t)] {
+                GhwType::Enum { wkt, .. } if is_logic(wkt) => (WaveFormat::Bit, Encoding::Logic(wkt)),
+                GhwType::Enum { ref literals, .. } => {
+                    let names = EnumNames::new(literals.iter()
+                        .enumerate()
+                        .map(|(i, literal)| (Integer::from(i), literal.clone())));
+                    (WaveFormat::Enum(bits_for_literals(literals.len()), enums.insert(names)), Encoding::Enum)
+                }
+                // the range is known, once all values were read
+                GhwType::Scalar { kind: ScalarKind::F64 } => (WaveFormat::Real(0.0, 1.0), Encoding::Real),
+                GhwType::Scalar { kind: ScalarKind::I32 }
+                | GhwType::Scalar { kind: ScalarKind::P32 } => (WaveFormat::Vector(32), Encoding::Int(32)),
+                _ => (WaveFormat::Vector(64), Encoding::Int(64)),
+            };
+
+            if let Some(sig) = sigs.first() {
+                out.push(SignalDeclaration { name, format, encoding, sigs: vec![*sig] });
+            }
+        }
+
+        GhwType::SubtypeArray { ref ranges, element, .. } => {
+            if ranges.len() == 1 && is_logic_type(types, element) {
+                let wkt = match types[scalar_base(types, element)] {
+                    GhwType::Enum { wkt, .. } => wkt,
+                    _ => WKT_BIT,
+                };
+
+                out.push(SignalDeclaration {
+                    name,
+                    format: WaveFormat::Vector(sigs.len() as u32),
+                    encoding: Encoding::Logic(wkt),
+                    sigs: sigs.to_vec(),
+                });
+            } else {
+                let stride = std::cmp::max(num_elements(types, element).unwrap_or(1), 1);
+                for (i, chunk) in sigs.chunks(stride).enumerate() {
+                    let element_name = format!("{}[{}]", name, array_index(ranges, i));
+                    flatten_signal(types, element_name, element, chunk, out, enums);
+                }
+            }
+        }
+
+        GhwType::Record { ref fields, .. } | GhwType::SubtypeRecord { ref fields, .. } => {
+            let mut offset = 0;
+            for (field_name, field_type) in fields.iter() {
+                let num_sigs = num_elements(types, *field_type).unwrap_or(0);
+                let end = std::cmp::min(offset + num_sigs, sigs.len());
+                flatten_signal(types, format!("{}.{}", name, field_name), *field_type,
+                    &sigs[offset..end], out, enums);
+                offset = end;
+            }
+        }
+
+        GhwType::Array { .. } => {}
+    }
+}
+
+
+//
+// Loader
+//
+
+/// Loads waveforms from GHDL's native GHW format.
+///
+/// Times in GHW files are always given in fs. Cycles are the multiples of the `cycle_time` given
+/// on creation, so no values are flagged as glitches: the clock process of a test bench running
+/// at that period changes its signal twice per cycle and would be flagged throughout.
+pub struct GhwLoader {
+    filename: PathBuf,
+    signals: Vec<SignalDeclaration>,
+    signal_names: HashMap<String, usize>,
+    enums: EnumTables,
+    /// Scalar kind of each basic signal, as parsed from the header
+    sig_kinds: Vec<Option<ScalarKind>>,
+    big_endian: bool,
+    version: u8,
+    /// Checkpoints every `CHECKPOINT_INTERVAL` cycles, the first at the end of the header
+    checkpoints: Vec<Checkpoint>,
+    num_cycles: usize,
+    cycle_time: SimTime,
+}
+
+/// Results of reading all value changes once
+struct Scan {
+    num_cycles: usize,
+    checkpoints: Vec<Checkpoint>,
+    real_ranges: RealRanges,
+}
+
+impl GhwLoader {
+    pub fn new(filename: impl AsRef<Path>, cycle_time: SimTime) -> Result<Self> {
+        let file = File::open(filename.as_ref())?;
+        let reader = BufReader::new(file);
+        let (rd, header) = parse_header(reader)?;
+        let big_endian = rd.big_endian;
+        let version = rd.version;
+
+        let mut signals = vec![];
+        let mut enums = EnumTables::default();
+        for signal in header.signals.iter() {
+            flatten_signal(&header.types, signal.name.clone(), signal.type_id, &signal.sigs, &mut signals,
+                &mut enums);
+        }
+
+        let mut signal_names = HashMap::with_capacity(signals.len());
+        for (i, signal) in signals.iter().enumerate() {
+            signal_names.insert(signal.name.clone(), i);
+        }
+
+        let mut events = GhwEvents::new(rd, &header.sig_kinds);
+        let scan = Self::load_checkpoints(&mut events, Self::cycle_time_fs(cycle_time));
+        Self::show_real_ranges(&mut signals, &scan.real_ranges);
+
+        Ok(Self {
+            filename: filename.as_ref().into(),
+            signals,
+            signal_names,
+            enums,
+            sig_kinds: header.sig_kinds,
+            big_endian,
+            version,
+            checkpoints: scan.checkpoints,
+            num_cycles: scan.num_cycles,
+            cycle_time,
+        })
+    }
+
+    fn cycle_time_fs(cycle_time: SimTime) -> u64 {
+        std::cmp::max(cycle_time / SimTime::from_fs(1), 1)
+    }
+
+    /// Count cycles, record a checkpoint every `CHECKPOINT_INTERVAL` cycles and the range of
+    /// each real signal.
+    fn load_checkpoints<R: Read>(events: &mut GhwEvents<R>, cycle_time_fs: u64) -> Scan {
+        let mut values = vec![GhwValue::Enum(0); events.sig_kinds.len()];
+        let mut checkpoints = vec![events.checkpoint(0, 0, &values)];
+        let mut next_checkpoint = CHECKPOINT_INTERVAL;
+        let mut real_ranges = RealRanges::new();
+        let mut cur_t = 0;
+        let mut cur_cycle = 0;
+
+        // A file that is cut short, e.g. because the simulation is still running, is read up to
+        // the point where it breaks off.
+        while let Ok(Some(event)) = events.next_event() {
+            match event {
+                GhwEvent::Time(t) => {
+                    while t.saturating_sub(cur_t) >= cycle_time_fs {
+                        cur_t += cycle_time_fs;
+                        cur_cycle += 1;
+                    }
+
+                    if cur_cycle >= next_checkpoint {
+                        checkpoints.push(events.checkpoint(cur_cycle, cur_t, &values));
+                        next_checkpoint = cur_cycle + CHECKPOINT_INTERVAL;
+                    }
+                }
+
+                GhwEvent::Change(sig, value) => {
+                    if let GhwValue::Real(x) = value {
+                        let range = real_ranges.entry(sig).or_insert((x, x));
+                        range.0 = range.0.min(x);
+                        range.1 = range.1.max(x);
+                    }
+                    values[sig] = value;
+                }
+            }
+        }
+
+        Scan {
+            num_cycles: cur_cycle as usize,
+            checkpoints,
+            real_ranges,
+        }
+    }
+
+    fn show_real_ranges(signals: &mut [SignalDeclaration], real_ranges: &RealRanges) {
+        for decl in signals.iter_mut() {
+            let range = decl.sigs.first().and_then(|sig| real_ranges.get(sig));
+            if let (Encoding::Real, Some((min, max))) = (decl.encoding, range) {
+                decl.format = WaveFormat::Real(*min, *max);
+            }
+        }
+    }
+}
+
+impl QuerySource for GhwLoader {
+    type Id = String;
+    type IntoSignalIter = Vec<Signal<Self::Id>>;
+
+    fn query_signals(&self) -> Result<Self::IntoSignalIter> {
+        let rv: Self::IntoSignalIter = self
+            .signals
+            .iter()
+            .map(|decl| Signal {
+                id: decl.name.clone(),
+                name: decl.name.clone(),
+                format: decl.format,
+            })
+            .collect();
+
+        Ok(rv)
+    }
+
+    fn query_time_range(&self) -> Result<SimTimeRange> {
+        let start = SimTime::zero();
+        let stop = self.cycle_time * (self.num_cycles as u64);
+
+        Ok(SimTimeRange(start, stop))
+    }
+
+    fn query_time(&self, cycle: usize) -> SimTime {
+        self.cycle_time * (cycle as u64)
+    }
+
+    fn query_cycle_count(&self) -> usize {
+        self.num_cycles
+    }
+
+    fn query_reload(&mut self) -> Result<()> {
+        *self = Self::new(&self.filename, self.cycle_time)?;
+        Ok(())
+    }
+
+    fn query_files(&self) -> Vec<PathBuf> {
+        vec![self.filename.clone()]
+    }
+
+    fn query_enums(&self) -> EnumTables {
+        self.enums.clone()
+    }
+}
+
+impl LookupId for GhwLoader {
+    type FromId = String;
+    type ToId = usize;
+
+    fn lookup_id(&self, id: &Self::FromId) -> Result<Self::ToId> {
+        self.signal_names.get(id)
+            .copied()
+            .ok_or_else(|| Error::NotFound(id.clone()))
+    }
+
+    fn rev_lookup_id(&self, id: &Self::ToId) -> Result<Self::FromId> {
+        if *id < self.signals.len() {
+            Ok(self.signals[*id].name.clone())
+        } else {
+            Err(Error::IdOutOfRange(*id, 0..self.signals.len()))
+        }
+    }
+}
+
+impl Sample for GhwLoader {
+    type Id = String;
+    type Value = LogicValue;
+
+    fn sample(
+        &mut self,
+        ids: &Vec<Self::Id>,
+        times: &SimTimeRange,
+    ) -> Result<CycleValues<Self::Value>> {
+        let start_cycle = times.0 / self.cycle_time;
+        let stop_cycle = times.1 / self.cycle_time;
+        let cycle_time_fs = Self::cycle_time_fs(self.cycle_time);
+
+        let decls: Vec<Option<&SignalDeclaration>> = ids.iter()
+            .map(|id| self.signal_names.get(id).map(|i| &self.signals[*i]))
+            .collect();
+
+        let num_cycles = stop_cycle.saturating_sub(start_cycle) as usize;
+        let mut data = Array2::default((num_cycles, ids.len()));
+        if num_cycles == 0 {
+            return Ok(data);
+        }
+
+        // load data from file, starting at the last checkpoint before the requested range
+        let checkpoint_index = self.checkpoints
+            .partition_point(|cp| cp.cycle <= start_cycle)
+            .saturating_sub(1);
+        let checkpoint = &self.checkpoints[checkpoint_index];
+
+        let mut file = File::open(&self.filename)?;
+        file.seek(SeekFrom::Start(checkpoint.offset))?;
+        let rd = GhwReader {
+            inner: BufReader::new(file),
+            big_endian: self.big_endian,
+            version: self.version,
+            position: checkpoint.offset,
+        };
+        let mut events = GhwEvents::resume(rd, &self.sig_kinds, checkpoint);
+
+        let mut values = checkpoint.values.clone();
+        let mut cur_t = checkpoint.cycle_start;
+        let mut cur_cycle = checkpoint.cycle;
+
+        'event_loop: while let Some(event) = events.next_event()? {
+            match event {
+                GhwEvent::Time(t) => {
+                    while t.saturating_sub(cur_t) >= cycle_time_fs {
+                        if (start_cycle..stop_cycle).contains(&cur_cycle) {
+                            let mut row = data.row_mut((cur_cycle - start_cycle) as usize);
+                            for (col, decl) in decls.iter().enumerate() {
+                                if let Some(decl) = decl {
+                                    decl.assign_value(&values, &mut row[col]);
+                                }
+                            }
+                        }
+
+                        cur_t += cycle_time_fs;
+                        cur_cycle += 1;
+                    }
+
+                    if cur_cycle >= stop_cycle {
+                        // early exit when all requested data is recorded, e.g. before the end of
+                        // a file, that is still being written
+                        break 'event_loop;
+                    }
+                }
+
+                GhwEvent::Change(sig, value) => {
+                    values[sig] = value;
+                }
+            }
+        }
+
+        Ok(data)
+    }
+}
+
+impl Source<String, usize, LogicValue> for GhwLoader {}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    fn reader(bytes: &[u8]) -> GhwReader<Cursor<Vec<u8>>> {
+        GhwReader {
+            inner: Cursor::new(bytes.to_vec()),
+            big_endian: false,
+            version: 1,
+            position: 0,
+        }
+    }
+
+    #[test]
+    fn test_leb128() {
+        let mut rd = reader(&[0x02, 0xe5, 0x8e, 0x26, 0x7f, 0x80, 0x7f]);
+
+        assert_eq!(2, rd.read_uleb128().unwrap());
+        assert_eq!(624485, rd.read_uleb128().unwrap());
+        assert_eq!(-1, rd.read_sleb128().unwrap());
+        assert_eq!(-128, rd.read_sleb128().unwrap());
+    }
+
+    #[test]
+    fn test_string_table() {
+        // "clk", "clk_en" (sharing prefix "clk"), "rst"
+        let mut bytes = vec![0, 0, 0, 0, 3, 0, 0, 0, 12, 0, 0, 0];
+        bytes.extend_from_slice(b"clk\x03");
+        bytes.extend_from_slice(b"_en\x00");
+        bytes.extend_from_slice(b"rst\x00");
+        bytes.extend_from_slice(b"EOS\0");
+
+        let mut rd = reader(&bytes);
+        let strings = read_strings(&mut rd).unwrap();
+
+        assert_eq!(vec!["<anon>", "clk", "clk_en", "rst"], strings);
+    }
+
+    #[test]
+    fn test_array_index() {
+        let downto = GhwRange { downto: true, left: 7, right: 0, discrete: true };
+        let to = GhwRange { downto: false, left: 1, right: 3, discrete: true };
+
+        assert_eq!(8, downto.len());
+        assert_eq!(3, to.len());
+        assert_eq!("7", array_index(&[downto], 0));
+        assert_eq!("5", array_index(&[downto], 2));
+        assert_eq!("6,2", array_index(&[downto, to], 4));
+    }
+
+    #[test]
+    fn test_checkpoints() {
+        // short cycles, so that there are checkpoints at the first time steps after 4096 and 8192
+        let mut loader = GhwLoader::new("examples/counter.ghw", SimTime::from_ps(10)).unwrap();
+        assert_eq!(10000, loader.query_cycle_count());
+        assert_eq!(vec![0, 4500, 9000], loader.checkpoints.iter().map(|cp| cp.cycle).collect::<Vec<_>>());
+
+        let ids = vec!["tb.cnt".to_string(), "tb.level".to_string()];
+        let all = loader.sample(&ids, &SimTimeRange(SimTime::zero(), SimTime::from_ns(100))).unwrap();
+        let part = loader.sample(&ids, &SimTimeRange(SimTime::from_ps(92000), SimTime::from_ps(97000))).unwrap();
+
+        assert_eq!(all.slice(s![9200..9700, ..]), part);
+    }
+}
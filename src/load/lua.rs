@@ -0,0 +1,273 @@
+use crate::data::*;
+use crate::formatting::WaveFormat;
+use crate::error::*;
+
+use rug::Assign;
+
+use mlua::{FromLuaMulti, Function, Lua, RegistryKey, Table, ToLuaMulti, Value};
+use ndarray::prelude::*;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+#[derive(Clone)]
+struct SignalDeclaration {
+    pub name: String,
+    pub format: WaveFormat,
+}
+
+/// Loads waveforms from a source defined in a Lua script
+///
+/// The source is a Lua table with the methods `signals()`, `cycle_count()` and
+/// `sample(ids, first, last)`. `signals` returns a list of signals, each either a name of a
+/// single bit or a table with `name` and `width`. `sample` returns a row for each cycle from
+/// `first` up to, but not including `last`. Each row holds the values of the signals named in
+/// `ids`, either as integers or as strings of bits like `"10xz"`. Missing values read as 0.
+pub struct LuaLoader {
+    lua: Rc<Lua>,
+    /// The source table, kept in the registry of `lua`
+    source: RegistryKey,
+    signals: Vec<SignalDeclaration>,
+    signal_names: HashMap<String, usize>,
+    num_cycles: usize,
+    cycle_time: SimTime,
+}
+
+impl LuaLoader {
+    pub fn new(lua: Rc<Lua>, source: Table, cycle_time: SimTime) -> Result<Self> {
+        let source = lua.create_registry_value(source)?;
+        let mut loader = Self {
+            lua,
+            source,
+            signals: Vec::new(),
+            signal_names: HashMap::new(),
+            num_cycles: 0,
+            cycle_time,
+        };
+        loader.declare_signals()?;
+
+        Ok(loader)
+    }
+
+    /// Ask the source for its signals and number of cycles
+    fn declare_signals(&mut self) -> Result<()> {
+        let entries: Vec<Value> = self.call("signals", ())?;
+        let signals = entries.into_iter()
+            .map(Self::declare_signal)
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut signal_names = HashMap::with_capacity(signals.len());
+        for (i, signal) in signals.iter().enumerate() {
+            signal_names.insert(signal.name.clone(), i);
+        }
+
+        self.num_cycles = self.call("cycle_count", ())?;
+        self.signals = signals;
+        self.signal_names = signal_names;
+
+        Ok(())
+    }
+
+    /// Call method `name` of the source
+    fn call<'lua, A, R>(&'lua self, name: &str, args: A) -> Result<R>
+        where
+            A: ToLuaMulti<'lua>,
+            R: FromLuaMulti<'lua>,
+    {
+        let source: Table = self.lua.registry_value(&self.source)?;
+        let method: Function = source.get(name)?;
+        let rv = method.call((source, args))?;
+
+        Ok(rv)
+    }
+
+    fn declare_signal(entry: Value) -> Result<SignalDeclaration> {
+        let (name, width) = match entry {
+            Value::String(name) => (name.to_str()?.to_string(), 1),
+            Value::Table(signal) => {
+                let name: String = signal.get("name")?;
+                let width: Option<u32> = signal.get("width")?;
+                (name, width.unwrap_or(1))
+            }
+            other => {
+                return Err(Error::MalformedData(format!("Lua source: expected signal name or table, found {}",
+                    other.type_name())));
+            }
+        };
+
+        let format = if width == 1 {
+            WaveFormat::Bit
+        } else {
+            WaveFormat::Vector(width)
+        };
+
+        Ok(SignalDeclaration { name, format })
+    }
+
+}
+
+/// Assign a value given in Lua: an integer, a boolean or a string of bits like `"10xz"`. Nil
+/// reads as 0.
+pub(crate) fn value_from_lua(target: &mut LogicValue, value: &Value) -> Result<()> {
+    match value {
+        Value::Nil => target.assign(0),
+        Value::Boolean(bit) => target.assign(*bit as u32),
+        Value::Integer(x) => target.assign(*x),
+        Value::Number(x) if x.fract() == 0.0 => target.assign(*x as i64),
+        Value::String(bits) => {
+            target.assign_bits(bits.as_bytes().iter().map(|bit| LogicBit::from_ascii(*bit)));
+        }
+        other => {
+            return Err(Error::InvalidArgument(format!("Lua: {:?} is not a value of a signal", other)));
+        }
+    }
+
+    Ok(())
+}
+
+/// Represent a value in Lua as integer, if it is known and fits. Otherwise, as string of bits.
+pub(crate) fn value_to_lua<'lua>(lua: &'lua Lua, value: &LogicValue) -> Result<Value<'lua>> {
+    if value.is_known() && value.significant_bits() < 64 {
+        Ok(Value::Integer(value.value().to_i64_wrapping()))
+    } else {
+        Ok(Value::String(lua.create_string(&value.to_bit_string())?))
+    }
+}
+
+impl QuerySource for LuaLoader {
+    type Id = String;
+    type IntoSignalIter = Vec<Signal<Self::Id>>;
+
+    fn query_signals(&self) -> Result<Self::IntoSignalIter> {
+        let rv: Self::IntoSignalIter = self
+            .signals
+            .iter()
+            .map(|decl| Signal {
+                id: decl.name.clone(),
+                name: decl.name.clone(),
+                format: decl.format,
+            })
+            .collect();
+
+        Ok(rv)
+    }
+
+    fn query_time_range(&self) -> Result<SimTimeRange> {
+        let start = SimTime::zero();
+        let stop = self.cycle_time * (self.num_cycles as u64);
+
+        Ok(SimTimeRange(start, stop))
+    }
+
+    fn query_time(&self, cycle: usize) -> SimTime {
+        self.cycle_time * (cycle as u64)
+    }
+
+    fn query_cycle_count(&self) -> usize {
+        self.num_cycles
+    }
+
+    /// Ask the source for its signals and number of cycles again.
+    fn query_reload(&mut self) -> Result<()> {
+        self.declare_signals()
+    }
+}
+
+impl LookupId for LuaLoader {
+    type FromId = String;
+    type ToId = usize;
+
+    fn lookup_id(&self, id: &Self::FromId) -> Result<Self::ToId> {
+        self.signal_names.get(id)
+            .copied()
+            .ok_or_else(|| Error::NotFound(id.clone()))
+    }
+
+    fn rev_lookup_id(&self, id: &Self::ToId) -> Result<Self::FromId> {
+        if *id < self.signals.len() {
+            Ok(self.signals[*id].name.clone())
+        } else {
+            Err(Error::IdOutOfRange(*id, 0..self.signals.len()))
+        }
+    }
+}
+
+impl Sample for LuaLoader {
+    type Id = String;
+    type Value = LogicValue;
+
+    fn sample(
+        &mut self,
+        ids: &Vec<Self::Id>,
+        times: &SimTimeRange,
+    ) -> Result<CycleValues<Self::Value>> {
+        let start_cycle = (times.0 / self.cycle_time) as usize;
+        let stop_cycle = (times.1 / self.cycle_time) as usize;
+        let num_cycles = stop_cycle.saturating_sub(start_cycle);
+
+        let mut data = Array2::default((num_cycles, ids.len()));
+        if num_cycles == 0 || ids.is_empty() {
+            return Ok(data);
+        }
+
+        let rows: Vec<Table> = self.call("sample", (ids.clone(), start_cycle, stop_cycle))?;
+        for (mut data_row, row) in data.outer_iter_mut().zip(rows.iter()) {
+            for (i, target) in data_row.iter_mut().enumerate() {
+                let value: Value = row.get(i + 1)?;
+                value_from_lua(target, &value)?;
+            }
+        }
+
+        Ok(data)
+    }
+}
+
+impl Source<String, usize, LogicValue> for LuaLoader {}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_lua_source() {
+        let lua = Rc::new(Lua::new());
+        let source: Table = lua.load(r#"
+            return {
+                signals = function(self) return { "clk", { name = "count", width = 4 } } end,
+                cycle_count = function(self) return 10 end,
+                sample = function(self, ids, first, last)
+                    local rows = {}
+                    for cycle = first, last - 1 do
+                        local row = {}
+                        for i, id in ipairs(ids) do
+                            if id == "clk" then
+                                row[i] = cycle % 2
+                            elseif cycle == 0 then
+                                row[i] = "xxxx"
+                            else
+                                row[i] = cycle
+                            end
+                        end
+                        rows[#rows + 1] = row
+                    end
+                    return rows
+                end,
+            }
+        "#).eval().unwrap();
+
+        let mut loader = LuaLoader::new(lua.clone(), source, SimTime::from_ns(10)).unwrap();
+        assert_eq!(10, loader.query_cycle_count());
+        let signals = loader.query_signals().unwrap();
+        assert!(signals[0].format == WaveFormat::Bit);
+        assert!(signals[1].format == WaveFormat::Vector(4));
+        assert_eq!(1, loader.lookup_id(&"count".to_string()).unwrap());
+
+        let ids = vec!["count".to_string(), "clk".to_string()];
+        let data = loader.sample(&ids, &SimTimeRange(SimTime::zero(), SimTime::from_ns(30))).unwrap();
+        assert_eq!((3, 2), data.dim());
+        assert_eq!(LogicBit::X, data[[0, 0]].bit(3));
+        assert_eq!(LogicValue::from(2), data[[2, 0]]);
+        assert_eq!(LogicValue::from(1), data[[1, 1]]);
+    }
+}
@@ -0,0 +1,318 @@
+use crate::error::*;
+use crate::data::*;
+use crate::pipeline::SrcBox;
+use crate::formatting::{EnumTables, WaveFormat};
+
+use ndarray::prelude::*;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// One of the merged sources
+struct Part {
+    prefix: String,
+    source: SrcBox,
+}
+
+impl Part {
+    /// Cycle of this source, that covers `time`, if any
+    fn cycle_at(&self, time: SimTime) -> Option<usize> {
+        let count = self.source.query_cycle_count();
+        let cycle = self.source.query_cycle(time);
+
+        if cycle < count && self.source.query_time(cycle) == time {
+            Some(cycle)
+        } else if time < self.source.query_time(count) {
+            // within the cycle before
+            cycle.checked_sub(1)
+        } else {
+            None
+        }
+    }
+}
+
+struct MergedSignal {
+    name: String,
+    format: WaveFormat,
+    /// Index of the source of the signal
+    part: usize,
+    /// Id of the signal in its source
+    id: String,
+}
+
+/// Combines several sources into one wave
+///
+/// The names of the signals of each source get the prefix given with it. All sources are
+/// resampled onto the cycles of the first source: each cycle shows the cycle of the other
+/// sources, that covers the start of the cycle. Cycles outside of the time range of a source show
+/// its signals as unknown.
+pub struct MergeSource {
+    parts: Vec<Part>,
+    signals: Vec<MergedSignal>,
+    signal_names: HashMap<String, usize>,
+}
+
+impl MergeSource {
+    pub fn new(sources: Vec<(String, SrcBox)>) -> Result<Self> {
+        if sources.is_empty() {
+            return Err(Error::InvalidArgument("Merging needs at least one source".to_string()));
+        }
+
+        let parts = sources.into_iter()
+            .map(|(prefix, source)| Part { prefix, source })
+            .collect();
+        let mut rv = Self {
+            parts,
+            signals: vec![],
+            signal_names: HashMap::new(),
+        };
+
+        rv.init()?;
+        Ok(rv)
+    }
+
+    /// Collect the signals of all sources
+    fn init(&mut self) -> Result<()> {
+        let mut signals = Vec::new();
+        for (i, part) in self.parts.iter().enumerate() {
+            for signal in part.source.query_signals()? {
+                signals.push(MergedSignal {
+                    name: format!("{}{}", part.prefix, signal.name),
+                    format: signal.format,
+                    part: i,
+                    id: signal.id,
+                });
+            }
+        }
+
+        let mut signal_names = HashMap::with_capacity(signals.len());
+        for (i, signal) in signals.iter().enumerate() {
+            if signal_names.insert(signal.name.clone(), i).is_some() {
+                return Err(Error::InvalidArgument(format!("Signal '{}' found in more than one source", signal.name)));
+            }
+        }
+
+        self.signal_names = signal_names;
+        self.signals = signals;
+
+        Ok(())
+    }
+
+    /// Sample signals of part `part` for the cycles `first` up to, but not including `last`, of
+    /// the merged wave into `data`. `signals` holds the column in `data`, the id in the source
+    /// and the format of each signal.
+    fn sample_part(
+        &mut self,
+        part: usize,
+        signals: &[(usize, String, WaveFormat)],
+        first: usize,
+        last: usize,
+        data: &mut CycleValues<LogicValue>,
+    ) -> Result<()> {
+        let cycles: Vec<Option<usize>> = (first..last)
+            .map(|cycle| self.parts[part].cycle_at(self.query_time(cycle)))
+            .collect();
+        let source = &mut self.parts[part].source;
+
+        let low = cycles.iter().flatten().min().copied();
+        let high = cycles.iter().flatten().max().copied();
+        let values = match (low, high) {
+            (Some(low), Some(high)) => {
+                let ids = signals.iter().map(|(_, id, _)| id.clone()).collect();
+                let times = SimTimeRange(source.query_time(low), source.query_time(high + 1));
+                Some((low, source.sample(&ids, &times)?))
+            }
+            _ => None,
+        };
+
+        for (row, cycle) in cycles.iter().enumerate() {
+            for (i, (column, _, format)) in signals.iter().enumerate() {
+                let value = match (cycle, &values) {
+                    (Some(cycle), Some((low, values))) => values.get((cycle - low, i)).cloned(),
+                    _ => None,
+                };
+
+                data[[row, *column]] = match value {
+                    Some(value) => value,
+                    None => LogicValue::unknown(format.width().unwrap_or(1)),
+                };
+            }
+        }
+
+        Ok(())
+    }
+}
+
+
+impl QuerySource for MergeSource {
+    type Id = String;
+    type IntoSignalIter = Vec<Signal<Self::Id>>;
+
+    fn query_signals(&self) -> Result<Self::IntoSignalIter> {
+        let rv = self.signals.iter()
+            .map(|signal| Signal {
+                id: signal.name.clone(),
+                name: signal.name.clone(),
+                format: signal.format,
+            })
+            .collect();
+
+        Ok(rv)
+    }
+
+    fn query_time_range(&self) -> Result<SimTimeRange> {
+        self.parts[0].source.query_time_range()
+    }
+
+    fn query_time(&self, cycle: usize) -> SimTime {
+        self.parts[0].source.query_time(cycle)
+    }
+
+    fn query_cycle_count(&self) -> usize {
+        self.parts[0].source.query_cycle_count()
+    }
+
+    fn query_refresh(&mut self) -> Result<bool> {
+        let mut changed = false;
+        for part in self.parts.iter_mut() {
+            changed |= part.source.query_refresh()?;
+        }
+
+        Ok(changed)
+    }
+
+    fn query_reload(&mut self) -> Result<()> {
+        for part in self.parts.iter_mut() {
+            part.source.query_reload()?;
+        }
+
+        self.init()
+    }
+
+    fn query_files(&self) -> Vec<PathBuf> {
+        self.parts.iter()
+            .flat_map(|part| part.source.query_files())
+            .collect()
+    }
+
+    fn query_enums(&self) -> EnumTables {
+        let mut enums = EnumTables::default();
+        for part in self.parts.iter() {
+            enums.extend(part.source.query_enums());
+        }
+
+        enums
+    }
+}
+
+impl LookupId for MergeSource {
+    type FromId = String;
+    type ToId = usize;
+
+    fn lookup_id(&self, id: &Self::FromId) -> Result<Self::ToId> {
+        self.signal_names.get(id)
+            .copied()
+            .ok_or_else(|| Error::NotFound(id.clone()))
+    }
+
+    fn rev_lookup_id(&self, id: &Self::ToId) -> Result<Self::FromId> {
+        if *id < self.signals.len() {
+            Ok(self.signals[*id].name.clone())
+        } else {
+            Err(Error::IdOutOfRange(*id, 0..self.signals.len()))
+        }
+    }
+}
+
+impl Sample for MergeSource {
+    type Id = String;
+    type Value = LogicValue;
+
+    fn sample(
+        &mut self,
+        ids: &Vec<Self::Id>,
+        times: &SimTimeRange,
+    ) -> Result<CycleValues<Self::Value>> {
+        let first = self.query_cycle(times.0);
+        let last = self.query_cycle(times.1).max(first);
+
+        let mut part_signals: Vec<Vec<(usize, String, WaveFormat)>> = self.parts.iter()
+            .map(|_| vec![])
+            .collect();
+        for (column, id) in ids.iter().enumerate() {
+            let signal = &self.signals[self.lookup_id(id)?];
+            part_signals[signal.part].push((column, signal.id.clone(), signal.format));
+        }
+
+        let mut data = Array2::default((last - first, ids.len()));
+        for (part, signals) in part_signals.iter().enumerate() {
+            if !signals.is_empty() {
+                self.sample_part(part, signals, first, last, &mut data)?;
+            }
+        }
+
+        Ok(data)
+    }
+}
+
+impl Source<String, usize, LogicValue> for MergeSource {}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::load::vcd::VcdLoader;
+    use tempdir::TempDir;
+
+    const HEADER: &str = "$timescale 1ns $end\n\
+        $scope module tb $end\n\
+        $var wire 4 ! count $end\n\
+        $upscope $end\n\
+        $enddefinitions $end\n";
+
+    fn load(dir: &TempDir, name: &str, period: u64, body: &str) -> SrcBox {
+        let path = dir.path().join(name);
+        std::fs::write(&path, format!("{}{}", HEADER, body)).unwrap();
+
+        Box::new(VcdLoader::new(path, Some(SimTime::from_ns(period))).unwrap())
+    }
+
+    #[test]
+    fn test_merge() {
+        let dir = TempDir::new("viow-test").unwrap();
+        let core = load(&dir, "core.vcd", 10, "#0\nb0 !\n#10\nb1 !\n#20\nb10 !\n#30\nb11 !\n#40\nb100 !\n#50\n");
+        let model = load(&dir, "model.vcd", 20, "#0\nb101 !\n#20\nb110 !\n#40\n");
+
+        let mut merged = MergeSource::new(vec![
+            ("core.".to_string(), core),
+            ("model.".to_string(), model),
+        ]).unwrap();
+        assert_eq!(5, merged.query_cycle_count());
+
+        let names: Vec<String> = merged.query_signals().unwrap()
+            .into_iter()
+            .map(|signal| signal.name)
+            .collect();
+        assert_eq!(vec!["core.tb.count", "model.tb.count"], names);
+
+        let ids = vec!["model.tb.count".to_string(), "core.tb.count".to_string()];
+        let times = SimTimeRange(SimTime::from_ns(10), SimTime::from_ns(50));
+        let data = merged.sample(&ids, &times).unwrap();
+
+        assert_eq!((4, 2), data.dim());
+        let model_values: Vec<LogicValue> = data.column(0).iter().cloned().collect();
+        assert_eq!(LogicValue::from(5), model_values[0]);
+        assert_eq!(LogicValue::from(6), model_values[1]);
+        assert_eq!(LogicValue::from(6), model_values[2]);
+        // past the end of the model
+        assert!(model_values[3].has_x());
+        assert_eq!(LogicValue::from(1), data[[0, 1]]);
+        assert_eq!(LogicValue::from(4), data[[3, 1]]);
+
+        let twice = MergeSource::new(vec![
+            ("".to_string(), load(&dir, "a.vcd", 10, "#0\nb0 !\n#10\n")),
+            ("".to_string(), load(&dir, "b.vcd", 10, "#0\nb0 !\n#10\n")),
+        ]);
+        assert!(twice.is_err());
+    }
+}
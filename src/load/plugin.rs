@@ -8,26 +8,111 @@ use viow_plugin_api::{
 };
 use abi_stable::std_types::*;
 use rug::{
+    Assign,
     Integer,
     integer::Order
 };
 use ndarray::prelude::*;
 use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
 pub struct PluggedLoader {
     plugin: FiletypeLoader_Ref,
+    input: RString,
+    sampling: Sampling,
     loader: WaveLoadType,
     signals: Vec<SignalSpec>,
     signal_names: HashMap<String, usize>,
     cycle_time: SimTime,
     num_cycles: usize,
+    rows: RowGrid,
+    /// Number of cycles loaded by the plugin
+    num_rows: u64,
+}
+
+/// Rows of the plugin data, that hold the samples of the cycles
+///
+/// For sample points in between cycle boundaries, the plugin is opened with a shorter cycle
+/// time, that divides both the cycle time and the offset of the sample point. Each cycle then
+/// spans several rows, of which only one holds its sample.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct RowGrid {
+    /// Cycle time, that the plugin is opened with
+    step_ps: u64,
+    /// Number of plugin cycles per cycle
+    substeps: u64,
+    /// Row, that holds the sample of cycle 0
+    first_row: i64,
+}
+
+impl RowGrid {
+    fn new(cycle_time_ps: u64, sampling: &Sampling) -> Self {
+        let offset = sampling.offset_in(SimTime::from_ps(1));
+        let step_ps = gcd(cycle_time_ps, offset.unsigned_abs()).max(1);
+        let substeps = (cycle_time_ps / step_ps).max(1);
+        let first_row = offset / step_ps as i64 + match sampling.policy {
+            SamplePolicy::Start => 0,
+            SamplePolicy::End => substeps as i64 - 1,
+        };
+
+        Self {
+            step_ps,
+            substeps,
+            first_row,
+        }
+    }
+
+    /// Row, that holds the sample of `cycle`. Sample points outside of the `num_rows` rows of the
+    /// file use its first or last values.
+    fn row(&self, cycle: u64, num_rows: u64) -> u64 {
+        let row = self.first_row + (cycle * self.substeps) as i64;
+        row.clamp(0, num_rows.saturating_sub(1) as i64) as u64
+    }
+
+    /// Ranges of consecutive rows, that hold the samples of `cycles`. Rows of sub-steps in
+    /// between are left out.
+    fn runs(&self, cycles: Range<u64>, num_rows: u64) -> Vec<Range<u64>> {
+        let mut runs: Vec<Range<u64>> = vec![];
+
+        for cycle in cycles {
+            let row = self.row(cycle, num_rows);
+            match runs.last_mut() {
+                Some(run) if row <= run.end => run.end = row + 1,
+                _ => runs.push(row..row + 1),
+            }
+        }
+
+        runs
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
 }
 
 impl PluggedLoader {
     pub fn new(plugin: FiletypeLoader_Ref, input: impl Into<RString>, cycle_time: SimTime) -> Result<Self> {
+        Self::with_sampling(plugin, input, cycle_time, Sampling::default())
+    }
+
+    /// Load file and sample each cycle according to `sampling`.
+    ///
+    /// Plugins sample at the end of each of their cycles. For sample points in between cycle
+    /// boundaries, the plugin is opened with a shorter cycle time, see `RowGrid`.
+    pub fn with_sampling(plugin: FiletypeLoader_Ref, input: impl Into<RString>, cycle_time: SimTime, sampling: Sampling)
+        -> Result<Self>
+    {
         let cycle_time_ps = cycle_time.as_ps()
             .ok_or(Error::Internal(format!("Cycle time {cycle_time:?} to large to represent in units of ps")))?;
-        let mut loader = plugin.open()(&input.into(), cycle_time_ps).into_result()?;
+        let rows = RowGrid::new(cycle_time_ps, &sampling);
+
+        let input = input.into();
+        let mut loader = plugin.open()(&input, rows.step_ps).into_result()?;
 
         //let signals = loader.list_signal().into_result()?
             //.into_iter()
@@ -41,15 +126,20 @@ impl PluggedLoader {
             signal_names.insert(sig.name.to_string(), i);
         }
 
-        let num_cycles = loader.count_cycles().into_result()? as usize;
+        let num_rows = loader.count_cycles().into_result()?;
+        let num_cycles = (num_rows / rows.substeps) as usize;
 
         Ok(Self {
             plugin,
+            input,
+            sampling,
             loader,
             signals,
             signal_names,
             cycle_time,
             num_cycles,
+            rows,
+            num_rows,
         })
     }
 }
@@ -87,6 +177,22 @@ impl QuerySource for PluggedLoader {
     fn query_cycle_count(&self) -> usize {
         self.num_cycles
     }
+
+    fn query_reload(&mut self) -> Result<()> {
+        *self = Self::with_sampling(self.plugin.clone(), self.input.clone(), self.cycle_time, self.sampling)?;
+        Ok(())
+    }
+
+    /// The input is only watched, if it names a file.
+    fn query_files(&self) -> Vec<PathBuf> {
+        let path = Path::new(self.input.as_str());
+
+        if path.is_file() {
+            vec![path.to_path_buf()]
+        } else {
+            vec![]
+        }
+    }
 }
 
 impl LookupId for PluggedLoader {
@@ -117,7 +223,7 @@ impl LookupId for PluggedLoader {
 
 impl Sample for PluggedLoader {
     type Id = String;
-    type Value = Integer;
+    type Value = LogicValue;
 
     fn sample(
         &mut self,
@@ -127,22 +233,34 @@ impl Sample for PluggedLoader {
         let start_cycle = times.0 / self.cycle_time;
         let stop_cycle = times.1 / self.cycle_time;
 
-        // load subset
+        // convert to LogicValue
+        let num_cycles = stop_cycle.saturating_sub(start_cycle) as usize;
+        let num_signals = ids.len();
+        let mut data: Array2<LogicValue> = Array2::default((num_cycles, num_signals));
+        if num_cycles == 0 || self.num_rows == 0 {
+            return Ok(data);
+        }
+
+        // load subsets, one for each run of consecutive rows holding samples
         let rids: RVec<_> = ids.iter()
             .map(|x| RString::from(x.as_str()))
             .collect();
-        let subset = self.loader.load(&rids, Tuple2::from((start_cycle, stop_cycle)))
-            .into_result()?;
+        let mut cycle = start_cycle;
 
-        // convert to Integer
-        let num_cycles = (stop_cycle - start_cycle) as usize;
-        let num_signals = ids.len();
-        let mut data: Array2<Integer> = Array2::default((num_cycles, num_signals));
+        for run in self.rows.runs(start_cycle..stop_cycle, self.num_rows) {
+            let subset = self.loader.load(&rids, Tuple2::from((run.start, run.end)))
+                .into_result()?;
+
+            while cycle < stop_cycle && self.rows.row(cycle, self.num_rows) < run.end {
+                let subset_row = self.rows.row(cycle, self.num_rows) - run.start;
+                let mut row = data.row_mut((cycle - start_cycle) as usize);
 
-        for (row_i, mut row) in data.outer_iter_mut().enumerate() {
-            for (col_i, _) in ids.iter().enumerate() {
-                let bits = subset.get(col_i as u64, row_i as u64);
-                row[[col_i]].assign_digits(&bits, Order::Msf);
+                for (col_i, _) in ids.iter().enumerate() {
+                    let bits = subset.get(col_i as u64, subset_row);
+                    row[[col_i]].assign(Integer::from_digits(&bits, Order::Msf));
+                }
+
+                cycle += 1;
             }
         }
 
@@ -150,6 +268,48 @@ impl Sample for PluggedLoader {
     }
 }
 
-impl Source<String, usize, Integer> for PluggedLoader {}
+impl Source<String, usize, LogicValue> for PluggedLoader {}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_gcd() {
+        assert_eq!(1000, gcd(10000, 1000));
+        assert_eq!(250, gcd(10000, 250));
+        assert_eq!(10000, gcd(10000, 0));
+        assert_eq!(1, gcd(7, 3));
+    }
+
+    #[test]
+    fn test_row_grid() {
+        // sample at the end of each cycle, as the plugin does
+        let rows = RowGrid::new(10000, &Sampling::default());
+        assert_eq!(RowGrid { step_ps: 10000, substeps: 1, first_row: 0 }, rows);
+        assert_eq!(5, rows.row(5, 10));
+        assert_eq!(vec![2..5], rows.runs(2..5, 10));
+
+        // 1 ns before the end of each cycle
+        let rows = RowGrid::new(10000, &Sampling::new(SamplePolicy::End, -1, SimTimeUnit::Ns));
+        assert_eq!(RowGrid { step_ps: 1000, substeps: 10, first_row: 8 }, rows);
+        assert_eq!(8, rows.row(0, 25));
+        assert_eq!(18, rows.row(1, 25));
+        // after the end of the file
+        assert_eq!(24, rows.row(3, 25));
+        assert_eq!(vec![8..9, 18..19, 24..25], rows.runs(0..4, 25));
 
+        // 250 ps after the start of each cycle
+        let rows = RowGrid::new(10000, &Sampling::new(SamplePolicy::Start, 250, SimTimeUnit::Ps));
+        assert_eq!(RowGrid { step_ps: 250, substeps: 40, first_row: 1 }, rows);
+        assert_eq!(81, rows.row(2, 1000));
 
+        // before the start of the file
+        let rows = RowGrid::new(10000, &Sampling::new(SamplePolicy::Start, -1, SimTimeUnit::Ns));
+        assert_eq!(-1, rows.first_row);
+        assert_eq!(0, rows.row(0, 100));
+        assert_eq!(vec![0..1, 9..10], rows.runs(0..2, 100));
+    }
+}
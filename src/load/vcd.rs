@@ -1,25 +1,223 @@
+mod index;
+
 use crate::data::*;
 use crate::formatting::WaveFormat;
 use crate::error::*;
+use super::compressed;
 
 use rug::Integer;
 
-use ::vcd::{self, Header, Parser, ScopeItem, Value};
+use ::vcd::{self, Header, Parser, ScopeItem, Value, VarType};
 use ndarray::prelude::*;
-use rug::Assign;
+use rug::{Assign, integer::Order};
+use std::cell::Cell;
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufReader, Read, Seek, SeekFrom};
 use std::path::{Path,PathBuf};
+use std::rc::Rc;
+
+/// Number of cycles between two checkpoints
+const CHECKPOINT_INTERVAL: u64 = 4096;
+
+/// Number of bits used to store a real value
+const REAL_BITS: u32 = 64;
+/// Number of bits used to store the string table index of a string value
+const TEXT_BITS: u32 = 32;
+
+/// Kind of values a VCD variable holds
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum VarKind {
+    Logic,
+    /// IEEE-754 double, stored as its bit pattern
+    Real,
+    /// String, stored as index into the `StringTable`
+    Text,
+}
 
 struct SignalInfo {
     index: usize,
+    /// Number of bits used to store the value
     size: u32,
+    kind: VarKind,
+}
+
+/// Distinct values of all string variables
+///
+/// String values are stored as their index into the table, so they fit into the same bit arrays
+/// as all other values.
+#[derive(Default)]
+struct StringTable {
+    strings: Vec<String>,
+    ids: HashMap<String, u32>,
+}
+
+impl StringTable {
+    fn intern(&mut self, s: String) -> u32 {
+        if let Some(id) = self.ids.get(&s) {
+            return *id;
+        }
+
+        let id = self.strings.len() as u32;
+        self.strings.push(s.clone());
+        self.ids.insert(s, id);
+        id
+    }
+
+    fn get(&self, id: u32) -> Option<&str> {
+        self.strings.get(id as usize)
+            .map(|s| s.as_str())
+    }
+}
+
+/// Minimum and maximum of each real variable
+type RealRanges = HashMap<vcd::IdCode, (f64, f64)>;
+
+/// Edge of a clock signal, that starts a new cycle
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ClockEdge {
+    Rising,
+    Falling,
+}
+
+impl ClockEdge {
+    pub fn from_string(s: impl AsRef<str>) -> Result<Self> {
+        match s.as_ref() {
+            "rising" | "posedge" => Ok(Self::Rising),
+            "falling" | "negedge" => Ok(Self::Falling),
+            other => Err(Error::InvalidArgument(format!("'{}' is not a clock edge, use 'rising' or 'falling'", other))),
+        }
+    }
+
+    fn is_edge(self, old: Value, new: Value) -> bool {
+        match self {
+            Self::Rising => new == Value::V1 && old != Value::V1,
+            Self::Falling => new == Value::V0 && old != Value::V0,
+        }
+    }
+}
+
+/// Defines how the time in a VCD file is divided into cycles
+#[derive(Clone, Debug)]
+pub enum CycleGrid {
+    /// Cycles of fixed length. The timescale of the file is used, if no length is given.
+    Period(Option<SimTime>),
+    /// Each cycle starts at an edge of the named clock signal.
+    Clock(String, ClockEdge),
+}
+
+/// Sample point of each cycle in timescale units
+struct SampleGrid<'a> {
+    /// Start of each cycle, if cycles are defined by a clock
+    edges: Option<&'a [u64]>,
+    cycle_time_ts: u64,
+    offset_ts: i64,
+    policy: SamplePolicy,
+}
+
+impl<'a> SampleGrid<'a> {
+    fn new(edges: Option<&'a [u64]>, cycle_time: SimTime, timescale: SimTime, sampling: &Sampling) -> Self {
+        Self {
+            edges,
+            cycle_time_ts: cycle_time / timescale,
+            offset_ts: sampling.offset_in(timescale),
+            policy: sampling.policy,
+        }
+    }
+
+    /// Index of the cycle boundary, that the sample point of `cycle` is relative to
+    fn boundary(&self, cycle: u64) -> u64 {
+        match self.policy {
+            SamplePolicy::Start => cycle,
+            SamplePolicy::End => cycle + 1,
+        }
+    }
+
+    /// Sample point of `cycle`. May be before the start of the file.
+    fn point(&self, cycle: u64) -> Option<i64> {
+        let boundary = self.boundary(cycle);
+        let t = match self.edges {
+            Some(edges) => *edges.get(boundary as usize)?,
+            None => boundary * self.cycle_time_ts,
+        };
+
+        Some(t as i64 + self.offset_ts)
+    }
+
+    /// True, if changes at time `t` are no longer part of the sample of `cycle`.
+    fn is_past(&self, t: u64, cycle: u64) -> bool {
+        match (self.point(cycle), self.policy) {
+            (Some(point), SamplePolicy::Start) => t as i64 > point,
+            (Some(point), SamplePolicy::End) => t as i64 >= point,
+            (None, _) => false,
+        }
+    }
+
+    /// Some cycle at or before the first cycle, that is not past at time `t`
+    fn first_cycle(&self, t: u64) -> u64 {
+        let t = t as i64 - self.offset_ts;
+        let boundary = match self.edges {
+            Some(edges) => edges.partition_point(|edge| (*edge as i64) < t) as u64,
+            None => t.max(0) as u64 / self.cycle_time_ts.max(1),
+        };
+
+        boundary.saturating_sub(self.boundary(0) + 1)
+    }
+}
+
+/// Result of scanning through all value changes of a file
+struct Scan {
+    num_cycles: usize,
+    checkpoints: Vec<Checkpoint>,
+    real_ranges: RealRanges,
+    /// Times of clock edges in timescale units, if cycles are defined by a clock
+    edges: Option<Vec<u64>>,
+    /// State at the last timestamp, but with all values at the end of the scan. Scanning
+    /// continues from here, when data is appended to the file.
+    tail: Checkpoint,
+    next_checkpoint: u64,
+}
+
+impl Scan {
+    /// Start scanning at `offset` right after the header
+    fn new(offset: u64, width: usize, cycle_time_ts: u64, clocked: bool) -> Self {
+        let start = Checkpoint {
+            offset,
+            cycle: 0,
+            time: 0,
+            timestamp: 0,
+            cycle_time_ts,
+            values: Array1::from_elem(width, Value::X),
+        };
+
+        Self {
+            num_cycles: 0,
+            checkpoints: vec![start.clone()],
+            real_ranges: RealRanges::new(),
+            edges: clocked.then(Vec::new),
+            tail: start,
+            next_checkpoint: CHECKPOINT_INTERVAL,
+        }
+    }
+}
+
+/// Scan state of a file, that is still being written
+struct Follow {
+    /// Number of bytes scanned so far. Ends after the last complete line.
+    length: u64,
+    clock: Option<(vcd::IdCode, ClockEdge)>,
+    real_ranges: RealRanges,
+    tail: Checkpoint,
+    next_checkpoint: u64,
 }
 
 struct Subset {
     data: Array2<vcd::Value>,
     bitmap: SignalBitMap,
+    /// Cycles, in which a signal changed more than once
+    glitches: Array2<bool>,
+    /// Column of each signal in `glitches`
+    columns: HashMap<vcd::IdCode, usize>,
 }
 
 #[derive(Clone)]
@@ -28,21 +226,126 @@ struct SignalDeclaration {
     pub format: WaveFormat,
 }
 
+/// Parser state at a timestamp in the file, from which parsing can be resumed.
+#[derive(Clone)]
+struct Checkpoint {
+    /// Byte offset in file right after the timestamp
+    offset: u64,
+    cycle: u64,
+    /// Start of `cycle`
+    time: u64,
+    /// Time of the timestamp itself
+    timestamp: u64,
+    cycle_time_ts: u64,
+    /// Values of all signals before the changes following the timestamp are applied. Bits are
+    /// arranged according to `VcdLoader::checkpoint_bitmap`.
+    values: Array1<vcd::Value>,
+}
+
+/// Reader that counts the bytes read through it, to know positions while parsing.
+struct CountingReader<R> {
+    inner: R,
+    count: Rc<Cell<u64>>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.set(self.count.get() + n as u64);
+        Ok(n)
+    }
+}
+
 type SignalMap = HashMap<vcd::IdCode, SignalInfo>;
 type SignalBitMap = HashMap<vcd::IdCode, std::ops::Range<usize>>;
 type NameMap = HashMap<String, vcd::IdCode>;
 
 pub struct VcdLoader {
     filename: PathBuf,
+    /// Set, if the file is compressed
+    compressed: bool,
+    /// Decompressed content of a compressed file, which is read instead of it. Loaded from an
+    /// index, it is only created when the file is sampled for the first time.
+    decompressed: Option<compressed::Decompressed>,
     signals: Vec<SignalDeclaration>,
     num_cycles: usize,
     cycle_time: SimTime,
+    timescale: SimTime,
+    sigmap: SignalMap,
+    namemap: NameMap,
+    checkpoints: Vec<Checkpoint>,
+    checkpoint_bitmap: SignalBitMap,
+    strings: StringTable,
+    /// Start of each cycle in timescale units, if cycles are defined by a clock
+    edges: Option<Vec<u64>>,
+    sampling: Sampling,
+    /// Set, if data appended to the file is loaded by `query_refresh`
+    follow: Option<Follow>,
+    grid: CycleGrid,
+    /// Where the index of the file is kept, if any
+    index_dir: Option<PathBuf>,
+}
+
+/// Length of file up to and including its last line break
+fn complete_length(filename: &Path) -> Result<u64> {
+    const CHUNK_SIZE: u64 = 4096;
+
+    let mut file = File::open(filename)?;
+    let mut end = file.metadata()?.len();
+    let mut buf = vec![0; CHUNK_SIZE as usize];
+
+    while end > 0 {
+        let start = end.saturating_sub(CHUNK_SIZE);
+        let chunk = &mut buf[..(end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(chunk)?;
+
+        if let Some(pos) = chunk.iter().rposition(|b| *b == b'\n') {
+            return Ok(start + pos as u64 + 1);
+        }
+        end = start;
+    }
+
+    Ok(0)
 }
 
 impl VcdLoader {
     pub fn new(filename: impl AsRef<Path>, cycle_time: Option<SimTime>) -> Result<Self> {
-        let file = File::open(filename.as_ref())?;
-        let reader = BufReader::new(file);
+        Self::with_grid(filename, CycleGrid::Period(cycle_time))
+    }
+
+    /// Load file and divide it into cycles according to `grid`.
+    pub fn with_grid(filename: impl AsRef<Path>, grid: CycleGrid) -> Result<Self> {
+        Self::load(filename.as_ref(), grid, false)
+    }
+
+    /// Like `with_grid`, but for a file that is still being written, e.g. by a running
+    /// simulation.
+    ///
+    /// Only complete lines are read, as the last line may still be incomplete. Data appended
+    /// later is loaded by `query_refresh`. Compressed files can not be followed.
+    pub fn follow(filename: impl AsRef<Path>, grid: CycleGrid) -> Result<Self> {
+        if compressed::is_compressed(filename.as_ref()) {
+            return Err(Error::InvalidArgument(format!("Can not follow compressed file '{}'",
+                filename.as_ref().display())));
+        }
+
+        Self::load(filename.as_ref(), grid, true)
+    }
+
+    fn load(filename: &Path, grid: CycleGrid, follow: bool) -> Result<Self> {
+        let length = if follow {
+            Some(complete_length(filename)?)
+        } else {
+            None
+        };
+        let decompressed = compressed::decompress(filename)?;
+        let data_file = decompressed.as_ref().map_or(filename, |decompressed| decompressed.path());
+        let offset = Rc::new(Cell::new(0));
+        let reader = CountingReader {
+            inner: Self::open_range(data_file, 0, length)?,
+            count: offset.clone(),
+        };
         let mut parser = Parser::new(reader);
 
         let header = parser.parse_header()?;
@@ -50,19 +353,118 @@ impl VcdLoader {
             .timescale
             .map(|(n, ts)| Self::timescale_to_simtime(n, ts))
             .unwrap_or(SimTime::from_ps(1));
-        let (signals, _ids, _) = Self::load_all_scopes(&header);
-        let cycle_time = cycle_time.unwrap_or(timescale);
+        let (mut signals, sigmap, namemap) = Self::load_all_scopes(&header);
+
+        let (cycle_time, clock) = match grid {
+            CycleGrid::Period(cycle_time) => (cycle_time.unwrap_or(timescale), None),
+            CycleGrid::Clock(ref name, edge) => {
+                let id = namemap.get(name)
+                    .ok_or(Error::NotFound(name.clone()))?;
+                (timescale, Some((*id, edge)))
+            }
+        };
 
-        let num_cycles = Self::load_count_cycles(&mut parser, cycle_time, timescale);
+        let all_ids: Vec<vcd::IdCode> = sigmap.keys().copied().collect();
+        let (checkpoint_bitmap, width) = Self::assign_bit_positions(&sigmap, &all_ids)?;
+        let mut strings = StringTable::default();
+        let mut scan = Scan::new(offset.get(), width, cycle_time / timescale, clock.is_some());
+        Self::load_checkpoints(&mut parser, &offset, cycle_time, &checkpoint_bitmap, &mut strings,
+            clock, &mut scan);
+        Self::show_real_ranges(&mut signals, &namemap, &scan.real_ranges);
+
+        let Scan { num_cycles, checkpoints, real_ranges, edges, tail, next_checkpoint } = scan;
+        let follow = length.map(|length| Follow {
+            length,
+            clock,
+            real_ranges,
+            tail,
+            next_checkpoint,
+        });
 
         Ok(Self {
-            filename: filename.as_ref().into(),
+            filename: filename.into(),
+            compressed: decompressed.is_some(),
+            decompressed,
             signals,
             num_cycles,
             cycle_time,
+            timescale,
+            sigmap,
+            namemap,
+            checkpoints,
+            checkpoint_bitmap,
+            strings,
+            edges,
+            sampling: Sampling::default(),
+            follow,
+            grid,
+            index_dir: None,
         })
     }
 
+    /// File holding the uncompressed content. A compressed file is decompressed, unless it
+    /// already was.
+    fn data_file(&mut self) -> Result<&Path> {
+        if self.compressed && self.decompressed.is_none() {
+            self.decompressed = compressed::decompress(&self.filename)?;
+        }
+
+        Ok(self.decompressed.as_ref()
+            .map_or(&self.filename, |decompressed| decompressed.path()))
+    }
+
+    /// Open file at `offset`, reading only up to `length`, if given.
+    fn open_range(filename: &Path, offset: u64, length: Option<u64>) -> Result<Box<dyn Read>> {
+        let mut file = File::open(filename)?;
+        file.seek(SeekFrom::Start(offset))?;
+        let reader = BufReader::new(file);
+
+        match length {
+            Some(length) => Ok(Box::new(reader.take(length.saturating_sub(offset)))),
+            None => Ok(Box::new(reader)),
+        }
+    }
+
+    /// Show reals over the whole range of their values.
+    fn show_real_ranges(signals: &mut [SignalDeclaration], namemap: &NameMap, real_ranges: &RealRanges) {
+        for decl in signals.iter_mut() {
+            if let Some((min, max)) = namemap.get(&decl.name).and_then(|code| real_ranges.get(code)) {
+                decl.format = WaveFormat::Real(*min, *max);
+            }
+        }
+    }
+
+    /// Change the point within each cycle, at which values are sampled.
+    pub fn set_sampling(&mut self, sampling: Sampling) {
+        self.sampling = sampling;
+    }
+
+    /// Like `new`, but reuse the result of previous scans of the same file.
+    ///
+    /// The index is looked up in and stored to `index_dir`. Failing to store the index is not
+    /// an error. Indexes of older versions of the file are removed. A compressed file is only
+    /// decompressed, when it is sampled.
+    pub fn with_index(filename: impl AsRef<Path>, grid: CycleGrid, index_dir: impl AsRef<Path>)
+        -> Result<Self>
+    {
+        let key = index::IndexKey::new(filename.as_ref(), &grid)?;
+        let index_file = key.index_file(index_dir.as_ref());
+
+        let mut loader = match index::read_index(&index_file, &key, filename.as_ref()) {
+            Ok(Some(loader)) => loader,
+            _ => {
+                let loader = Self::with_grid(filename, grid.clone())?;
+                let _ = index::write_index(&index_file, &key, &loader);
+                index::remove_stale_indexes(index_dir.as_ref(), &key);
+                loader
+            }
+        };
+        loader.grid = grid;
+        loader.index_dir = Some(index_dir.as_ref().into());
+
+        Ok(loader)
+    }
+
     fn load_all_scopes(header: &Header) -> (Vec<SignalDeclaration>, SignalMap, NameMap) {
         let mut rv = vec![];
         let mut stack = vec![("".to_string(), &header.items)];
@@ -75,10 +477,12 @@ impl VcdLoader {
                     match item {
                         ScopeItem::Var(var) => {
                             let name = format!("{}{}", prefix, var.reference);
-                            let format = if var.size == 1 {
-                                WaveFormat::Bit
-                            } else {
-                                WaveFormat::Vector(var.size)
+                            let (format, kind, size) = match var.var_type {
+                                // range is updated once all values are known
+                                VarType::Real => (WaveFormat::Real(0.0, 1.0), VarKind::Real, REAL_BITS),
+                                VarType::String => (WaveFormat::Text, VarKind::Text, TEXT_BITS),
+                                _ if var.size == 1 => (WaveFormat::Bit, VarKind::Logic, 1),
+                                _ => (WaveFormat::Vector(var.size), VarKind::Logic, var.size),
                             };
 
                             namemap.insert(name.clone(), var.code);
@@ -86,7 +490,8 @@ impl VcdLoader {
 
                             let info = SignalInfo {
                                 index: rv.len() - 1,
-                                size: var.size,
+                                size,
+                                kind,
                             };
 
                             sigmap.insert(var.code, info);
@@ -137,19 +542,20 @@ impl VcdLoader {
         }
     }
 
-    fn map_array_to_int<'a>(target: &mut Integer, x: impl AsArray<'a, vcd::Value>) {
-        target.assign(0);
-        let ar = x.into();
-        for (i, bit) in ar.iter().enumerate() {
-            let val = match *bit {
-                Value::V1 => true,
-                _ => false,
-            };
-
-            target.set_bit((ar.len() - 1 - i) as u32, val);
+    fn map_value_to_bit(x: &Value) -> LogicBit {
+        match *x {
+            Value::V0 => LogicBit::Zero,
+            Value::V1 => LogicBit::One,
+            Value::X => LogicBit::X,
+            Value::Z => LogicBit::Z,
         }
     }
 
+    fn map_array_to_int<'a>(target: &mut LogicValue, x: impl AsArray<'a, vcd::Value>) {
+        let ar = x.into();
+        target.assign_bits(ar.iter().map(Self::map_value_to_bit));
+    }
+
     fn timescale_to_simtime(ts: u32, unit: vcd::TimescaleUnit) -> SimTime {
         use vcd::TimescaleUnit::*;
         let u = match unit {
@@ -164,14 +570,81 @@ impl VcdLoader {
         SimTime::new(ts as u64, u)
     }
 
-    fn load_count_cycles<T: std::io::Read>(
+    /// Assign vector value, that may be shorter than the signal, to its bits.
+    ///
+    /// Short values are extended to the left according to VCD rules.
+    fn assign_vector(mut target: ArrayViewMut1<vcd::Value>, v: &[vcd::Value]) {
+        let width = target.len();
+
+        if v.len() >= width {
+            target.assign(&ArrayView1::from(&v[v.len() - width..]));
+        } else {
+            let pad = match v.first() {
+                Some(Value::X) => Value::X,
+                Some(Value::Z) => Value::Z,
+                _ => Value::V0,
+            };
+            let num_pad = width - v.len();
+
+            for (i, bit) in target.iter_mut().enumerate() {
+                *bit = if i < num_pad { pad } else { v[i - num_pad] };
+            }
+        }
+    }
+
+    /// Assign number to bits, most significant bit first.
+    fn assign_number(target: ArrayViewMut1<vcd::Value>, x: u64) {
+        let width = target.len();
+
+        for (i, bit) in target.into_iter().enumerate() {
+            let pos = width - 1 - i;
+            *bit = if pos < 64 && (x >> pos) & 1 == 1 { Value::V1 } else { Value::V0 };
+        }
+    }
+
+    /// Convert bits of a string variable to the bytes of its value, first character most
+    /// significant.
+    fn map_array_to_string<'a>(target: &mut LogicValue, x: impl AsArray<'a, vcd::Value>, strings: &StringTable) {
+        Self::map_array_to_int(target, x);
+
+        if target.is_known() {
+            let text = target.value().to_u32()
+                .and_then(|id| strings.get(id))
+                .unwrap_or("");
+            target.assign(rug::Integer::from_digits(text.as_bytes(), Order::Msf));
+        }
+    }
+
+    /// Count cycles and record a checkpoint every `CHECKPOINT_INTERVAL` cycles.
+    ///
+    /// Scanning continues from the tail of `scan`, which is updated with the results. All string
+    /// values are added to `strings`, and the range of each real variable is recorded as well.
+    ///
+    /// With a `clock`, cycles start at its edges instead of multiples of `cycle_time`. As an edge
+    /// is only seen after its timestamp, a checkpoint at the time of an edge still refers to the
+    /// previous cycle.
+    #[allow(clippy::too_many_arguments)]
+    fn load_checkpoints<T: std::io::Read>(
         parser: &mut Parser<T>,
+        offset: &Cell<u64>,
         cycle_time: SimTime,
-        timescale: SimTime,
-    ) -> usize {
-        let mut cur_t = 0;
-        let mut cur_cycle = 0;
-        let mut cycle_time_ts: u64 = cycle_time / timescale;
+        bitmap: &SignalBitMap,
+        strings: &mut StringTable,
+        clock: Option<(vcd::IdCode, ClockEdge)>,
+        scan: &mut Scan,
+    ) {
+        // Changes after the last timestamp are applied again, when scanning continues. As they
+        // only assign values, this does no harm.
+        let mut cur = scan.tail.values.clone();
+        let mut cur_t = scan.tail.time;
+        let mut last_t = scan.tail.timestamp;
+        let mut last_offset = scan.tail.offset;
+        let mut cur_cycle = scan.tail.cycle;
+        let mut cycle_time_ts = scan.tail.cycle_time_ts;
+
+        // least significant bit of clock signal
+        let clock_bit = clock.and_then(|(id, _)| bitmap.get(&id))
+            .map(|bitrange| bitrange.end - 1);
 
         for command in parser {
             if command.is_err() {
@@ -179,6 +652,7 @@ impl VcdLoader {
             }
 
             let command = command.unwrap();
+            let last_clock = clock_bit.map(|bit| cur[[bit]]);
 
             use vcd::Command::*;
             match command {
@@ -188,17 +662,95 @@ impl VcdLoader {
                 }
 
                 Timestamp(t) => {
-                    while (t - cur_t) >= cycle_time_ts {
-                        cur_t += cycle_time_ts;
-                        cur_cycle += 1;
+                    if let Some(edges) = scan.edges.as_ref() {
+                        // changes before the second edge all belong to the first cycle
+                        cur_cycle = edges.len().saturating_sub(1) as u64;
+                        cur_t = edges.last().copied().unwrap_or(0);
+                    } else {
+                        while (t - cur_t) >= cycle_time_ts {
+                            cur_t += cycle_time_ts;
+                            cur_cycle += 1;
+                        }
+                    }
+                    last_t = t;
+                    last_offset = offset.get();
+
+                    if cur_cycle >= scan.next_checkpoint {
+                        scan.checkpoints.push(Checkpoint {
+                            offset: offset.get(),
+                            cycle: cur_cycle,
+                            time: cur_t,
+                            timestamp: t,
+                            cycle_time_ts,
+                            values: cur.clone(),
+                        });
+                        scan.next_checkpoint = cur_cycle + CHECKPOINT_INTERVAL;
+                    }
+                }
+
+                ChangeScalar(i, v) => {
+                    if let Some(bitrange) = bitmap.get(&i) {
+                        cur[[bitrange.start]] = v;
+                    }
+                }
+
+                ChangeVector(i, v) => {
+                    if let Some(bitrange) = bitmap.get(&i) {
+                        Self::assign_vector(cur.slice_mut(s![bitrange.clone()]), &v);
+                    }
+                }
+
+                ChangeReal(i, x) => {
+                    if let Some(bitrange) = bitmap.get(&i) {
+                        Self::assign_number(cur.slice_mut(s![bitrange.clone()]), x.to_bits());
+
+                        let range = scan.real_ranges.entry(i).or_insert((x, x));
+                        range.0 = range.0.min(x);
+                        range.1 = range.1.max(x);
+                    }
+                }
+
+                ChangeString(i, v) => {
+                    if let Some(bitrange) = bitmap.get(&i) {
+                        let id = strings.intern(v);
+                        Self::assign_number(cur.slice_mut(s![bitrange.clone()]), id as u64);
                     }
                 }
 
                 _ => (),
             }
+
+            if let (Some(edges), Some(bit), Some(last), Some((_, edge))) = (scan.edges.as_mut(), clock_bit, last_clock, clock) {
+                if edge.is_edge(last, cur[[bit]]) && edges.last() != Some(&last_t) {
+                    edges.push(last_t);
+                }
+            }
         }
 
-        cur_cycle
+        scan.num_cycles = match scan.edges.as_ref() {
+            // the cycle after the last edge is not complete
+            Some(edges) => edges.len().saturating_sub(1),
+            None => cur_cycle as usize,
+        };
+        scan.tail = Checkpoint {
+            offset: last_offset,
+            cycle: cur_cycle,
+            time: cur_t,
+            timestamp: last_t,
+            cycle_time_ts,
+            values: cur,
+        };
+    }
+
+    /// First cycle starting at or after `time`
+    fn time_to_cycle(&self, time: SimTime) -> u64 {
+        match self.edges.as_ref() {
+            Some(edges) => {
+                let t = time / self.timescale;
+                edges.partition_point(|edge| *edge < t) as u64
+            }
+            None => time / self.cycle_time,
+        }
     }
 
     // no longer used, but keeping it for now
@@ -271,6 +823,15 @@ impl VcdLoader {
         Ok((rv, ptr))
     }
 
+    /// A signal glitched, if it changed more than once between two sample points. Setting a
+    /// value, that was completely unknown, e.g. the initial value, is not counted as a change.
+    fn record_glitches(mut target: ArrayViewMut1<bool>, changes: &[u8]) {
+        for (glitch, n) in target.iter_mut().zip(changes.iter()) {
+            *glitch = *n > 1;
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn load_subset<T: std::io::Read>(
         parser: &mut Parser<T>,
         ids: &SignalMap,
@@ -278,6 +839,10 @@ impl VcdLoader {
         timescale: SimTime,
         record_ids: &[vcd::IdCode],
         record_cycles: std::ops::Range<u64>,
+        resume_from: Option<(&Checkpoint, &SignalBitMap)>,
+        strings: &mut StringTable,
+        edges: Option<&[u64]>,
+        sampling: &Sampling,
     ) -> Result<Subset> {
         // construct <cycles> x <signals> array for result data
         let (bitmap, width) = Self::assign_bit_positions(ids, record_ids)?;
@@ -285,10 +850,36 @@ impl VcdLoader {
         let mut data = Array2::from_elem((height, width), vcd::Value::X);
         let mut cur = Array1::from_elem(width, Value::X);
         let mut cur_cycle: u64 = 0;
-        let mut cur_t = 0;
-        let mut cycle_time_ts: u64 = cycle_time / timescale;
+        let mut grid = SampleGrid::new(edges, cycle_time, timescale, sampling);
 
-        'command_loop: for command in parser {
+        // number of changes of each recorded signal since the last sample point
+        let columns: HashMap<vcd::IdCode, usize> = record_ids.iter()
+            .enumerate()
+            .map(|(col, id)| (*id, col))
+            .collect();
+        let mut glitches = Array2::from_elem((height, record_ids.len()), false);
+        let mut changes = vec![0u8; record_ids.len()];
+
+        // parser is positioned at the checkpoint, continue with its state
+        if let Some((checkpoint, checkpoint_bitmap)) = resume_from {
+            for (id, bitrange) in bitmap.iter() {
+                if let Some(src) = checkpoint_bitmap.get(id) {
+                    cur.slice_mut(s![bitrange.clone()])
+                        .assign(&checkpoint.values.slice(s![src.clone()]));
+                }
+            }
+
+            grid.cycle_time_ts = checkpoint.cycle_time_ts;
+            cur_cycle = grid.first_cycle(checkpoint.timestamp);
+        }
+
+        // Sample points may be before the checkpoint and a clock edge at the timestamp of the
+        // checkpoint was not known, when the checkpoint was recorded. So, process its timestamp
+        // again.
+        let replay = resume_from
+            .map(|(checkpoint, _)| Ok(vcd::Command::Timestamp(checkpoint.timestamp)));
+
+        'command_loop: for command in replay.into_iter().chain(parser) {
             if command.is_err() {
                 continue;
             }
@@ -296,23 +887,33 @@ impl VcdLoader {
             let command = command.unwrap();
 
             use vcd::Command::*;
+
+            // keep previous value to count only actual changes
+            let changed_id = match &command {
+                ChangeScalar(i, _) | ChangeVector(i, _) | ChangeReal(i, _) | ChangeString(i, _) => Some(*i),
+                _ => None,
+            };
+            let previous = changed_id.and_then(|i| bitmap.get(&i))
+                .map(|bitrange| cur.slice(s![bitrange.clone()]).to_owned());
+
             match command {
                 Timescale(ts, unit) => {
                     let timescale = Self::timescale_to_simtime(ts, unit);
-                    cycle_time_ts = cycle_time / timescale;
+                    grid = SampleGrid::new(edges, cycle_time, timescale, sampling);
                 }
 
                 Timestamp(t) => {
-                    while (t - cur_t) >= cycle_time_ts {
+                    while grid.is_past(t, cur_cycle) {
                         if record_cycles.contains(&cur_cycle) {
                             let rel_cycle = (cur_cycle - record_cycles.start) as usize;
                             data.slice_mut(s![rel_cycle, ..]).assign(&cur);
+                            Self::record_glitches(glitches.row_mut(rel_cycle), &changes);
                         } else if cur_cycle >= record_cycles.end {
                             // early exit when all requested data is recorded
                             break 'command_loop;
                         }
 
-                        cur_t += cycle_time_ts;
+                        changes.iter_mut().for_each(|n| *n = 0);
                         cur_cycle += 1;
                     }
                 }
@@ -325,18 +926,48 @@ impl VcdLoader {
 
                 ChangeVector(i, v) => {
                     if let Some(bitrange) = bitmap.get(&i) {
-                        cur.slice_mut(s![bitrange.clone()])
-                            .assign(&Array1::from_vec(v));
+                        Self::assign_vector(cur.slice_mut(s![bitrange.clone()]), &v);
+                    }
+                }
+
+                ChangeReal(i, x) => {
+                    if let Some(bitrange) = bitmap.get(&i) {
+                        Self::assign_number(cur.slice_mut(s![bitrange.clone()]), x.to_bits());
+                    }
+                }
+
+                ChangeString(i, v) => {
+                    if let Some(bitrange) = bitmap.get(&i) {
+                        let id = strings.intern(v);
+                        Self::assign_number(cur.slice_mut(s![bitrange.clone()]), id as u64);
                     }
                 }
 
                 _ => (),
             }
+
+            if let (Some(id), Some(previous)) = (changed_id, previous) {
+                let was_unknown = previous.iter().all(|value| *value == Value::X);
+                if !was_unknown && cur.slice(s![bitmap[&id].clone()]) != previous {
+                    let n = &mut changes[columns[&id]];
+                    *n = n.saturating_add(1);
+                }
+            }
+        }
+
+        // values remain until the end of the file, when sample points are after the last change
+        for cycle in cur_cycle.max(record_cycles.start)..record_cycles.end {
+            let rel_cycle = (cycle - record_cycles.start) as usize;
+            data.slice_mut(s![rel_cycle, ..]).assign(&cur);
+            Self::record_glitches(glitches.row_mut(rel_cycle), &changes);
+            changes.iter_mut().for_each(|n| *n = 0);
         }
 
         let rv = Subset {
             data,
-            bitmap
+            bitmap,
+            glitches,
+            columns,
         };
 
         Ok(rv)
@@ -367,19 +998,85 @@ impl QuerySource for VcdLoader {
     }
 
     fn query_time_range(&self) -> Result<SimTimeRange> {
-        let start = SimTime::zero();
-        let stop = self.cycle_time * (self.num_cycles as u64);
+        let start = self.query_time(0);
+        let stop = self.query_time(self.num_cycles);
 
         Ok(SimTimeRange(start, stop))
     }
 
     fn query_time(&self, cycle: usize) -> SimTime {
-        self.cycle_time * (cycle as u64)
+        match self.edges.as_ref() {
+            Some(edges) => {
+                let edge = edges.get(cycle)
+                    .or_else(|| edges.last())
+                    .copied()
+                    .unwrap_or(0);
+                self.timescale * edge
+            }
+            None => self.cycle_time * (cycle as u64),
+        }
     }
 
     fn query_cycle_count(&self) -> usize {
         self.num_cycles
     }
+
+    fn query_refresh(&mut self) -> Result<bool> {
+        let follow = match self.follow.as_mut() {
+            Some(follow) => follow,
+            None => return Ok(false),
+        };
+
+        let length = complete_length(&self.filename)?;
+        if length <= follow.length {
+            return Ok(false);
+        }
+
+        // continue scanning at the last timestamp
+        let offset = Rc::new(Cell::new(follow.tail.offset));
+        let reader = CountingReader {
+            inner: Self::open_range(&self.filename, follow.tail.offset, Some(length))?,
+            count: offset.clone(),
+        };
+        let mut parser = Parser::new(reader);
+        let mut scan = Scan {
+            num_cycles: self.num_cycles,
+            checkpoints: std::mem::take(&mut self.checkpoints),
+            real_ranges: std::mem::take(&mut follow.real_ranges),
+            edges: self.edges.take(),
+            tail: follow.tail.clone(),
+            next_checkpoint: follow.next_checkpoint,
+        };
+        Self::load_checkpoints(&mut parser, &offset, self.cycle_time, &self.checkpoint_bitmap,
+            &mut self.strings, follow.clock, &mut scan);
+        Self::show_real_ranges(&mut self.signals, &self.namemap, &scan.real_ranges);
+
+        self.num_cycles = scan.num_cycles;
+        self.checkpoints = scan.checkpoints;
+        self.edges = scan.edges;
+        follow.length = length;
+        follow.real_ranges = scan.real_ranges;
+        follow.tail = scan.tail;
+        follow.next_checkpoint = scan.next_checkpoint;
+
+        Ok(true)
+    }
+
+    fn query_reload(&mut self) -> Result<()> {
+        let mut loader = match (&self.follow, &self.index_dir) {
+            (Some(_), _) => Self::follow(&self.filename, self.grid.clone())?,
+            (None, Some(index_dir)) => Self::with_index(&self.filename, self.grid.clone(), index_dir)?,
+            (None, None) => Self::with_grid(&self.filename, self.grid.clone())?,
+        };
+        loader.sampling = self.sampling;
+        *self = loader;
+
+        Ok(())
+    }
+
+    fn query_files(&self) -> Vec<PathBuf> {
+        vec![self.filename.clone()]
+    }
 }
 
 impl LookupId for VcdLoader {
@@ -406,28 +1103,34 @@ impl LookupId for VcdLoader {
 
 impl Sample for VcdLoader {
     type Id = String;
-    type Value = Integer;
+    type Value = LogicValue;
 
     fn sample(
         &mut self,
         ids: &Vec<Self::Id>,
         times: &SimTimeRange,
     ) -> Result<CycleValues<Self::Value>> {
-        let start_cycle = times.0 / self.cycle_time;
-        let stop_cycle = times.1 / self.cycle_time;
-
-        // load data from file
-        let file = File::open(&self.filename)?;
-        let reader = BufReader::new(file);
+        let start_cycle = self.time_to_cycle(times.0);
+        let stop_cycle = self.time_to_cycle(times.1);
+        let data_file = self.data_file()?.to_path_buf();
+        let namemap = &self.namemap;
+
+        // Load data from file, starting at the last checkpoint before the requested range. To
+        // see all changes in the first cycle, start before the sample point of the cycle before.
+        let grid = SampleGrid::new(self.edges.as_deref(), self.cycle_time, self.timescale, &self.sampling);
+        let first_point = match start_cycle {
+            0 => Some(i64::MIN),
+            _ => grid.point(start_cycle - 1),
+        }.unwrap_or(i64::MAX);
+        let checkpoint_index = self.checkpoints
+            .partition_point(|cp| cp.timestamp as i64 <= first_point)
+            .saturating_sub(1);
+        let checkpoint = &self.checkpoints[checkpoint_index];
+
+        let length = self.follow.as_ref().map(|follow| follow.length);
+        let reader = Self::open_range(&data_file, checkpoint.offset, length)?;
         let mut parser = Parser::new(reader);
 
-        let header = parser.parse_header()?;
-        let timescale = header
-            .timescale
-            .map(|(n, ts)| Self::timescale_to_simtime(n, ts))
-            .unwrap_or(SimTime::from_ps(1));
-        let (_signals, info, namemap) = Self::load_all_scopes(&header);
-
         // translate to VCD Ids
         let record_ids: Vec<vcd::IdCode> = ids.iter()
             .filter_map(|id| {
@@ -437,10 +1140,19 @@ impl Sample for VcdLoader {
             .collect();
 
         // load subset
-        let subset = Self::load_subset(&mut parser, &info, self.cycle_time, timescale, &record_ids,
-            start_cycle..stop_cycle)?;
+        let subset = Self::load_subset(&mut parser, &self.sigmap, self.cycle_time, self.timescale,
+            &record_ids, start_cycle..stop_cycle, Some((checkpoint, &self.checkpoint_bitmap)),
+            &mut self.strings, self.edges.as_deref(), &self.sampling)?;
+
+        // A clock running at a fixed cycle period changes twice in each cycle, just like a
+        // glitch. So glitches are only flagged in cycles defined by a clock, except for the
+        // clock itself.
+        let clock = match &self.grid {
+            CycleGrid::Clock(name, _) => Some(namemap.get(name)),
+            CycleGrid::Period(_) => None,
+        };
 
-        // convert to Integer
+        // convert to LogicValue
         let num_cycles = (stop_cycle - start_cycle) as usize;
         let num_signals = ids.len();
         let mut data = Array2::default((num_cycles, num_signals));
@@ -451,7 +1163,14 @@ impl Sample for VcdLoader {
                     let bitrange = subset.bitmap.get(idcode)
                         .ok_or(Error::Internal(format!("Could not find bit position of VCD IdCode '{}'", idcode)))?;
                     let bits = subset.data.slice(s![row_i, bitrange.clone()]);
-                    Self::map_array_to_int(&mut row[col_i], bits);
+
+                    match self.sigmap[idcode].kind {
+                        VarKind::Text => Self::map_array_to_string(&mut row[col_i], bits, &self.strings),
+                        _ => Self::map_array_to_int(&mut row[col_i], bits),
+                    }
+                    if clock.is_some_and(|clock| clock != Some(idcode)) {
+                        row[col_i].set_glitch(subset.glitches[[row_i, subset.columns[idcode]]]);
+                    }
                 }
             }
         }
@@ -460,7 +1179,7 @@ impl Sample for VcdLoader {
     }
 }
 
-impl Source<String, usize, Integer> for VcdLoader {}
+impl Source<String, usize, LogicValue> for VcdLoader {}
 
 
 
@@ -497,7 +1216,7 @@ mod test {
         let cycle_time = SimTime::from_ps(1);
 
         let subset = VcdLoader::load_subset(&mut parser,&info, cycle_time, timescale, &record_ids,
-            record_cycles).unwrap();
+            record_cycles, None, &mut StringTable::default(), None, &Sampling::default()).unwrap();
 
         println!("subset:\n{:?}", subset.data);
 
@@ -543,7 +1262,7 @@ mod test {
         let cycle_time = SimTime::from_ps(100);
 
         let subset = VcdLoader::load_subset(&mut parser,&info, cycle_time, timescale, &record_ids,
-            record_cycles).unwrap();
+            record_cycles, None, &mut StringTable::default(), None, &Sampling::default()).unwrap();
 
         println!("subset:\n{:?}", subset.data);
 
@@ -566,4 +1285,263 @@ mod test {
         assert_eq!(Value::V1, subset.data[[11, 2]]);
         assert_eq!(Value::V0, subset.data[[11, 3]]);
     }
+
+    #[test]
+    fn test_resume_from_checkpoint() {
+        const FILENAME: &'static str = "examples/core.vcd";
+        let cycle_time = SimTime::from_ps(1);
+
+        let mut loader = VcdLoader::new(FILENAME, Some(cycle_time)).unwrap();
+        assert_eq!(10000, loader.num_cycles);
+        assert_eq!(3, loader.checkpoints.len());
+        assert_eq!(8200, loader.checkpoints[2].cycle);
+
+        let ids = vec![
+            "tb_core.clk".to_string(),
+            "tb_core.reset".to_string(),
+            "tb_core.uut.ifu.i0_pass_q[0:1]".to_string(),
+        ];
+        let resumed = loader.sample(&ids,
+            &SimTimeRange(SimTime::from_ps(8150), SimTime::from_ps(8350))).unwrap();
+
+        // compare with data parsed from the start of the file
+        let file = File::open(Path::new(FILENAME)).unwrap();
+        let reader = BufReader::new(file);
+        let mut parser = Parser::new(reader);
+        parser.parse_header().unwrap();
+        let record_ids: Vec<vcd::IdCode> = ids.iter()
+            .map(|id| loader.namemap[id])
+            .collect();
+        let subset = VcdLoader::load_subset(&mut parser, &loader.sigmap, cycle_time, loader.timescale,
+            &record_ids, 8150..8350, None, &mut StringTable::default(), None, &Sampling::default()).unwrap();
+
+        for (row_i, row) in resumed.outer_iter().enumerate() {
+            for (col_i, id) in record_ids.iter().enumerate() {
+                let bitrange = subset.bitmap[id].clone();
+                let mut expected = LogicValue::new();
+                VcdLoader::map_array_to_int(&mut expected, subset.data.slice(s![row_i, bitrange]));
+                assert_eq!(expected, row[col_i]);
+            }
+        }
+        assert_eq!(LogicValue::from(1), resumed[[0, 2]]);
+        assert_eq!(LogicValue::from(1), resumed[[199, 0]]);
+    }
+
+    #[test]
+    fn test_real_and_string() {
+        use crate::formatting::{format_value, EnumTables};
+
+        let mut loader = VcdLoader::new("examples/mixed.vcd", Some(SimTime::from_ns(10))).unwrap();
+        assert_eq!(3, loader.query_cycle_count());
+
+        let signals = loader.query_signals().unwrap();
+        assert!(signals[1].format == WaveFormat::Real(-0.25, 1.5));
+        assert!(signals[2].format == WaveFormat::Text);
+
+        let ids = vec!["tb.vout".to_string(), "tb.state".to_string()];
+        let data = loader.sample(&ids, &SimTimeRange(SimTime::zero(), SimTime::from_ns(30))).unwrap();
+
+        assert_eq!(0.0, data[[0, 0]].to_real());
+        assert_eq!(1.5, data[[1, 0]].to_real());
+        assert_eq!(-0.25, data[[2, 0]].to_real());
+        let enums = EnumTables::default();
+        assert_eq!("\"IDLE\"", format_value(&data[[0, 1]], WaveFormat::Text, &enums));
+        assert_eq!("\"BUSY\"", format_value(&data[[1, 1]], WaveFormat::Text, &enums));
+        assert_eq!("\"IDLE\"", format_value(&data[[2, 1]], WaveFormat::Text, &enums));
+    }
+
+    #[test]
+    fn test_clock_edges() {
+        let grid = CycleGrid::Clock("top.clk".to_string(), ClockEdge::Rising);
+        let mut loader = VcdLoader::with_grid("examples/verilator.vcd", grid).unwrap();
+
+        assert_eq!(105, loader.query_cycle_count());
+        assert_eq!(1, loader.query_time(0) / SimTime::from_ps(1));
+        assert_eq!(3, loader.query_time(1) / SimTime::from_ps(1));
+        assert_eq!(211, loader.query_time(105) / SimTime::from_ps(1));
+
+        let ids = vec!["top.clk".to_string(), "top.cnt".to_string()];
+        let times = SimTimeRange(loader.query_time(0), loader.query_time(2));
+        let data = loader.sample(&ids, &times).unwrap();
+
+        assert_eq!(2, data.nrows());
+        assert_eq!(LogicValue::from(0), data[[0, 0]]);
+        assert_eq!(LogicValue::from(0x8378), data[[0, 1]]);
+        assert_eq!(LogicValue::from(0), data[[1, 0]]);
+        assert_eq!(LogicValue::from(0), data[[1, 1]]);
+
+        let grid = CycleGrid::Clock("top.nonexistent".to_string(), ClockEdge::Rising);
+        assert!(VcdLoader::with_grid("examples/verilator.vcd", grid).is_err());
+    }
+
+    #[test]
+    fn test_sampling() {
+        let mut loader = VcdLoader::new("examples/mixed.vcd", Some(SimTime::from_ns(10))).unwrap();
+        let ids = vec!["tb.clk".to_string(), "tb.vout".to_string()];
+        let times = SimTimeRange(SimTime::zero(), SimTime::from_ns(30));
+
+        let data = loader.sample(&ids, &times).unwrap();
+        assert_eq!(LogicValue::from(1), data[[1, 0]]);
+        assert_eq!(1.5, data[[1, 1]].to_real());
+
+        loader.set_sampling(Sampling::new(SamplePolicy::Start, 0, SimTimeUnit::Ns));
+        let data = loader.sample(&ids, &times).unwrap();
+        assert_eq!(LogicValue::from(0), data[[1, 0]]);
+        assert_eq!(1.5, data[[1, 1]].to_real());
+
+        // 1 ns before each cycle starts
+        loader.set_sampling(Sampling::new(SamplePolicy::Start, -1, SimTimeUnit::Ns));
+        let data = loader.sample(&ids, &times).unwrap();
+        assert!(data[[0, 0]].has_x());
+        assert_eq!(LogicValue::from(1), data[[1, 0]]);
+        assert_eq!(0.0, data[[1, 1]].to_real());
+        assert_eq!(1.5, data[[2, 1]].to_real());
+
+        // last sample point is after the end of the file
+        loader.set_sampling(Sampling::new(SamplePolicy::End, 5, SimTimeUnit::Ns));
+        let data = loader.sample(&ids, &times).unwrap();
+        assert_eq!(1.5, data[[0, 1]].to_real());
+        assert_eq!(-0.25, data[[1, 1]].to_real());
+        assert_eq!(-0.25, data[[2, 1]].to_real());
+    }
+
+    #[test]
+    fn test_glitches() {
+        use tempdir::TempDir;
+
+        let dir = TempDir::new("viow-test").unwrap();
+        let vcd = "$timescale 1ns $end\n\
+            $scope module tb $end\n\
+            $var wire 1 ! clk $end\n\
+            $var wire 1 \" irq $end\n\
+            $var wire 4 # cnt $end\n\
+            $upscope $end\n\
+            $enddefinitions $end\n\
+            #0\n0!\n0\"\nb0 #\n#5\n1!\nb1 #\n#10\n0!\n#12\n1\"\n#13\n0\"\n#15\n1!\nb10 #\n\
+            #20\n0!\n#25\n1!\nb11 #\n#27\nb100 #\n#28\nb101 #\n#30\n0!\n#35\n1!\n#40\n0!\n";
+        let path = dir.path().join("glitches.vcd");
+        std::fs::write(&path, vcd).unwrap();
+        let ids = vec!["tb.clk".to_string(), "tb.irq".to_string(), "tb.cnt".to_string()];
+
+        // the pulse of irq and the changes of cnt in the last cycle are glitches. The clock
+        // defining the cycles does not glitch.
+        let grid = CycleGrid::Clock("tb.clk".to_string(), ClockEdge::Rising);
+        let mut loader = VcdLoader::with_grid(&path, grid).unwrap();
+        assert_eq!(3, loader.query_cycle_count());
+        let times = SimTimeRange(loader.query_time(0), loader.query_time(3));
+        let data = loader.sample(&ids, &times).unwrap();
+        let glitches: Vec<Vec<bool>> = data.outer_iter()
+            .map(|row| row.iter().map(|value| value.has_glitch()).collect())
+            .collect();
+        assert_eq!(vec![
+            vec![false, true, false],
+            vec![false, false, false],
+            vec![false, false, true],
+        ], glitches);
+
+        // all changes within a cycle are seen, when sampling starts in the middle of the file
+        let data = loader.sample(&ids, &SimTimeRange(loader.query_time(2), loader.query_time(3))).unwrap();
+        assert!(data[[0, 2]].has_glitch());
+
+        // with a fixed cycle time, the clock changes twice in each cycle just like a glitch, so
+        // nothing is flagged
+        let mut loader = VcdLoader::new(&path, Some(SimTime::from_ns(10))).unwrap();
+        let data = loader.sample(&ids, &SimTimeRange(SimTime::zero(), SimTime::from_ns(40))).unwrap();
+        assert!(data.iter().all(|value| !value.has_glitch()));
+    }
+
+    #[test]
+    fn test_follow() {
+        use std::io::Write;
+        use tempdir::TempDir;
+
+        let content = std::fs::read_to_string("examples/mixed.vcd").unwrap();
+        let split = content.find("#20").unwrap() + 2;
+        let dir = TempDir::new("viow-test").unwrap();
+        let filename = dir.path().join("growing.vcd");
+
+        // last line is still incomplete
+        std::fs::write(&filename, &content[..split]).unwrap();
+        let mut loader = VcdLoader::follow(&filename, CycleGrid::Period(Some(SimTime::from_ns(10)))).unwrap();
+        assert_eq!(1, loader.query_cycle_count());
+        assert!(!loader.query_refresh().unwrap());
+        assert!(loader.query_signals().unwrap()[1].format == WaveFormat::Real(0.0, 1.5));
+
+        let mut file = std::fs::OpenOptions::new().append(true).open(&filename).unwrap();
+        file.write_all(content[split..].as_bytes()).unwrap();
+        assert!(loader.query_refresh().unwrap());
+        assert_eq!(3, loader.query_cycle_count());
+        assert!(loader.query_signals().unwrap()[1].format == WaveFormat::Real(-0.25, 1.5));
+
+        let mut complete = VcdLoader::new("examples/mixed.vcd", Some(SimTime::from_ns(10))).unwrap();
+        let ids = vec!["tb.clk".to_string(), "tb.vout".to_string(), "tb.state".to_string()];
+        let times = SimTimeRange(SimTime::zero(), SimTime::from_ns(30));
+        assert_eq!(complete.sample(&ids, &times).unwrap(), loader.sample(&ids, &times).unwrap());
+    }
+
+    #[test]
+    fn test_reload() {
+        use tempdir::TempDir;
+
+        let content = std::fs::read_to_string("examples/mixed.vcd").unwrap();
+        let dir = TempDir::new("viow-test").unwrap();
+        let filename = dir.path().join("rerun.vcd");
+        std::fs::write(&filename, &content).unwrap();
+
+        let mut loader = VcdLoader::new(&filename, Some(SimTime::from_ns(10))).unwrap();
+        loader.set_sampling(Sampling::new(SamplePolicy::Start, 0, SimTimeUnit::Ns));
+        assert_eq!(3, loader.query_cycle_count());
+
+        // new run ends earlier
+        let end = content.find("#25").unwrap();
+        std::fs::write(&filename, &content[..end]).unwrap();
+        loader.query_reload().unwrap();
+        assert_eq!(2, loader.query_cycle_count());
+
+        let ids = vec!["tb.clk".to_string()];
+        let data = loader.sample(&ids, &SimTimeRange(SimTime::zero(), SimTime::from_ns(20))).unwrap();
+        assert_eq!(LogicValue::from(0), data[[1, 0]]);
+    }
+
+    #[test]
+    fn test_resume_with_offset() {
+        const FILENAME: &'static str = "examples/core.vcd";
+        let cycle_time = SimTime::from_ps(1);
+        let mut loader = VcdLoader::new(FILENAME, Some(cycle_time)).unwrap();
+
+        let ids = vec![
+            "tb_core.clk".to_string(),
+            "tb_core.uut.ifu.i0_pass_q[0:1]".to_string(),
+        ];
+        let record_ids: Vec<vcd::IdCode> = ids.iter()
+            .map(|id| loader.namemap[id])
+            .collect();
+
+        for sampling in [
+            Sampling::new(SamplePolicy::Start, -100, SimTimeUnit::Ps),
+            Sampling::new(SamplePolicy::End, 100, SimTimeUnit::Ps),
+            Sampling::new(SamplePolicy::Start, 0, SimTimeUnit::Ps),
+        ] {
+            loader.set_sampling(sampling);
+            let resumed = loader.sample(&ids,
+                &SimTimeRange(SimTime::from_ps(8150), SimTime::from_ps(8350))).unwrap();
+
+            let file = File::open(Path::new(FILENAME)).unwrap();
+            let mut parser = Parser::new(BufReader::new(file));
+            parser.parse_header().unwrap();
+            let subset = VcdLoader::load_subset(&mut parser, &loader.sigmap, cycle_time, loader.timescale,
+                &record_ids, 8150..8350, None, &mut StringTable::default(), None, &sampling).unwrap();
+
+            for (row_i, row) in resumed.outer_iter().enumerate() {
+                for (col_i, id) in record_ids.iter().enumerate() {
+                    let bitrange = subset.bitmap[id].clone();
+                    let mut expected = LogicValue::new();
+                    VcdLoader::map_array_to_int(&mut expected, subset.data.slice(s![row_i, bitrange]));
+                    assert_eq!(expected, row[col_i], "{:?}, cycle {}", sampling, row_i);
+                    // no glitches without a clock defining the cycles
+                    assert!(!row[col_i].has_glitch());
+                }
+            }
+        }
+    }
 }
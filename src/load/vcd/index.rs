@@ -0,0 +1,599 @@
+//! Persistent index of VCD files
+//!
+//! Scanning a large VCD file for its cycle count and checkpoints takes a long time. The result
+//! of the scan is stored in an index file, so reopening an unchanged file does not need to
+//! parse it again. An index is only valid for the exact file (path, size and modification time)
+//! and cycle grid it was created for. The index of a compressed file is read without
+//! decompressing the file, which is only needed to sample it.
+
+use super::*;
+
+use std::ffi::OsStr;
+use std::io::{BufWriter, Write};
+use std::time::UNIX_EPOCH;
+
+const INDEX_MAGIC: &[u8] = b"VIOW-VCD-INDEX\x03";
+
+/// Identifies the file and settings, that an index was created for.
+#[derive(PartialEq)]
+pub(super) struct IndexKey {
+    path: String,
+    size: u64,
+    mtime_secs: u64,
+    mtime_nanos: u32,
+    /// Requested cycle time in fs or 0 if the timescale of the file is used.
+    cycle_time_fs: u64,
+    /// Edge and name of the clock signal defining the cycles, or empty for a fixed cycle time
+    clock: String,
+}
+
+impl IndexKey {
+    pub(super) fn new(filename: &Path, grid: &CycleGrid) -> Result<Self> {
+        let path = filename.canonicalize()?
+            .to_string_lossy()
+            .into_owned();
+        let meta = std::fs::metadata(filename)?;
+        let mtime = meta.modified()?
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let (cycle_time_fs, clock) = match grid {
+            CycleGrid::Period(cycle_time) => {
+                let cycle_time_fs = cycle_time
+                    .map(|t| t / SimTime::from_fs(1))
+                    .unwrap_or(0);
+                (cycle_time_fs, String::new())
+            }
+            CycleGrid::Clock(name, edge) => (0, format!("{:?}:{}", edge, name)),
+        };
+
+        Ok(Self {
+            path,
+            size: meta.len(),
+            mtime_secs: mtime.as_secs(),
+            mtime_nanos: mtime.subsec_nanos(),
+            cycle_time_fs,
+            clock,
+        })
+    }
+
+    /// Location of the index file within `index_dir`
+    pub(super) fn index_file(&self, index_dir: &Path) -> PathBuf {
+        let mut bytes = self.path.as_bytes().to_vec();
+        bytes.push(0);
+        bytes.extend_from_slice(&self.cycle_time_fs.to_le_bytes());
+        bytes.extend_from_slice(self.clock.as_bytes());
+
+        index_dir.join(format!("{:016x}.vcdidx", fnv1a(&bytes)))
+    }
+
+    /// Read the key of an existing index file. Returns `None` for a file of another format.
+    fn read_file(index_file: &Path) -> Result<Option<Self>> {
+        let mut r = BufReader::new(File::open(index_file)?);
+
+        let mut magic = vec![0u8; INDEX_MAGIC.len()];
+        r.read_exact(&mut magic)?;
+        if magic != INDEX_MAGIC {
+            return Ok(None);
+        }
+
+        Ok(Some(Self::read(&mut r)?))
+    }
+
+    fn write(&self, w: &mut impl Write) -> Result<()> {
+        write_str(w, &self.path)?;
+        write_u64(w, self.size)?;
+        write_u64(w, self.mtime_secs)?;
+        write_u32(w, self.mtime_nanos)?;
+        write_u64(w, self.cycle_time_fs)?;
+        write_str(w, &self.clock)
+    }
+
+    fn read(r: &mut impl Read) -> Result<Self> {
+        Ok(Self {
+            path: read_str(r)?,
+            size: read_u64(r)?,
+            mtime_secs: read_u64(r)?,
+            mtime_nanos: read_u32(r)?,
+            cycle_time_fs: read_u64(r)?,
+            clock: read_str(r)?,
+        })
+    }
+}
+
+/// 64 bit FNV-1a hash. Unlike `DefaultHasher`, it is the same for every build, so an index is
+/// found again after viow is updated.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// Remove the indexes in `index_dir`, that were created for an older version of the file of
+/// `key`, or for the same settings under another name. Errors are ignored.
+pub(super) fn remove_stale_indexes(index_dir: &Path, key: &IndexKey) {
+    let entries = match std::fs::read_dir(index_dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    let current = key.index_file(index_dir);
+
+    for index_file in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+        if index_file == current || index_file.extension() != Some(OsStr::new("vcdidx")) {
+            continue;
+        }
+
+        let stale = match IndexKey::read_file(&index_file) {
+            Ok(Some(other)) if other.path == key.path => {
+                (other.size, other.mtime_secs, other.mtime_nanos) != (key.size, key.mtime_secs, key.mtime_nanos)
+                    || (other.cycle_time_fs == key.cycle_time_fs && other.clock == key.clock)
+            }
+            _ => false,
+        };
+        if stale {
+            let _ = std::fs::remove_file(&index_file);
+        }
+    }
+}
+
+/// Load loader state from index file. Returns `None` if the index does not exist or is stale.
+pub(super) fn read_index(index_file: &Path, key: &IndexKey, filename: &Path) -> Result<Option<VcdLoader>> {
+    let file = match File::open(index_file) {
+        Ok(file) => file,
+        Err(_) => return Ok(None),
+    };
+    let mut r = BufReader::new(file);
+
+    let mut magic = vec![0u8; INDEX_MAGIC.len()];
+    r.read_exact(&mut magic)?;
+    if magic != INDEX_MAGIC || IndexKey::read(&mut r)? != *key {
+        return Ok(None);
+    }
+
+    let cycle_time = read_simtime(&mut r)?;
+    let timescale = read_simtime(&mut r)?;
+    let num_cycles = read_u64(&mut r)? as usize;
+
+    let num_signals = read_u64(&mut r)? as usize;
+    let mut signals = Vec::with_capacity(num_signals);
+    for _ in 0..num_signals {
+        let name = read_str(&mut r)?;
+        let format = read_format(&mut r)?;
+        signals.push(SignalDeclaration { name, format });
+    }
+
+    let num_entries = read_u64(&mut r)? as usize;
+    let mut sigmap = SignalMap::with_capacity(num_entries);
+    for _ in 0..num_entries {
+        let code = read_idcode(&mut r)?;
+        let index = read_u64(&mut r)? as usize;
+        let size = read_u32(&mut r)?;
+        let kind = read_kind(&mut r)?;
+        sigmap.insert(code, SignalInfo { index, size, kind });
+    }
+
+    let num_entries = read_u64(&mut r)? as usize;
+    let mut namemap = NameMap::with_capacity(num_entries);
+    for _ in 0..num_entries {
+        let name = read_str(&mut r)?;
+        let code = read_idcode(&mut r)?;
+        namemap.insert(name, code);
+    }
+
+    let num_strings = read_u64(&mut r)? as usize;
+    let mut strings = StringTable::default();
+    for _ in 0..num_strings {
+        strings.intern(read_str(&mut r)?);
+    }
+
+    let num_entries = read_u64(&mut r)? as usize;
+    let mut checkpoint_bitmap = SignalBitMap::with_capacity(num_entries);
+    for _ in 0..num_entries {
+        let code = read_idcode(&mut r)?;
+        let start = read_u64(&mut r)? as usize;
+        let end = read_u64(&mut r)? as usize;
+        checkpoint_bitmap.insert(code, start..end);
+    }
+
+    let num_checkpoints = read_u64(&mut r)? as usize;
+    let mut checkpoints = Vec::with_capacity(num_checkpoints);
+    for _ in 0..num_checkpoints {
+        let offset = read_u64(&mut r)?;
+        let cycle = read_u64(&mut r)?;
+        let time = read_u64(&mut r)?;
+        let timestamp = read_u64(&mut r)?;
+        let cycle_time_ts = read_u64(&mut r)?;
+        let mut bytes = vec![0u8; read_u64(&mut r)? as usize];
+        r.read_exact(&mut bytes)?;
+        let values = bytes.iter()
+            .map(|b| byte_to_value(*b))
+            .collect::<Result<Array1<_>>>()?;
+
+        checkpoints.push(Checkpoint { offset, cycle, time, timestamp, cycle_time_ts, values });
+    }
+
+    let edges = if read_u8(&mut r)? != 0 {
+        let num_edges = read_u64(&mut r)? as usize;
+        let mut edges = Vec::with_capacity(num_edges);
+        for _ in 0..num_edges {
+            edges.push(read_u64(&mut r)?);
+        }
+        Some(edges)
+    } else {
+        None
+    };
+
+    Ok(Some(VcdLoader {
+        filename: filename.into(),
+        compressed: compressed::is_compressed(filename),
+        decompressed: None,
+        signals,
+        num_cycles,
+        cycle_time,
+        timescale,
+        sigmap,
+        namemap,
+        checkpoints,
+        checkpoint_bitmap,
+        strings,
+        edges,
+        sampling: Sampling::default(),
+        follow: None,
+        grid: CycleGrid::Period(Some(cycle_time)),
+        index_dir: None,
+    }))
+}
+
+/// Store loader state in index file
+pub(super) fn write_index(index_file: &Path, key: &IndexKey, loader: &VcdLoader) -> Result<()> {
+    // write to temporary file first, so no partially written index is ever read
+    let tmp_file = index_file.with_extension("tmp");
+    let mut w = BufWriter::new(File::create(&tmp_file)?);
+
+    w.write_all(INDEX_MAGIC)?;
+    key.write(&mut w)?;
+    write_simtime(&mut w, loader.cycle_time)?;
+    write_simtime(&mut w, loader.timescale)?;
+    write_u64(&mut w, loader.num_cycles as u64)?;
+
+    write_u64(&mut w, loader.signals.len() as u64)?;
+    for decl in loader.signals.iter() {
+        write_str(&mut w, &decl.name)?;
+        write_format(&mut w, decl.format)?;
+    }
+
+    write_u64(&mut w, loader.sigmap.len() as u64)?;
+    for (code, info) in loader.sigmap.iter() {
+        write_str(&mut w, &code.to_string())?;
+        write_u64(&mut w, info.index as u64)?;
+        write_u32(&mut w, info.size)?;
+        write_kind(&mut w, info.kind)?;
+    }
+
+    write_u64(&mut w, loader.namemap.len() as u64)?;
+    for (name, code) in loader.namemap.iter() {
+        write_str(&mut w, name)?;
+        write_str(&mut w, &code.to_string())?;
+    }
+
+    write_u64(&mut w, loader.strings.strings.len() as u64)?;
+    for s in loader.strings.strings.iter() {
+        write_str(&mut w, s)?;
+    }
+
+    write_u64(&mut w, loader.checkpoint_bitmap.len() as u64)?;
+    for (code, bitrange) in loader.checkpoint_bitmap.iter() {
+        write_str(&mut w, &code.to_string())?;
+        write_u64(&mut w, bitrange.start as u64)?;
+        write_u64(&mut w, bitrange.end as u64)?;
+    }
+
+    write_u64(&mut w, loader.checkpoints.len() as u64)?;
+    for checkpoint in loader.checkpoints.iter() {
+        write_u64(&mut w, checkpoint.offset)?;
+        write_u64(&mut w, checkpoint.cycle)?;
+        write_u64(&mut w, checkpoint.time)?;
+        write_u64(&mut w, checkpoint.timestamp)?;
+        write_u64(&mut w, checkpoint.cycle_time_ts)?;
+        let bytes: Vec<u8> = checkpoint.values.iter()
+            .map(value_to_byte)
+            .collect();
+        write_u64(&mut w, bytes.len() as u64)?;
+        w.write_all(&bytes)?;
+    }
+
+    if let Some(edges) = loader.edges.as_ref() {
+        w.write_all(&[1])?;
+        write_u64(&mut w, edges.len() as u64)?;
+        for edge in edges.iter() {
+            write_u64(&mut w, *edge)?;
+        }
+    } else {
+        w.write_all(&[0])?;
+    }
+
+    w.flush()?;
+    drop(w);
+    std::fs::rename(&tmp_file, index_file)?;
+
+    Ok(())
+}
+
+
+//
+// Encoding of values
+//
+
+fn write_u32(w: &mut impl Write, x: u32) -> Result<()> {
+    w.write_all(&x.to_le_bytes())?;
+    Ok(())
+}
+
+fn write_u64(w: &mut impl Write, x: u64) -> Result<()> {
+    w.write_all(&x.to_le_bytes())?;
+    Ok(())
+}
+
+fn write_str(w: &mut impl Write, s: &str) -> Result<()> {
+    write_u64(w, s.len() as u64)?;
+    w.write_all(s.as_bytes())?;
+    Ok(())
+}
+
+fn read_u8(r: &mut impl Read) -> Result<u8> {
+    let mut buf = [0u8];
+    r.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u32(r: &mut impl Read) -> Result<u32> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(r: &mut impl Read) -> Result<u64> {
+    let mut buf = [0u8; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_str(r: &mut impl Read) -> Result<String> {
+    let mut buf = vec![0u8; read_u64(r)? as usize];
+    r.read_exact(&mut buf)?;
+    String::from_utf8(buf)
+        .map_err(|_| Error::MalformedData("index: invalid string".into()))
+}
+
+fn read_idcode(r: &mut impl Read) -> Result<vcd::IdCode> {
+    let s = read_str(r)?;
+    s.parse()
+        .map_err(|_| Error::MalformedData(format!("index: invalid id code '{}'", s)))
+}
+
+fn write_simtime(w: &mut impl Write, t: SimTime) -> Result<()> {
+    use SimTimeUnit::*;
+    let unit = match t.get_unit() {
+        Fs => 0,
+        Ps => 1,
+        Ns => 2,
+        Us => 3,
+        Ms => 4,
+        S => 5,
+    };
+
+    write_u64(w, t.get_value())?;
+    w.write_all(&[unit])?;
+    Ok(())
+}
+
+fn read_simtime(r: &mut impl Read) -> Result<SimTime> {
+    use SimTimeUnit::*;
+    let value = read_u64(r)?;
+    let mut unit = [0u8];
+    r.read_exact(&mut unit)?;
+    let unit = match unit[0] {
+        0 => Fs,
+        1 => Ps,
+        2 => Ns,
+        3 => Us,
+        4 => Ms,
+        5 => S,
+        _ => return Err(Error::MalformedData("index: invalid time unit".into())),
+    };
+
+    Ok(SimTime::new(value, unit))
+}
+
+fn write_format(w: &mut impl Write, format: WaveFormat) -> Result<()> {
+    let (tag, width) = match format {
+        WaveFormat::Bit => (0, 1),
+        WaveFormat::Vector(width) => (1, width),
+        WaveFormat::BitVector(width) => (2, width),
+        WaveFormat::Comment => (3, 0),
+        WaveFormat::Real(..) => (4, 64),
+        WaveFormat::Text => (5, 0),
+        _ => return Err(Error::Internal("format can not be stored in index".into())),
+    };
+
+    w.write_all(&[tag])?;
+    write_u32(w, width)?;
+
+    if let WaveFormat::Real(min, max) = format {
+        write_u64(w, min.to_bits())?;
+        write_u64(w, max.to_bits())?;
+    }
+
+    Ok(())
+}
+
+fn read_format(r: &mut impl Read) -> Result<WaveFormat> {
+    let mut tag = [0u8];
+    r.read_exact(&mut tag)?;
+    let width = read_u32(r)?;
+
+    match tag[0] {
+        0 => Ok(WaveFormat::Bit),
+        1 => Ok(WaveFormat::Vector(width)),
+        2 => Ok(WaveFormat::BitVector(width)),
+        3 => Ok(WaveFormat::Comment),
+        4 => {
+            let min = f64::from_bits(read_u64(r)?);
+            let max = f64::from_bits(read_u64(r)?);
+            Ok(WaveFormat::Real(min, max))
+        }
+        5 => Ok(WaveFormat::Text),
+        _ => Err(Error::MalformedData("index: invalid format".into())),
+    }
+}
+
+fn write_kind(w: &mut impl Write, kind: VarKind) -> Result<()> {
+    let tag = match kind {
+        VarKind::Logic => 0,
+        VarKind::Real => 1,
+        VarKind::Text => 2,
+    };
+
+    w.write_all(&[tag])?;
+    Ok(())
+}
+
+fn read_kind(r: &mut impl Read) -> Result<VarKind> {
+    let mut tag = [0u8];
+    r.read_exact(&mut tag)?;
+
+    match tag[0] {
+        0 => Ok(VarKind::Logic),
+        1 => Ok(VarKind::Real),
+        2 => Ok(VarKind::Text),
+        _ => Err(Error::MalformedData("index: invalid variable kind".into())),
+    }
+}
+
+fn value_to_byte(v: &vcd::Value) -> u8 {
+    match v {
+        Value::V0 => 0,
+        Value::V1 => 1,
+        Value::X => 2,
+        Value::Z => 3,
+    }
+}
+
+fn byte_to_value(b: u8) -> Result<vcd::Value> {
+    match b {
+        0 => Ok(Value::V0),
+        1 => Ok(Value::V1),
+        2 => Ok(Value::X),
+        3 => Ok(Value::Z),
+        _ => Err(Error::MalformedData("index: invalid value".into())),
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_index_roundtrip() {
+        const FILENAME: &'static str = "examples/core.vcd";
+        let filename = Path::new(FILENAME);
+        let cycle_time = Some(SimTime::from_ps(1));
+        let tmpd = TempDir::new("index").unwrap();
+
+        let key = IndexKey::new(filename, &CycleGrid::Period(cycle_time)).unwrap();
+        let index_file = key.index_file(tmpd.path());
+        assert!(read_index(&index_file, &key, filename).unwrap().is_none());
+
+        let loader = VcdLoader::new(filename, cycle_time).unwrap();
+        write_index(&index_file, &key, &loader).unwrap();
+
+        let indexed = read_index(&index_file, &key, filename).unwrap().unwrap();
+        assert_eq!(loader.num_cycles, indexed.num_cycles);
+        assert_eq!(loader.signals.len(), indexed.signals.len());
+        assert_eq!(loader.namemap, indexed.namemap);
+        assert_eq!(loader.checkpoint_bitmap, indexed.checkpoint_bitmap);
+        assert_eq!(loader.checkpoints.len(), indexed.checkpoints.len());
+        for (a, b) in loader.checkpoints.iter().zip(indexed.checkpoints.iter()) {
+            assert_eq!(a.offset, b.offset);
+            assert_eq!(a.cycle, b.cycle);
+            assert_eq!(a.values, b.values);
+        }
+
+        // index for different cycle time is not used
+        let other_key = IndexKey::new(filename, &CycleGrid::Period(Some(SimTime::from_ps(2)))).unwrap();
+        assert!(read_index(&index_file, &other_key, filename).unwrap().is_none());
+
+        // clock edges are stored
+        let grid = CycleGrid::Clock("tb_core.clk".to_string(), ClockEdge::Rising);
+        let key = IndexKey::new(filename, &grid).unwrap();
+        let loader = VcdLoader::with_grid(filename, grid).unwrap();
+        write_index(&index_file, &key, &loader).unwrap();
+
+        let indexed = read_index(&index_file, &key, filename).unwrap().unwrap();
+        assert_eq!(loader.edges, indexed.edges);
+        assert!(indexed.edges.is_some());
+    }
+
+    #[test]
+    fn test_stale_indexes() {
+        const FILENAME: &str = "examples/core.vcd";
+        let filename = Path::new(FILENAME);
+        let tmpd = TempDir::new("index").unwrap();
+        let loader = VcdLoader::new(filename, None).unwrap();
+
+        // the same for every build
+        assert_eq!(0xcbf2_9ce4_8422_2325, fnv1a(b""));
+        assert_eq!(0xaf63_dc4c_8601_ec8c, fnv1a(b"a"));
+
+        let key = IndexKey::new(filename, &CycleGrid::Period(None)).unwrap();
+        let index_file = key.index_file(tmpd.path());
+        write_index(&index_file, &key, &loader).unwrap();
+
+        // other settings for the same version of the file are kept
+        let other_grid = IndexKey::new(filename, &CycleGrid::Period(Some(SimTime::from_ps(2)))).unwrap();
+        let other_grid_file = other_grid.index_file(tmpd.path());
+        write_index(&other_grid_file, &other_grid, &loader).unwrap();
+
+        // older versions of the file and the same settings under another name are removed
+        let older = IndexKey {
+            path: key.path.clone(),
+            size: key.size,
+            mtime_secs: key.mtime_secs - 1,
+            mtime_nanos: key.mtime_nanos,
+            cycle_time_fs: key.cycle_time_fs,
+            clock: key.clock.clone(),
+        };
+        let older_file = tmpd.path().join("older.vcdidx");
+        write_index(&older_file, &older, &loader).unwrap();
+        let renamed_file = tmpd.path().join("renamed.vcdidx");
+        write_index(&renamed_file, &key, &loader).unwrap();
+
+        remove_stale_indexes(tmpd.path(), &key);
+        assert!(index_file.exists());
+        assert!(other_grid_file.exists());
+        assert!(!older_file.exists());
+        assert!(!renamed_file.exists());
+    }
+
+    #[test]
+    fn test_compressed_index() {
+        let tmpd = TempDir::new("index").unwrap();
+        let filename = tmpd.path().join("core.vcd.gz");
+        let mut encoder = flate2::write::GzEncoder::new(File::create(&filename).unwrap(),
+            flate2::Compression::default());
+        encoder.write_all(&std::fs::read("examples/core.vcd").unwrap()).unwrap();
+        encoder.finish().unwrap();
+
+        let mut scanned = VcdLoader::with_index(&filename, CycleGrid::Period(None), tmpd.path()).unwrap();
+        assert!(scanned.decompressed.is_some());
+
+        // decompressed only when sampled
+        let mut indexed = VcdLoader::with_index(&filename, CycleGrid::Period(None), tmpd.path()).unwrap();
+        assert!(indexed.decompressed.is_none());
+
+        let ids: Vec<String> = scanned.signals.iter().map(|decl| decl.name.clone()).collect();
+        let times = SimTimeRange(SimTime::zero(), scanned.cycle_time * 20);
+        assert_eq!(scanned.sample(&ids, &times).unwrap(), indexed.sample(&ids, &times).unwrap());
+        assert!(indexed.decompressed.is_some());
+    }
+}
@@ -2,10 +2,15 @@ pub mod filter;
 
 use crate::data::*;
 use crate::error::*;
+use crate::formatting::EnumTables;
 
-pub type Pipeline = Stage<String, usize, rug::Integer>;
-pub type SrcBox = Box<dyn Source<String, usize, rug::Integer, IntoSignalIter = Vec<Signal<String>>>>;
-pub type FilterBox = Box<dyn Filter<usize, rug::Integer, IntoSigIter = Vec<Signal<usize>>, IntoIdIter = Vec<usize>>>;
+use ndarray::prelude::*;
+use std::cell::Cell;
+use std::path::PathBuf;
+
+pub type Pipeline = Stage<String, usize, LogicValue>;
+pub type SrcBox = Box<dyn Source<String, usize, LogicValue, IntoSignalIter = Vec<Signal<String>>>>;
+pub type FilterBox = Box<dyn Filter<usize, LogicValue, IntoSigIter = Vec<Signal<usize>>, IntoIdIter = Vec<usize>>>;
 
 //
 // Pipeline stages
@@ -23,6 +28,9 @@ pub enum Stage<SrcId, PipeId, PipeVal> {
                 IntoIdIter = Vec<PipeId>,
             >,
         >,
+        /// Cycles kept by the filter, once they were asked for. Cleared, when the stages before
+        /// the filter change.
+        Cell<Option<Option<CycleMap>>>,
     ),
 }
 
@@ -32,12 +40,12 @@ impl<SrcId, PipeId, PipeVal> Stage<SrcId, PipeId, PipeVal> {
     }
 
     pub fn push(self, stage: Box<dyn Filter< PipeId, PipeVal, IntoSigIter = Vec<Signal<PipeId>>, IntoIdIter = Vec<PipeId> >>) -> Self {
-        Self::Fil(Box::new(self), stage)
+        Self::Fil(Box::new(self), stage, Cell::new(None))
     }
 
     pub fn pop(self) -> (Self, Option<Box<dyn Filter< PipeId, PipeVal, IntoSigIter = Vec<Signal<PipeId>>, IntoIdIter = Vec<PipeId> >>>) {
         match self {
-            Self::Fil(prev, filter) => {
+            Self::Fil(prev, filter, _) => {
                 (*prev, Some(filter))
             }
 
@@ -46,6 +54,29 @@ impl<SrcId, PipeId, PipeVal> Stage<SrcId, PipeId, PipeVal> {
             }
         }
     }
+
+    /// Source at the start of the pipeline, dropping all filters
+    pub fn into_source(self) -> Box<dyn Source<SrcId, PipeId, PipeVal, IntoSignalIter = Vec<Signal<SrcId>>>> {
+        match self {
+            Self::Fil(prev, _, _) => prev.into_source(),
+            Self::Src(source) => source,
+        }
+    }
+
+    /// Cycles kept by the filter of this stage, if it maps cycles
+    fn cycle_map(&self) -> Option<CycleMap> {
+        match self {
+            Self::Fil(ref prev, ref filter, ref cached) => match cached.get() {
+                Some(map) => map,
+                None => {
+                    let map = filter.map_cycles(&**prev);
+                    cached.set(Some(map));
+                    map
+                }
+            },
+            Self::Src(_) => None,
+        }
+    }
 }
 
 impl<SrcId, PipeId, PipeVal> QuerySource for Stage<SrcId, PipeId, PipeVal> {
@@ -61,13 +92,10 @@ impl<SrcId, PipeId, PipeVal> QuerySource for Stage<SrcId, PipeId, PipeVal> {
         let translated;
 
         match self {
-            Self::Fil(ref prev, ref filter) => {
+            Self::Fil(ref prev, ref filter, _) => {
+                // Filters may add signals of their own, that are computed from other signals.
                 let prev_signals = prev.query_signals()?;
                 translated = filter.translate_signals(prev_signals)?;
-
-                // not used right now, would allow filters to create their own signals.
-                //let mut src_signals = filter.query_signals()?;
-                //translated.append(&mut src_signals);
             }
 
             Self::Src(ref src) => {
@@ -91,7 +119,14 @@ impl<SrcId, PipeId, PipeVal> QuerySource for Stage<SrcId, PipeId, PipeVal> {
 
     fn query_time_range(&self) -> Result<SimTimeRange> {
         match self {
-            Self::Fil(ref prev, _) => prev.query_time_range(),
+            Self::Fil(..) if self.cycle_map().is_some() => {
+                let start = self.query_time(0);
+                let stop = self.query_time(self.query_cycle_count());
+
+                Ok(SimTimeRange(start, stop))
+            }
+
+            Self::Fil(ref prev, _, _) => prev.query_time_range(),
 
             Self::Src(ref src) => src.query_time_range(),
         }
@@ -99,22 +134,72 @@ impl<SrcId, PipeId, PipeVal> QuerySource for Stage<SrcId, PipeId, PipeVal> {
 
     fn query_time(&self, cycle: usize) -> SimTime {
         match self {
-            Self::Fil(ref prev, _) => prev.query_time(cycle),
+            Self::Fil(ref prev, _, _) => match self.cycle_map() {
+                // the end of the last cycle may lie beyond the cycles before the filter
+                Some(map) => prev.query_time(map.upstream(cycle).min(prev.query_cycle_count())),
+                None => prev.query_time(cycle),
+            },
             Self::Src(ref src) => src.query_time(cycle),
         }
     }
 
     fn query_cycle_count(&self) -> usize {
         match self {
-            Self::Fil(ref prev, _) => prev.query_cycle_count(),
+            Self::Fil(ref prev, _, _) => match self.cycle_map() {
+                Some(map) => map.count,
+                None => prev.query_cycle_count(),
+            },
             Self::Src(ref src) => src.query_cycle_count(),
         }
     }
+
+    fn query_refresh(&mut self) -> Result<bool> {
+        match self {
+            Self::Fil(ref mut prev, _, ref cached) => {
+                let changed = prev.query_refresh()?;
+                if changed {
+                    cached.set(None);
+                }
+
+                Ok(changed)
+            }
+            Self::Src(ref mut src) => src.query_refresh(),
+        }
+    }
+
+    fn query_reload(&mut self) -> Result<()> {
+        match self {
+            Self::Fil(ref mut prev, _, ref cached) => {
+                cached.set(None);
+                prev.query_reload()
+            }
+            Self::Src(ref mut src) => src.query_reload(),
+        }
+    }
+
+    fn query_files(&self) -> Vec<PathBuf> {
+        match self {
+            Self::Fil(ref prev, _, _) => prev.query_files(),
+            Self::Src(ref src) => src.query_files(),
+        }
+    }
+
+    fn query_enums(&self) -> EnumTables {
+        match self {
+            Self::Fil(ref prev, ref filter, _) => {
+                let mut enums = prev.query_enums();
+                filter.add_enums(&mut enums);
+                enums
+            }
+            Self::Src(ref src) => src.query_enums(),
+        }
+    }
 }
 
 impl<SrcId, PipeId, PipeVal> Sample for Stage<SrcId, PipeId, PipeVal>
 where
     PipeId: Clone,
+    PipeVal: Clone + Default,
 {
     type Id = PipeId;
     type Value = PipeVal;
@@ -124,11 +209,22 @@ where
         ids: &Vec<Self::Id>,
         times: &SimTimeRange,
     ) -> Result<CycleValues<Self::Value>> {
+        let map = self.cycle_map();
+
         match self {
-            Self::Fil(ref mut prev, ref mut filter) => {
+            Self::Fil(ref mut prev, ref mut filter, _) => {
                 let trans_ids = filter.rev_translate_ids(ids.to_vec())?;
-                let mut vals = prev.sample(&trans_ids, times)?;
-                filter.transform(&mut vals);
+                let shifts = trans_ids.iter()
+                    .map(|id| filter.cycle_shift(id))
+                    .collect::<Result<Vec<Option<(isize, PipeVal)>>>>()?;
+                let mut vals = match map {
+                    Some(map) => sample_mapped(&mut **prev, &trans_ids, times, map)?,
+                    None if shifts.iter().any(Option::is_some) => {
+                        sample_shifted(&mut **prev, &trans_ids, times, shifts)?
+                    }
+                    None => prev.sample(&trans_ids, times)?,
+                };
+                filter.transform(ids, &mut vals)?;
 
                 Ok(vals)
             }
@@ -144,6 +240,102 @@ where
     }
 }
 
+/// Sample the cycles kept by `map`, that start within `times`, from `prev`
+fn sample_mapped<P>(prev: &mut P, ids: &Vec<<P as Sample>::Id>, times: &SimTimeRange, map: CycleMap)
+    -> Result<CycleValues<P::Value>>
+where
+    P: QuerySource + Sample,
+    P::Value: Clone + Default,
+{
+    // first kept cycle at or after a cycle before the filter
+    let kept = |cycle: usize| {
+        let cycle = cycle.saturating_sub(map.first);
+        std::cmp::min(cycle.div_ceil(map.step), map.count)
+    };
+    let first = kept(prev.query_cycle(times.0));
+    let last = kept(prev.query_cycle(times.1)).max(first);
+    if first == last {
+        return prev.sample(ids, &SimTimeRange(times.0, times.0));
+    }
+
+    // sample all cycles at once, including the ones skipped in between
+    let prev_times = SimTimeRange(
+        prev.query_time(map.upstream(first)),
+        prev.query_time(map.upstream(last - 1) + 1));
+    let values = prev.sample(ids, &prev_times)?;
+    if map.step == 1 {
+        return Ok(values);
+    }
+
+    // cycles missing before the filter are left at their default
+    let mut data = Array2::default((last - first, ids.len()));
+    for (mut row, values) in data.outer_iter_mut().zip(values.outer_iter().step_by(map.step)) {
+        row.assign(&values);
+    }
+
+    Ok(data)
+}
+
+/// Sample the cycles, that start within `times`, from `prev`, each signal shifted by the cycles
+/// in `shifts`
+fn sample_shifted<P>(
+    prev: &mut P,
+    ids: &Vec<<P as Sample>::Id>,
+    times: &SimTimeRange,
+    shifts: Vec<Option<(isize, P::Value)>>,
+) -> Result<CycleValues<P::Value>>
+where
+    P: QuerySource + Sample,
+    <P as Sample>::Id: Clone,
+    P::Value: Clone + Default,
+{
+    let count = prev.query_cycle_count() as isize;
+    let first = prev.query_cycle(times.0) as isize;
+    let last = (prev.query_cycle(times.1) as isize).max(first);
+
+    // columns of the signals with the same shift
+    let mut groups: Vec<(isize, Vec<usize>)> = Vec::new();
+    for (column, shift) in shifts.iter().enumerate() {
+        let shift = shift.as_ref().map_or(0, |(shift, _)| *shift);
+        match groups.iter_mut().find(|(group_shift, _)| *group_shift == shift) {
+            Some((_, columns)) => columns.push(column),
+            None => groups.push((shift, vec![column])),
+        }
+    }
+
+    let mut data = Array2::default(((last - first) as usize, ids.len()));
+    for (shift, columns) in groups {
+        // cycles before the filter, that are within the trace
+        let start = (first - shift).clamp(0, count);
+        let stop = (last - shift).clamp(0, count);
+
+        for (row, cycle) in (first - shift..last - shift).enumerate() {
+            if cycle < start || cycle >= stop {
+                for column in columns.iter() {
+                    if let Some((_, fill)) = &shifts[*column] {
+                        data[[row, *column]] = fill.clone();
+                    }
+                }
+            }
+        }
+        if start == stop {
+            continue;
+        }
+
+        let group_ids = columns.iter().map(|column| ids[*column].clone()).collect();
+        let group_times = SimTimeRange(prev.query_time(start as usize), prev.query_time(stop as usize));
+        let values = prev.sample(&group_ids, &group_times)?;
+
+        let first_row = (start + shift - first) as usize;
+        for (i, column) in columns.iter().enumerate() {
+            data.slice_mut(s![first_row..first_row + values.nrows(), *column])
+                .assign(&values.column(i));
+        }
+    }
+
+    Ok(data)
+}
+
 impl <SrcId, PipeId, PipeVal> ConfigurePipeline for Stage<SrcId, PipeId, PipeVal> {
     fn configure_pipeline(&mut self, config: &PipelineConfig) -> Result<()> {
         match self {
@@ -151,7 +343,7 @@ impl <SrcId, PipeId, PipeVal> ConfigurePipeline for Stage<SrcId, PipeId, PipeVal
                 Ok(())
             }
 
-            Self::Fil(prev, filter) => {
+            Self::Fil(prev, filter, _) => {
                 filter.configure_pipeline(config)?;
                 prev.configure_pipeline(config)
             }
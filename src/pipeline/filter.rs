@@ -4,6 +4,13 @@ mod remove_comments;
 mod replace_prefix;
 mod ignore;
 mod analog;
+mod format;
+mod derive;
+mod bits;
+mod enumeration;
+mod cycles;
+mod shift;
+mod lua;
 
 pub use grep::*;
 pub use signal_list::*;
@@ -11,3 +18,10 @@ pub use remove_comments::*;
 pub use replace_prefix::*;
 pub use ignore::*;
 pub use analog::*;
+pub use format::*;
+pub use derive::*;
+pub use bits::*;
+pub use enumeration::*;
+pub use cycles::*;
+pub use shift::*;
+pub use lua::*;
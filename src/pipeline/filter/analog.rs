@@ -2,24 +2,24 @@ use regex::RegexSet;
 
 use crate::error::*;
 use crate::data::*;
-use crate::formatting::WaveFormat;
+use crate::formatting::{Numeric, Scale, WaveFormat};
 
 
 pub struct Analog {
     patterns: RegexSet,
-    min: f64,
-    max: f64,
+    numeric: Numeric,
+    scale: Scale,
 }
 
 
 impl Analog {
-    pub fn new<T: AsRef<str>>(patterns: &[T], min: f64, max: f64) -> Result<Self> {
+    pub fn new<T: AsRef<str>>(patterns: &[T], numeric: Numeric, scale: Scale) -> Result<Self> {
         let patterns = RegexSet::new(patterns)?;
 
         Ok(Self {
             patterns,
-            min,
-            max,
+            numeric,
+            scale,
         })
     }
 }
@@ -35,8 +35,8 @@ impl<I> TranslateSignals<I> for Analog {
                 if self.patterns.is_match(&signal.name) {
                     signal.format = match signal.format {
                         WaveFormat::BitVector(sz)
-                        | WaveFormat::Vector(sz) => {
-                            WaveFormat::Analog(sz, self.min, self.max)
+                        | WaveFormat::Vector(sz) if self.numeric.fits(sz) => {
+                            WaveFormat::Analog(sz, self.numeric, self.scale)
                         }
                         _ => signal.format
                     };
@@ -54,11 +54,11 @@ impl<I> TranslateSignals<I> for Analog {
     }
 }
 
-impl Transform for Analog {
-    type Value = rug::Integer;
+impl<I> Transform<I> for Analog {
+    type Value = LogicValue;
 }
 
 impl ConfigurePipeline for Analog {}
 
-impl<I> Filter<I, rug::Integer> for Analog {}
+impl<I> Filter<I, LogicValue> for Analog {}
 
@@ -0,0 +1,485 @@
+use regex::{Regex, RegexSet};
+
+use crate::error::*;
+use crate::data::*;
+use crate::formatting::WaveFormat;
+
+use ndarray::prelude::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// Piece of a signal made up of bits of upstream signals
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Part {
+    /// Bits `high` down to `low` of an upstream signal
+    Bits { id: usize, high: u32, low: u32 },
+    /// Unknown bits, filling a gap
+    Unknown(u32),
+}
+
+impl Part {
+    fn width(&self) -> u32 {
+        match *self {
+            Part::Bits { high, low, .. } => high - low + 1,
+            Part::Unknown(width) => width,
+        }
+    }
+}
+
+/// Parts of the signals a filter made up, by id. Parts are ordered most significant first.
+///
+/// Sampling a made up signal samples the upstream signals of its parts in its place.
+#[derive(Default)]
+struct Layout {
+    composed: RefCell<HashMap<usize, Vec<Part>>>,
+}
+
+impl Layout {
+    fn set(&self, composed: HashMap<usize, Vec<Part>>) {
+        *self.composed.borrow_mut() = composed;
+    }
+
+    fn rev_translate_ids(&self, ids: Vec<usize>) -> Vec<usize> {
+        let composed = self.composed.borrow();
+        if !ids.iter().any(|id| composed.contains_key(id)) {
+            return ids;
+        }
+
+        let mut rv = Vec::with_capacity(ids.len());
+        for id in ids {
+            match composed.get(&id) {
+                Some(parts) => {
+                    rv.extend(parts.iter().filter_map(|part| match *part {
+                        Part::Bits { id, .. } => Some(id),
+                        Part::Unknown(_) => None,
+                    }));
+                }
+                None => rv.push(id),
+            }
+        }
+
+        rv
+    }
+
+    fn transform(&self, ids: &[usize], values: &mut CycleValues<LogicValue>) {
+        let composed = self.composed.borrow();
+        if !ids.iter().any(|id| composed.contains_key(id)) {
+            return;
+        }
+
+        let mut rv = Array2::default((values.nrows(), ids.len()));
+        for (row, mut rv_row) in values.outer_iter().zip(rv.outer_iter_mut()) {
+            let mut upstream = row.iter();
+
+            for (id, target) in ids.iter().zip(rv_row.iter_mut()) {
+                let parts = match composed.get(id) {
+                    Some(parts) => parts,
+                    None => {
+                        if let Some(value) = upstream.next() {
+                            *target = value.clone();
+                        }
+                        continue;
+                    }
+                };
+
+                let mut value = LogicValue::new();
+                let mut glitch = false;
+                let mut pos: u32 = parts.iter().map(Part::width).sum();
+                for part in parts {
+                    pos -= part.width();
+                    match *part {
+                        Part::Bits { low, .. } => {
+                            let source = match upstream.next() {
+                                Some(source) => source,
+                                None => break,
+                            };
+                            for i in 0..part.width() {
+                                value.set_bit(pos + i, source.bit(low + i));
+                            }
+                            glitch |= source.has_glitch();
+                        }
+                        Part::Unknown(width) => {
+                            for i in 0..width {
+                                value.set_bit(pos + i, LogicBit::X);
+                            }
+                        }
+                    }
+                }
+
+                value.set_glitch(glitch);
+                *target = value;
+            }
+        }
+
+        *values = rv;
+    }
+}
+
+fn format_of_width(width: u32) -> WaveFormat {
+    if width == 1 {
+        WaveFormat::Bit
+    } else {
+        WaveFormat::Vector(width)
+    }
+}
+
+/// Width of vector signals, that can be cut into bits
+fn vector_width(format: WaveFormat) -> Option<u32> {
+    match format {
+        WaveFormat::Vector(width) | WaveFormat::BitVector(width) => Some(width),
+        _ => None,
+    }
+}
+
+fn next_id(signals: &[Signal<usize>]) -> usize {
+    signals.iter()
+        .map(|signal| signal.id + 1)
+        .max()
+        .unwrap_or(0)
+}
+
+
+/// Adds the bits `high` down to `low` of each matching vector as a new signal, e.g. `data[15:8]`,
+/// right after the vector itself. Vectors with less than `high + 1` bits are left alone.
+pub struct Slice {
+    pattern: Regex,
+    high: u32,
+    low: u32,
+    layout: Layout,
+}
+
+impl Slice {
+    pub fn new(pattern: &str, high: u32, low: u32) -> Result<Self> {
+        if low > high {
+            return Err(Error::InvalidArgument(format!("Slice [{}:{}] must not count upwards", high, low)));
+        }
+
+        Ok(Self {
+            pattern: Regex::new(pattern)?,
+            high,
+            low,
+            layout: Layout::default(),
+        })
+    }
+}
+
+impl TranslateSignals<usize> for Slice {
+    type IntoSigIter = Vec<Signal<usize>>;
+    type IntoIdIter = Vec<usize>;
+
+    fn translate_signals(&self, signals: Self::IntoSigIter) -> Result<Self::IntoSigIter> {
+        let mut id = next_id(&signals);
+        let mut composed = HashMap::new();
+        let mut rv = Vec::with_capacity(signals.len());
+
+        for signal in signals {
+            let fits = vector_width(signal.format)
+                .map(|width| self.high < width)
+                .unwrap_or(false);
+
+            if fits && self.pattern.is_match(&signal.name) {
+                let slice = Signal {
+                    id,
+                    name: format!("{}[{}:{}]", signal.name, self.high, self.low),
+                    format: format_of_width(self.high - self.low + 1),
+                };
+                composed.insert(id, vec![Part::Bits { id: signal.id, high: self.high, low: self.low }]);
+                id += 1;
+
+                rv.push(signal);
+                rv.push(slice);
+            } else {
+                rv.push(signal);
+            }
+        }
+
+        self.layout.set(composed);
+        Ok(rv)
+    }
+
+    fn rev_translate_ids(&self, signals: Self::IntoIdIter) -> Result<Self::IntoIdIter> {
+        Ok(self.layout.rev_translate_ids(signals))
+    }
+}
+
+impl Transform<usize> for Slice {
+    type Value = LogicValue;
+
+    fn transform(&mut self, ids: &[usize], values: &mut CycleValues<Self::Value>) -> Result<()> {
+        self.layout.transform(ids, values);
+        Ok(())
+    }
+}
+
+impl ConfigurePipeline for Slice {}
+
+impl Filter<usize, LogicValue> for Slice {}
+
+
+/// Replaces each matching vector by its single bits, most significant first. Bits are named like
+/// `ctrl[3]`.
+pub struct Split {
+    patterns: RegexSet,
+    layout: Layout,
+}
+
+impl Split {
+    pub fn new<T: AsRef<str>>(patterns: &[T]) -> Result<Self> {
+        Ok(Self {
+            patterns: RegexSet::new(patterns)?,
+            layout: Layout::default(),
+        })
+    }
+}
+
+impl TranslateSignals<usize> for Split {
+    type IntoSigIter = Vec<Signal<usize>>;
+    type IntoIdIter = Vec<usize>;
+
+    fn translate_signals(&self, signals: Self::IntoSigIter) -> Result<Self::IntoSigIter> {
+        let mut id = next_id(&signals);
+        let mut composed = HashMap::new();
+        let mut rv = Vec::with_capacity(signals.len());
+
+        for signal in signals {
+            let width = match vector_width(signal.format) {
+                Some(width) if self.patterns.is_match(&signal.name) => width,
+                _ => {
+                    rv.push(signal);
+                    continue;
+                }
+            };
+
+            for bit in (0..width).rev() {
+                rv.push(Signal {
+                    id,
+                    name: format!("{}[{}]", signal.name, bit),
+                    format: WaveFormat::Bit,
+                });
+                composed.insert(id, vec![Part::Bits { id: signal.id, high: bit, low: bit }]);
+                id += 1;
+            }
+        }
+
+        self.layout.set(composed);
+        Ok(rv)
+    }
+
+    fn rev_translate_ids(&self, signals: Self::IntoIdIter) -> Result<Self::IntoIdIter> {
+        Ok(self.layout.rev_translate_ids(signals))
+    }
+}
+
+impl Transform<usize> for Split {
+    type Value = LogicValue;
+
+    fn transform(&mut self, ids: &[usize], values: &mut CycleValues<Self::Value>) -> Result<()> {
+        self.layout.transform(ids, values);
+        Ok(())
+    }
+}
+
+impl ConfigurePipeline for Split {}
+
+impl Filter<usize, LogicValue> for Split {}
+
+
+/// Groups matching single bits named like `x[0]`, `x[1]`, ... into one vector `x`, with bit `x[i]`
+/// at index `i`. The vector takes the place of the first of its bits. Bits missing in between
+/// are unknown.
+pub struct Concat {
+    patterns: RegexSet,
+    layout: Layout,
+}
+
+impl Concat {
+    pub fn new<T: AsRef<str>>(patterns: &[T]) -> Result<Self> {
+        Ok(Self {
+            patterns: RegexSet::new(patterns)?,
+            layout: Layout::default(),
+        })
+    }
+
+    /// Name of the vector and index of the bit, if `signal` is one of the bits to group.
+    fn bit_of(&self, signal: &Signal<usize>) -> Option<(String, u32)> {
+        if signal.format != WaveFormat::Bit || !self.patterns.is_match(&signal.name) {
+            return None;
+        }
+
+        let (base, index) = signal.name.strip_suffix(']')?.rsplit_once('[')?;
+        let index = index.parse().ok()?;
+
+        if base.is_empty() {
+            None
+        } else {
+            Some((base.to_string(), index))
+        }
+    }
+}
+
+impl TranslateSignals<usize> for Concat {
+    type IntoSigIter = Vec<Signal<usize>>;
+    type IntoIdIter = Vec<usize>;
+
+    fn translate_signals(&self, signals: Self::IntoSigIter) -> Result<Self::IntoSigIter> {
+        // bits of each vector by index
+        let mut groups: HashMap<String, HashMap<u32, usize>> = HashMap::new();
+        for signal in signals.iter() {
+            if let Some((base, index)) = self.bit_of(signal) {
+                groups.entry(base).or_default().insert(index, signal.id);
+            }
+        }
+
+        let mut id = next_id(&signals);
+        let mut composed = HashMap::new();
+        let mut rv = Vec::with_capacity(signals.len());
+
+        for signal in signals {
+            let base = match self.bit_of(&signal) {
+                Some((base, _)) => base,
+                None => {
+                    rv.push(signal);
+                    continue;
+                }
+            };
+
+            // the first bit of a group in the list adds the vector, the others vanish
+            let bits = match groups.remove(&base) {
+                Some(bits) => bits,
+                None => continue,
+            };
+
+            let width = bits.keys().max().map(|high| high + 1).unwrap_or(0);
+            let mut parts = Vec::with_capacity(width as usize);
+            for index in (0..width).rev() {
+                match bits.get(&index) {
+                    Some(bit_id) => parts.push(Part::Bits { id: *bit_id, high: 0, low: 0 }),
+                    None => parts.push(Part::Unknown(1)),
+                }
+            }
+
+            rv.push(Signal {
+                id,
+                name: base,
+                format: format_of_width(width),
+            });
+            composed.insert(id, parts);
+            id += 1;
+        }
+
+        self.layout.set(composed);
+        Ok(rv)
+    }
+
+    fn rev_translate_ids(&self, signals: Self::IntoIdIter) -> Result<Self::IntoIdIter> {
+        Ok(self.layout.rev_translate_ids(signals))
+    }
+}
+
+impl Transform<usize> for Concat {
+    type Value = LogicValue;
+
+    fn transform(&mut self, ids: &[usize], values: &mut CycleValues<Self::Value>) -> Result<()> {
+        self.layout.transform(ids, values);
+        Ok(())
+    }
+}
+
+impl ConfigurePipeline for Concat {}
+
+impl Filter<usize, LogicValue> for Concat {}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn signal(id: usize, name: &str, format: WaveFormat) -> Signal<usize> {
+        Signal { id, name: name.to_string(), format }
+    }
+
+    fn names(signals: &[Signal<usize>]) -> Vec<&str> {
+        signals.iter().map(|signal| signal.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_slice() {
+        let mut filter = Slice::new("data", 7, 4).unwrap();
+        let signals = filter.translate_signals(vec![
+            signal(0, "data", WaveFormat::Vector(16)),
+            signal(1, "clk", WaveFormat::Bit),
+            signal(2, "small_data", WaveFormat::Vector(4)),
+        ]).unwrap();
+        assert_eq!(vec!["data", "data[7:4]", "clk", "small_data"], names(&signals));
+        assert_eq!(3, signals[1].id);
+        assert!(signals[1].format == WaveFormat::Vector(4));
+
+        let ids = vec![1, 3, 0];
+        assert_eq!(vec![1, 0, 0], filter.rev_translate_ids(ids.clone()).unwrap());
+
+        let mut glitch = LogicValue::from(0xabcd);
+        glitch.set_glitch(true);
+        let mut values = Array2::from_shape_vec((1, 3), vec![
+            LogicValue::from(1), glitch.clone(), glitch.clone(),
+        ]).unwrap();
+        filter.transform(&ids, &mut values).unwrap();
+        assert_eq!(LogicValue::from(1), values[[0, 0]]);
+        assert_eq!(LogicValue::from(0xc), values[[0, 1]]);
+        assert!(values[[0, 1]].has_glitch());
+        assert_eq!(glitch, values[[0, 2]]);
+
+        assert!(Slice::new("data", 4, 7).is_err());
+    }
+
+    #[test]
+    fn test_split() {
+        let mut filter = Split::new(&["ctrl"]).unwrap();
+        let signals = filter.translate_signals(vec![
+            signal(0, "clk", WaveFormat::Bit),
+            signal(1, "ctrl", WaveFormat::Vector(3)),
+        ]).unwrap();
+        assert_eq!(vec!["clk", "ctrl[2]", "ctrl[1]", "ctrl[0]"], names(&signals));
+        assert!(signals[1].format == WaveFormat::Bit);
+
+        let ids: Vec<usize> = signals.iter().map(|signal| signal.id).collect();
+        assert_eq!(vec![0, 1, 1, 1], filter.rev_translate_ids(ids.clone()).unwrap());
+
+        let mut ctrl = LogicValue::new();
+        ctrl.assign_bits(vec![LogicBit::One, LogicBit::Z, LogicBit::Zero]);
+        let mut values = Array2::from_shape_vec((1, 4), vec![
+            LogicValue::from(0), ctrl.clone(), ctrl.clone(), ctrl,
+        ]).unwrap();
+        filter.transform(&ids, &mut values).unwrap();
+        assert_eq!(LogicValue::from(1), values[[0, 1]]);
+        assert_eq!(LogicBit::Z, values[[0, 2]].bit(0));
+        assert_eq!(LogicValue::from(0), values[[0, 3]]);
+    }
+
+    #[test]
+    fn test_concat() {
+        let mut filter = Concat::new(&[r"^x\["]).unwrap();
+        let signals = filter.translate_signals(vec![
+            signal(0, "x[0]", WaveFormat::Bit),
+            signal(1, "clk", WaveFormat::Bit),
+            signal(2, "x[3]", WaveFormat::Bit),
+            signal(3, "x[1]", WaveFormat::Bit),
+            signal(4, "y[0]", WaveFormat::Bit),
+        ]).unwrap();
+        assert_eq!(vec!["x", "clk", "y[0]"], names(&signals));
+        assert_eq!(5, signals[0].id);
+        assert!(signals[0].format == WaveFormat::Vector(4));
+
+        // most significant bit first, x[2] is missing
+        let ids = vec![1, 5];
+        assert_eq!(vec![1, 2, 3, 0], filter.rev_translate_ids(ids.clone()).unwrap());
+
+        let mut values = Array2::from_shape_vec((1, 4), vec![
+            LogicValue::from(0), LogicValue::from(1), LogicValue::from(0), LogicValue::from(1),
+        ]).unwrap();
+        filter.transform(&ids, &mut values).unwrap();
+        assert_eq!((1, 2), values.dim());
+        assert_eq!(LogicValue::from(0), values[[0, 0]]);
+        assert_eq!("1x01", values[[0, 1]].to_bit_string());
+    }
+}
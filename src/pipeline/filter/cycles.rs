@@ -0,0 +1,256 @@
+use crate::error::*;
+use crate::data::*;
+
+
+/// Keeps the cycles, that start within a window of time
+pub struct Crop {
+    start: SimTime,
+    stop: SimTime,
+}
+
+impl Crop {
+    /// Keep cycles starting at or after `start` and before `stop`
+    pub fn new(start: SimTime, stop: SimTime) -> Result<Self> {
+        if stop < start {
+            return Err(Error::InvalidArgument("End of crop window is before its start".to_string()));
+        }
+
+        Ok(Self { start, stop })
+    }
+}
+
+
+impl<I> TranslateSignals<I> for Crop {
+    type IntoSigIter = Vec<Signal<I>>;
+    type IntoIdIter = Vec<I>;
+
+    fn translate_signals(&self, signals: Self::IntoSigIter) -> Result<Self::IntoSigIter> {
+        Ok(signals)
+    }
+
+    fn rev_translate_ids(&self, signals: Self::IntoIdIter) -> Result<Self::IntoIdIter> {
+        Ok(signals)
+    }
+}
+
+impl<I> Transform<I> for Crop {
+    type Value = LogicValue;
+}
+
+impl ConfigurePipeline for Crop {}
+
+impl<I> Filter<I, LogicValue> for Crop {
+    fn map_cycles(&self, prev: &dyn QuerySource<Id = I, IntoSignalIter = Vec<Signal<I>>>) -> Option<CycleMap> {
+        let first = prev.query_cycle(self.start);
+        let last = prev.query_cycle(self.stop).max(first);
+
+        Some(CycleMap { first, step: 1, count: last - first })
+    }
+}
+
+
+/// How many cycles `Decimate` combines into one
+#[derive(Clone, Copy, Debug)]
+pub enum Decimation {
+    /// Every n-th cycle is kept
+    Every(usize),
+    /// Cycles are kept at this period, rounded down to a multiple of the cycle time
+    Period(SimTime),
+}
+
+/// Keeps only every n-th cycle, e.g. to show a long trace on a coarser grid
+pub struct Decimate {
+    decimation: Decimation,
+}
+
+impl Decimate {
+    pub fn new(decimation: Decimation) -> Result<Self> {
+        if let Decimation::Every(0) = decimation {
+            return Err(Error::InvalidArgument("Can not keep every 0th cycle".to_string()));
+        }
+
+        Ok(Self { decimation })
+    }
+}
+
+
+impl<I> TranslateSignals<I> for Decimate {
+    type IntoSigIter = Vec<Signal<I>>;
+    type IntoIdIter = Vec<I>;
+
+    fn translate_signals(&self, signals: Self::IntoSigIter) -> Result<Self::IntoSigIter> {
+        Ok(signals)
+    }
+
+    fn rev_translate_ids(&self, signals: Self::IntoIdIter) -> Result<Self::IntoIdIter> {
+        Ok(signals)
+    }
+}
+
+impl<I> Transform<I> for Decimate {
+    type Value = LogicValue;
+}
+
+impl ConfigurePipeline for Decimate {}
+
+impl<I> Filter<I, LogicValue> for Decimate {
+    fn map_cycles(&self, prev: &dyn QuerySource<Id = I, IntoSignalIter = Vec<Signal<I>>>) -> Option<CycleMap> {
+        let prev_count = prev.query_cycle_count();
+        let step = match self.decimation {
+            Decimation::Every(step) => step,
+            // cycle time taken from the first cycle
+            Decimation::Period(_) if prev_count == 0 => 1,
+            Decimation::Period(period) => {
+                let cycle_time = prev.query_time(1) - prev.query_time(0);
+                if cycle_time.get_value() == 0 {
+                    1
+                } else {
+                    std::cmp::max(period / cycle_time, 1) as usize
+                }
+            }
+        };
+
+        Some(CycleMap { first: 0, step, count: prev_count.div_ceil(step) })
+    }
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::load::vcd::VcdLoader;
+    use crate::pipeline::Pipeline;
+    use tempdir::TempDir;
+
+    fn load(dir: &TempDir) -> Pipeline {
+        let mut vcd = "$timescale 1ns $end\n\
+            $scope module tb $end\n\
+            $var wire 8 ! count $end\n\
+            $upscope $end\n\
+            $enddefinitions $end\n".to_string();
+        for i in 0..10 {
+            vcd.push_str(&format!("#{}\nb{:b} !\n", i * 10, i));
+        }
+        vcd.push_str("#100\n");
+
+        let path = dir.path().join("count.vcd");
+        std::fs::write(&path, vcd).unwrap();
+        Pipeline::new(Box::new(VcdLoader::new(path, Some(SimTime::from_ns(10))).unwrap()))
+    }
+
+    fn sample_all(pipe: &mut Pipeline) -> Vec<LogicValue> {
+        let times = SimTimeRange(pipe.query_time(0), pipe.query_time(pipe.query_cycle_count()));
+        pipe.sample(&vec![0], &times).unwrap()
+            .column(0)
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn test_crop() {
+        let dir = TempDir::new("viow-test").unwrap();
+        let filter = Crop::new(SimTime::from_ns(25), SimTime::from_ns(60)).unwrap();
+        let mut pipe = load(&dir).push(Box::new(filter));
+
+        assert_eq!(3, pipe.query_cycle_count());
+        assert_eq!(Some(30_000), pipe.query_time(0).as_ps());
+        assert_eq!(Some(60_000), pipe.query_time(3).as_ps());
+
+        let expected: Vec<LogicValue> = (3..6u32).map(LogicValue::from).collect();
+        assert_eq!(expected, sample_all(&mut pipe));
+
+        // a part of the window
+        let data = pipe.sample(&vec![0], &SimTimeRange(SimTime::from_ns(40), SimTime::from_ns(60))).unwrap();
+        assert_eq!(2, data.nrows());
+        assert_eq!(LogicValue::from(4), data[[0, 0]]);
+
+        assert!(Crop::new(SimTime::from_ns(2), SimTime::from_ns(1)).is_err());
+    }
+
+    #[test]
+    fn test_crop_fs() {
+        let dir = TempDir::new("viow-test").unwrap();
+        let mut vcd = "$timescale 1fs $end\n\
+            $scope module tb $end\n\
+            $var wire 8 ! count $end\n\
+            $upscope $end\n\
+            $enddefinitions $end\n".to_string();
+        for i in 0..4 {
+            vcd.push_str(&format!("#{}\nb{:b} !\n", i * 500, i));
+        }
+        vcd.push_str("#2000\n");
+        let path = dir.path().join("fast.vcd");
+        std::fs::write(&path, vcd).unwrap();
+
+        let loader = VcdLoader::new(path, Some(SimTime::from_fs(500))).unwrap();
+        let pipe = Pipeline::new(Box::new(loader));
+        // cycles shorter than a picosecond
+        assert_eq!(1, pipe.query_cycle(SimTime::from_fs(500)));
+        assert_eq!(2, pipe.query_cycle(SimTime::from_fs(501)));
+
+        let filter = Crop::new(SimTime::from_fs(500), SimTime::from_fs(1500)).unwrap();
+        let mut pipe = pipe.push(Box::new(filter));
+        assert_eq!(2, pipe.query_cycle_count());
+        let expected: Vec<LogicValue> = (1..3u32).map(LogicValue::from).collect();
+        assert_eq!(expected, sample_all(&mut pipe));
+
+        assert!(Crop::new(SimTime::from_fs(1500), SimTime::from_ps(1)).is_err());
+    }
+
+    #[test]
+    fn test_decimate() {
+        let dir = TempDir::new("viow-test").unwrap();
+        let filter = Decimate::new(Decimation::Every(3)).unwrap();
+        let mut pipe = load(&dir).push(Box::new(filter));
+
+        assert_eq!(4, pipe.query_cycle_count());
+        assert_eq!(Some(30_000), pipe.query_time(1).as_ps());
+        // the last cycle ends with the trace
+        assert_eq!(Some(100_000), pipe.query_time(4).as_ps());
+
+        let expected: Vec<LogicValue> = [0, 3, 6, 9u32].iter().copied().map(LogicValue::from).collect();
+        assert_eq!(expected, sample_all(&mut pipe));
+
+        // decimate a cropped trace
+        let filter = Decimate::new(Decimation::Period(SimTime::from_ns(25))).unwrap();
+        let crop = Crop::new(SimTime::from_ns(10), SimTime::from_ns(80)).unwrap();
+        let mut pipe = load(&dir).push(Box::new(crop)).push(Box::new(filter));
+
+        assert_eq!(4, pipe.query_cycle_count());
+        let expected: Vec<LogicValue> = [1, 3, 5, 7u32].iter().copied().map(LogicValue::from).collect();
+        assert_eq!(expected, sample_all(&mut pipe));
+
+        assert!(Decimate::new(Decimation::Every(0)).is_err());
+    }
+
+    #[test]
+    fn test_decimate_follow() {
+        use crate::load::vcd::CycleGrid;
+        use std::io::Write;
+
+        let dir = TempDir::new("viow-test").unwrap();
+        let header = "$timescale 1ns $end\n\
+            $scope module tb $end\n\
+            $var wire 8 ! count $end\n\
+            $upscope $end\n\
+            $enddefinitions $end\n";
+        let path = dir.path().join("growing.vcd");
+        std::fs::write(&path, format!("{}#0\nb0 !\n#10\nb1 !\n#20\nb10 !\n#30\n", header)).unwrap();
+
+        let loader = VcdLoader::follow(&path, CycleGrid::Period(Some(SimTime::from_ns(10)))).unwrap();
+        let filter = Decimate::new(Decimation::Every(2)).unwrap();
+        let mut pipe = Pipeline::new(Box::new(loader)).push(Box::new(filter));
+        assert_eq!(2, pipe.query_cycle_count());
+
+        // the kept cycles are updated, when the trace grows
+        let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"b11 !\n#40\nb100 !\n#50\n").unwrap();
+        assert!(pipe.query_refresh().unwrap());
+        assert_eq!(3, pipe.query_cycle_count());
+
+        let expected: Vec<LogicValue> = [0, 2, 4u32].iter().copied().map(LogicValue::from).collect();
+        assert_eq!(expected, sample_all(&mut pipe));
+    }
+}
@@ -0,0 +1,188 @@
+mod expr;
+
+use crate::error::*;
+use crate::data::*;
+use crate::formatting::WaveFormat;
+
+use expr::Expr;
+use ndarray::prelude::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// Ids of the signals, that a derived signal is computed from
+struct Inputs {
+    /// Id of the derived signal
+    id: usize,
+    ids: Vec<usize>,
+    widths: Vec<u32>,
+}
+
+/// Adds a signal computed from an expression over other signals, e.g.
+/// `valid && ready && addr[31:28] == 4`. See `expr` for the syntax.
+///
+/// The derived signal gets the id following the largest id of its input. Sampling it samples all
+/// signals used in the expression instead.
+pub struct Derive {
+    name: String,
+    expr: Expr,
+    names: Vec<String>,
+    /// Set once signals are translated
+    inputs: RefCell<Option<Inputs>>,
+}
+
+/// The inputs are only known after the signals passed the filter.
+fn not_translated() -> Error {
+    Error::Internal("Derived signal sampled before its inputs were looked up".to_string())
+}
+
+impl Derive {
+    pub fn new(name: impl Into<String>, expression: &str) -> Result<Self> {
+        let (expr, names) = expr::parse(expression)?;
+
+        Ok(Self {
+            name: name.into(),
+            expr,
+            names,
+            inputs: RefCell::new(None),
+        })
+    }
+}
+
+
+impl TranslateSignals<usize> for Derive {
+    type IntoSigIter = Vec<Signal<usize>>;
+    type IntoIdIter = Vec<usize>;
+
+    fn translate_signals(&self, mut signals: Self::IntoSigIter) -> Result<Self::IntoSigIter> {
+        let by_name: HashMap<&str, &Signal<usize>> = signals.iter()
+            .map(|signal| (signal.name.as_str(), signal))
+            .collect();
+
+        let mut ids = Vec::with_capacity(self.names.len());
+        let mut widths = Vec::with_capacity(self.names.len());
+        for name in self.names.iter() {
+            let signal = by_name.get(name.as_str())
+                .ok_or_else(|| Error::NotFound(name.clone()))?;
+            let width = signal.format.width()
+                .ok_or_else(|| Error::InvalidArgument(format!("Signal '{}' has no fixed width", name)))?;
+
+            ids.push(signal.id);
+            widths.push(width);
+        }
+
+        let id = signals.iter()
+            .map(|signal| signal.id + 1)
+            .max()
+            .unwrap_or(0);
+        let format = match self.expr.width(&widths) {
+            1 => WaveFormat::Bit,
+            width => WaveFormat::Vector(width),
+        };
+
+        signals.push(Signal {
+            id,
+            name: self.name.clone(),
+            format,
+        });
+        *self.inputs.borrow_mut() = Some(Inputs { id, ids, widths });
+
+        Ok(signals)
+    }
+
+    /// The derived signal is replaced by its inputs, following all other ids.
+    fn rev_translate_ids(&self, signals: Self::IntoIdIter) -> Result<Self::IntoIdIter> {
+        let inputs = self.inputs.borrow();
+        let inputs = inputs.as_ref().ok_or_else(not_translated)?;
+        if !signals.contains(&inputs.id) {
+            return Ok(signals);
+        }
+
+        let mut rv: Vec<usize> = signals.into_iter()
+            .filter(|id| *id != inputs.id)
+            .collect();
+        rv.extend(inputs.ids.iter());
+
+        Ok(rv)
+    }
+}
+
+impl Transform<usize> for Derive {
+    type Value = LogicValue;
+
+    fn transform(&mut self, ids: &[usize], values: &mut CycleValues<Self::Value>) -> Result<()> {
+        let inputs = self.inputs.borrow();
+        let inputs = inputs.as_ref().ok_or_else(not_translated)?;
+        if !ids.contains(&inputs.id) {
+            return Ok(());
+        }
+
+        // inputs follow the other signals
+        let first_input = values.ncols() - inputs.ids.len();
+        let mut rv = Array2::default((values.nrows(), ids.len()));
+        for (row, mut rv_row) in values.outer_iter().zip(rv.outer_iter_mut()) {
+            let (others, input_values) = row.split_at(Axis(0), first_input);
+            let input_values: Vec<&LogicValue> = input_values.iter().collect();
+            let mut others = others.iter();
+
+            for (id, target) in ids.iter().zip(rv_row.iter_mut()) {
+                if *id == inputs.id {
+                    let (mut value, _) = self.expr.eval(&input_values, &inputs.widths);
+                    value.set_glitch(input_values.iter().any(|input| input.has_glitch()));
+                    *target = value;
+                } else if let Some(value) = others.next() {
+                    *target = value.clone();
+                }
+            }
+        }
+
+        *values = rv;
+        Ok(())
+    }
+}
+
+impl ConfigurePipeline for Derive {}
+
+impl Filter<usize, LogicValue> for Derive {}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_derive() {
+        let mut filter = Derive::new("hit", "valid && addr[7:4] == 4'h2").unwrap();
+        assert!(filter.rev_translate_ids(vec![0]).is_err());
+
+        let signals = vec![
+            Signal { id: 0, name: "addr".to_string(), format: WaveFormat::Vector(8) },
+            Signal { id: 2, name: "valid".to_string(), format: WaveFormat::Bit },
+        ];
+        let signals = filter.translate_signals(signals).unwrap();
+        assert_eq!(3, signals.len());
+        assert_eq!(3, signals[2].id);
+        assert_eq!("hit", signals[2].name);
+        assert!(signals[2].format == WaveFormat::Bit);
+
+        let ids = vec![3, 0];
+        assert_eq!(vec![0, 2, 0], filter.rev_translate_ids(ids.clone()).unwrap());
+        assert_eq!(vec![2], filter.rev_translate_ids(vec![2]).unwrap());
+
+        // sampled for upstream ids 0, 2, 0
+        let mut values = Array2::from_shape_vec((2, 3), vec![
+            LogicValue::from(0x21), LogicValue::from(1), LogicValue::from(0x21),
+            LogicValue::from(0x31), LogicValue::from(1), LogicValue::from(0x31),
+        ]).unwrap();
+        filter.transform(&ids, &mut values).unwrap();
+
+        assert_eq!((2, 2), values.dim());
+        assert_eq!(LogicValue::from(1), values[[0, 0]]);
+        assert_eq!(LogicValue::from(0x21), values[[0, 1]]);
+        assert_eq!(LogicValue::from(0), values[[1, 0]]);
+
+        let filter = Derive::new("bad", "missing + 1").unwrap();
+        assert!(filter.translate_signals(vec![]).is_err());
+        assert!(Derive::new("bad", "a +").is_err());
+    }
+}
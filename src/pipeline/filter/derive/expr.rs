@@ -0,0 +1,625 @@
+//! Expressions over signals, as used by the derive filter
+//!
+//! The syntax follows Verilog: bitwise (`~ & | ^`), logical (`! && ||`), comparison
+//! (`== != < <= > >=`), shift (`<< >>`) and arithmetic (`+ - * / %`) operators with the usual
+//! precedence, bit slices `a[7:4]` and `a[3]`, concatenation `{a, b}` and numbers like `42`,
+//! `0x2a`, `0b101` or `8'h2a`. Signal names may contain dots. Names with other characters are
+//! quoted in backticks, e.g. `` `x[0]` ``.
+//!
+//! Results are as wide as the widest operand, and wrap around like in Verilog. Comparisons and
+//! logical operators have one bit. Bits in state X or Z make the result of arithmetic and
+//! comparisons X, while bitwise and logical operators only propagate them where the result
+//! depends on them.
+
+use crate::data::LogicValue;
+use crate::error::*;
+
+use rug::Integer;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum UnaryOp {
+    Not,
+    LogicNot,
+    Neg,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum BinaryOp {
+    LogicOr,
+    LogicAnd,
+    Or,
+    Xor,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Shl,
+    Shr,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+#[derive(Clone, Debug)]
+pub(super) enum Expr {
+    /// Index into the list of signal names returned by `parse`
+    Signal(usize),
+    /// Value and its number of bits
+    Const(LogicValue, u32),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    /// Bits from high down to low
+    Slice(Box<Expr>, u32, u32),
+    /// Most significant part first
+    Concat(Vec<Expr>),
+}
+
+/// Binary operators by precedence, lowest first
+const BINARY_OPS: &[&[(&str, BinaryOp)]] = &[
+    &[("||", BinaryOp::LogicOr)],
+    &[("&&", BinaryOp::LogicAnd)],
+    &[("|", BinaryOp::Or)],
+    &[("^", BinaryOp::Xor)],
+    &[("&", BinaryOp::And)],
+    &[("==", BinaryOp::Eq), ("!=", BinaryOp::Ne)],
+    &[("<=", BinaryOp::Le), ("<", BinaryOp::Lt), (">=", BinaryOp::Ge), (">", BinaryOp::Gt)],
+    &[("<<", BinaryOp::Shl), (">>", BinaryOp::Shr)],
+    &[("+", BinaryOp::Add), ("-", BinaryOp::Sub)],
+    &[("*", BinaryOp::Mul), ("/", BinaryOp::Div), ("%", BinaryOp::Rem)],
+];
+
+/// Operator tokens, longest first
+const OPERATORS: &[&str] = &[
+    "||", "&&", "==", "!=", "<=", ">=", "<<", ">>",
+    "!", "~", "&", "|", "^", "+", "-", "*", "/", "%", "<", ">",
+    "(", ")", "[", "]", "{", "}", ",", ":",
+];
+
+#[derive(Clone, Debug)]
+enum Token {
+    Name(String),
+    Number(LogicValue, u32),
+    Op(&'static str),
+}
+
+/// Parse `text` into an expression and the names of the signals it uses.
+pub(super) fn parse(text: &str) -> Result<(Expr, Vec<String>)> {
+    let mut parser = Parser {
+        text,
+        tokens: tokenize(text)?,
+        pos: 0,
+        names: vec![],
+    };
+
+    let expr = parser.parse_binary(0)?;
+    if parser.pos < parser.tokens.len() {
+        return Err(parser.error("unexpected input"));
+    }
+
+    Ok((expr, parser.names))
+}
+
+fn syntax_error(text: &str, pos: usize, msg: &str) -> Error {
+    Error::InvalidArgument(format!("Expression '{}', at {}: {}", text, pos, msg))
+}
+
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>> {
+    let mut tokens = vec![];
+    let mut rest = text;
+
+    loop {
+        rest = rest.trim_start();
+        let pos = text.len() - rest.len();
+        let c = match rest.chars().next() {
+            Some(c) => c,
+            None => break,
+        };
+
+        let (token, len) = if c.is_ascii_digit() {
+            let len = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '\'' || c == '?'))
+                .unwrap_or(rest.len());
+            let (value, width) = parse_number(&rest[..len])
+                .ok_or_else(|| syntax_error(text, pos, "invalid number"))?;
+            (Token::Number(value, width), len)
+        } else if c.is_ascii_alphabetic() || c == '_' || c == '$' {
+            let len = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '$' || c == '.'))
+                .unwrap_or(rest.len());
+            (Token::Name(rest[..len].to_string()), len)
+        } else if c == '`' {
+            let len = rest[1..].find('`')
+                .ok_or_else(|| syntax_error(text, pos, "missing closing '`'"))?;
+            (Token::Name(rest[1..len + 1].to_string()), len + 2)
+        } else {
+            let op = OPERATORS.iter()
+                .copied()
+                .find(|op| rest.starts_with(op))
+                .ok_or_else(|| syntax_error(text, pos, "unknown operator"))?;
+            (Token::Op(op), op.len())
+        };
+
+        tokens.push((pos, token));
+        rest = &rest[len..];
+    }
+
+    Ok(tokens)
+}
+
+/// Parse a number like `42`, `0x2a`, `0b101` or `8'h2a`. Based numbers may have X and Z digits.
+fn parse_number(text: &str) -> Option<(LogicValue, u32)> {
+    let text = text.replace('_', "");
+
+    let (size, base, digits) = if let Some(tick) = text.find('\'') {
+        let size = if tick > 0 { Some(text[..tick].parse::<u32>().ok()?) } else { None };
+        let base = text[tick + 1..].chars().next()?.to_ascii_lowercase();
+        (size, base, &text[tick + 2..])
+    } else if let Some(digits) = text.strip_prefix("0x") {
+        (None, 'h', digits)
+    } else if let Some(digits) = text.strip_prefix("0b") {
+        (None, 'b', digits)
+    } else {
+        (None, 'd', &text[..])
+    };
+
+    if digits.is_empty() {
+        return None;
+    }
+
+    let bits_per_digit = match base {
+        'b' => 1,
+        'o' => 3,
+        'h' => 4,
+        'd' => {
+            let value = Integer::from_str_radix(digits, 10).ok()?;
+            let width = size.unwrap_or_else(|| value.significant_bits().max(1));
+            return Some((truncate(&LogicValue::from(value), width), width));
+        }
+        _ => return None,
+    };
+
+    let digit_mask = mask(bits_per_digit);
+    let mut value = Integer::new();
+    let mut x = Integer::new();
+    let mut z = Integer::new();
+    for c in digits.chars() {
+        value = value << bits_per_digit;
+        x = x << bits_per_digit;
+        z = z << bits_per_digit;
+
+        match c {
+            'x' | 'X' => x = x | &digit_mask,
+            'z' | 'Z' | '?' => z = z | &digit_mask,
+            _ => {
+                let digit = c.to_digit(1 << bits_per_digit)?;
+                value = value | &Integer::from(digit);
+            }
+        }
+    }
+
+    let width = size.unwrap_or(digits.len() as u32 * bits_per_digit);
+    Some((truncate(&LogicValue::from_parts(value, x, z), width), width))
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    names: Vec<String>,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, msg: &str) -> Error {
+        let pos = self.tokens.get(self.pos)
+            .map(|(pos, _)| *pos)
+            .unwrap_or(self.text.len());
+
+        syntax_error(self.text, pos, msg)
+    }
+
+    fn peek_op(&self, op: &str) -> bool {
+        matches!(self.tokens.get(self.pos), Some((_, Token::Op(next))) if *next == op)
+    }
+
+    fn eat_op(&mut self, op: &str) -> bool {
+        let found = self.peek_op(op);
+        if found {
+            self.pos += 1;
+        }
+
+        found
+    }
+
+    fn expect_op(&mut self, op: &str) -> Result<()> {
+        if self.eat_op(op) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", op)))
+        }
+    }
+
+    fn parse_binary(&mut self, level: usize) -> Result<Expr> {
+        if level == BINARY_OPS.len() {
+            return self.parse_unary();
+        }
+
+        let mut lhs = self.parse_binary(level + 1)?;
+        while let Some(op) = BINARY_OPS[level].iter().find(|(s, _)| self.peek_op(s)).map(|(_, op)| *op) {
+            self.pos += 1;
+            let rhs = self.parse_binary(level + 1)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr> {
+        let op = if self.eat_op("~") {
+            UnaryOp::Not
+        } else if self.eat_op("!") {
+            UnaryOp::LogicNot
+        } else if self.eat_op("-") {
+            UnaryOp::Neg
+        } else {
+            return self.parse_slice();
+        };
+
+        Ok(Expr::Unary(op, Box::new(self.parse_unary()?)))
+    }
+
+    fn parse_slice(&mut self) -> Result<Expr> {
+        let mut expr = self.parse_primary()?;
+
+        while self.eat_op("[") {
+            let high = self.parse_index()?;
+            let low = if self.eat_op(":") {
+                self.parse_index()?
+            } else {
+                high
+            };
+            self.expect_op("]")?;
+
+            if low > high {
+                return Err(self.error("slice must be given as [high:low]"));
+            }
+            expr = Expr::Slice(Box::new(expr), high, low);
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_index(&mut self) -> Result<u32> {
+        let index = match self.tokens.get(self.pos) {
+            Some((_, Token::Number(value, _))) if value.is_known() => value.value().to_u32(),
+            _ => None,
+        };
+
+        let index = index.ok_or_else(|| self.error("expected bit index"))?;
+        self.pos += 1;
+        Ok(index)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr> {
+        let token = self.tokens.get(self.pos)
+            .map(|(_, token)| token.clone())
+            .ok_or_else(|| self.error("unexpected end"))?;
+        self.pos += 1;
+
+        match token {
+            Token::Number(value, width) => Ok(Expr::Const(value, width)),
+
+            Token::Name(name) => {
+                let index = match self.names.iter().position(|known| *known == name) {
+                    Some(index) => index,
+                    None => {
+                        self.names.push(name);
+                        self.names.len() - 1
+                    }
+                };
+
+                Ok(Expr::Signal(index))
+            }
+
+            Token::Op("(") => {
+                let expr = self.parse_binary(0)?;
+                self.expect_op(")")?;
+                Ok(expr)
+            }
+
+            Token::Op("{") => {
+                let mut parts = vec![self.parse_binary(0)?];
+                while self.eat_op(",") {
+                    parts.push(self.parse_binary(0)?);
+                }
+                self.expect_op("}")?;
+                Ok(Expr::Concat(parts))
+            }
+
+            Token::Op(_) => {
+                self.pos -= 1;
+                Err(self.error("expected signal, number, '(' or '{'"))
+            }
+        }
+    }
+}
+
+impl Expr {
+    /// Number of bits of the result for the given widths of the signals
+    pub(super) fn width(&self, widths: &[u32]) -> u32 {
+        let zero = LogicValue::new();
+        let inputs = vec![&zero; widths.len()];
+
+        self.eval(&inputs, widths).1
+    }
+
+    /// Evaluate for values of the signals and their widths, both in the order of the names
+    /// returned by `parse`.
+    ///
+    /// Returns the value and its number of bits.
+    pub(super) fn eval(&self, inputs: &[&LogicValue], widths: &[u32]) -> (LogicValue, u32) {
+        match self {
+            Expr::Signal(i) => (truncate(inputs[*i], widths[*i]), widths[*i]),
+
+            Expr::Const(value, width) => (value.clone(), *width),
+
+            Expr::Unary(op, a) => {
+                let (a, width) = a.eval(inputs, widths);
+                (eval_unary(*op, &a, width), if *op == UnaryOp::LogicNot { 1 } else { width })
+            }
+
+            Expr::Binary(op, a, b) => {
+                let (a, width_a) = a.eval(inputs, widths);
+                let (b, width_b) = b.eval(inputs, widths);
+                eval_binary(*op, &a, width_a, &b, width_b)
+            }
+
+            Expr::Slice(a, high, low) => {
+                let (a, _) = a.eval(inputs, widths);
+                let width = high - low + 1;
+                (map_parts(&a, |part| Integer::from(part >> *low) & mask(width)), width)
+            }
+
+            Expr::Concat(parts) => {
+                parts.iter().fold((LogicValue::new(), 0), |(acc, width), part| {
+                    let (part, part_width) = part.eval(inputs, widths);
+                    let value = LogicValue::from_parts(
+                        Integer::from(acc.value() << part_width) | part.value(),
+                        Integer::from(acc.x_mask() << part_width) | part.x_mask(),
+                        Integer::from(acc.z_mask() << part_width) | part.z_mask());
+                    (value, width + part_width)
+                })
+            }
+        }
+    }
+}
+
+/// Mask of the lowest `width` bits
+fn mask(width: u32) -> Integer {
+    (Integer::from(1) << width) - Integer::from(1)
+}
+
+/// Apply `f` to the value and the masks of X and Z bits.
+fn map_parts(value: &LogicValue, f: impl Fn(&Integer) -> Integer) -> LogicValue {
+    LogicValue::from_parts(f(value.value()), f(value.x_mask()), f(value.z_mask()))
+}
+
+fn truncate(value: &LogicValue, width: u32) -> LogicValue {
+    let mask = mask(width);
+    map_parts(value, |part| Integer::from(part & &mask))
+}
+
+fn unknown(value: &LogicValue) -> Integer {
+    Integer::from(value.x_mask() | value.z_mask())
+}
+
+fn all_x(width: u32) -> LogicValue {
+    LogicValue::from_parts(Integer::new(), mask(width), Integer::new())
+}
+
+/// Value with bits set in `ones`, unknown where neither `ones` nor `zeros` are set.
+fn from_known(ones: Integer, zeros: Integer, width: u32) -> LogicValue {
+    let x = mask(width) & !(Integer::from(&ones | &zeros));
+    LogicValue::from_parts(ones, x, Integer::new())
+}
+
+/// True, if a bit is 1. False, if all bits are 0. None otherwise.
+fn truth(value: &LogicValue) -> Option<bool> {
+    if *value.value() != 0 {
+        Some(true)
+    } else if value.is_known() {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+fn from_truth(truth: Option<bool>) -> LogicValue {
+    match truth {
+        Some(truth) => LogicValue::from(truth as u32),
+        None => all_x(1),
+    }
+}
+
+fn eval_unary(op: UnaryOp, a: &LogicValue, width: u32) -> LogicValue {
+    match op {
+        UnaryOp::Not => {
+            let known_zeros = mask(width) & !(Integer::from(a.value() | &unknown(a)));
+            from_known(known_zeros, a.value().clone(), width)
+        }
+
+        UnaryOp::LogicNot => from_truth(truth(a).map(|truth| !truth)),
+
+        UnaryOp::Neg if a.is_known() => LogicValue::from(Integer::from(-a.value()) & mask(width)),
+        UnaryOp::Neg => all_x(width),
+    }
+}
+
+fn eval_binary(op: BinaryOp, a: &LogicValue, width_a: u32, b: &LogicValue, width_b: u32) -> (LogicValue, u32) {
+    use BinaryOp::*;
+
+    let width = width_a.max(width_b);
+    let zeros = |value: &LogicValue| mask(width) & !(Integer::from(value.value() | &unknown(value)));
+
+    match op {
+        LogicOr => {
+            let truth = match (truth(a), truth(b)) {
+                (Some(true), _) | (_, Some(true)) => Some(true),
+                (Some(false), Some(false)) => Some(false),
+                _ => None,
+            };
+            return (from_truth(truth), 1);
+        }
+
+        LogicAnd => {
+            let truth = match (truth(a), truth(b)) {
+                (Some(false), _) | (_, Some(false)) => Some(false),
+                (Some(true), Some(true)) => Some(true),
+                _ => None,
+            };
+            return (from_truth(truth), 1);
+        }
+
+        Or => {
+            let ones = Integer::from(a.value() | b.value());
+            return (from_known(ones, zeros(a) & zeros(b), width), width);
+        }
+
+        And => {
+            let ones = Integer::from(a.value() & b.value());
+            return (from_known(ones, zeros(a) | zeros(b), width), width);
+        }
+
+        Xor => {
+            let x = unknown(a) | unknown(b);
+            let ones = Integer::from(a.value() ^ b.value()) & !x.clone();
+            return (LogicValue::from_parts(ones, x, Integer::new()), width);
+        }
+
+        _ => (),
+    }
+
+    // all other operators need known operands
+    let width = match op {
+        Eq | Ne | Lt | Le | Gt | Ge => 1,
+        Shl | Shr | Div | Rem => width_a,
+        Mul => width_a + width_b,
+        _ => width,
+    };
+    if !a.is_known() || !b.is_known() {
+        return (all_x(width), width);
+    }
+
+    let (a, b) = (a.value(), b.value());
+    let value = match op {
+        Eq => Integer::from((a == b) as u32),
+        Ne => Integer::from((a != b) as u32),
+        Lt => Integer::from((a < b) as u32),
+        Le => Integer::from((a <= b) as u32),
+        Gt => Integer::from((a > b) as u32),
+        Ge => Integer::from((a >= b) as u32),
+        Shl | Shr => match b.to_u32() {
+            Some(shift) if shift < width => {
+                if op == Shl {
+                    Integer::from(a << shift) & mask(width)
+                } else {
+                    Integer::from(a >> shift)
+                }
+            }
+            _ => Integer::new(),
+        },
+        Add => Integer::from(a + b) & mask(width),
+        Sub => Integer::from(a - b) & mask(width),
+        Mul => Integer::from(a * b),
+        Div | Rem if *b == 0 => return (all_x(width), width),
+        Div => Integer::from(a / b),
+        Rem => Integer::from(a % b),
+        LogicOr | LogicAnd | Or | And | Xor => unreachable!(),
+    };
+
+    (LogicValue::from(value), width)
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::LogicBit;
+
+    fn eval(text: &str, inputs: &[(&str, LogicValue, u32)]) -> (LogicValue, u32) {
+        let (expr, names) = parse(text).unwrap();
+        let values: Vec<&LogicValue> = names.iter()
+            .map(|name| &inputs.iter().find(|input| input.0 == name.as_str()).unwrap().1)
+            .collect();
+        let widths: Vec<u32> = names.iter()
+            .map(|name| inputs.iter().find(|input| input.0 == name.as_str()).unwrap().2)
+            .collect();
+
+        expr.eval(&values, &widths)
+    }
+
+    #[test]
+    fn test_parse() {
+        let (_, names) = parse("valid && ready && tb.addr[31:28] == 4 && `x[0]`").unwrap();
+        assert_eq!(vec!["valid", "ready", "tb.addr", "x[0]"], names);
+
+        assert!(parse("a +").is_err());
+        assert!(parse("(a").is_err());
+        assert!(parse("a[3:4]").is_err());
+        assert!(parse("a # b").is_err());
+        assert!(parse("8'q12").is_err());
+
+        let (value, width) = parse_number("8'h2x").unwrap();
+        assert_eq!(8, width);
+        assert_eq!(LogicBit::One, value.bit(5));
+        assert_eq!(LogicBit::X, value.bit(3));
+        assert_eq!((LogicValue::from(42), 6), parse_number("42").unwrap());
+        assert_eq!((LogicValue::from(5), 4), parse_number("0b0101").unwrap());
+        assert_eq!((LogicValue::from(3), 2), parse_number("2'd7").unwrap());
+    }
+
+    #[test]
+    fn test_eval() {
+        let inputs = [
+            ("valid", LogicValue::from(1), 1),
+            ("ready", LogicValue::from(1), 1),
+            ("addr", LogicValue::from(0x4000_0010u32), 32),
+            ("count", LogicValue::from(0xff), 8),
+        ];
+
+        assert_eq!((LogicValue::from(1), 1), eval("valid && ready && addr[31:28] == 4", &inputs));
+        assert_eq!((LogicValue::from(0), 8), eval("count + 1", &inputs));
+        assert_eq!((LogicValue::from(0xfe), 8), eval("count - 1", &inputs));
+        assert_eq!((LogicValue::from(0x1ff), 12), eval("{addr[7:4], count}", &inputs));
+        assert_eq!((LogicValue::from(0xf0), 8), eval("~(count >> 4)", &inputs));
+        assert_eq!((LogicValue::from(4), 10), eval("count * 2 % 4 + 2", &inputs));
+        assert_eq!((LogicValue::from(0), 1), eval("!valid || count < 8'h10", &inputs));
+    }
+
+    #[test]
+    fn test_eval_unknown() {
+        let mut x = LogicValue::new();
+        x.assign_bits(vec![LogicBit::Zero, LogicBit::X]);
+        let inputs = [
+            ("x", x, 2),
+            ("zero", LogicValue::from(0), 2),
+            ("one", LogicValue::from(1), 1),
+        ];
+
+        // bitwise and logical operators only propagate X, where it matters
+        assert_eq!((LogicValue::from(0), 2), eval("x & zero", &inputs));
+        assert_eq!((LogicValue::from(0), 1), eval("x && zero", &inputs));
+        assert_eq!((LogicValue::from(1), 1), eval("x || one", &inputs));
+        assert_eq!(LogicBit::X, eval("x | zero", &inputs).0.bit(0));
+        assert_eq!(LogicBit::One, eval("~x", &inputs).0.bit(1));
+        assert_eq!(LogicBit::X, eval("x[0]", &inputs).0.bit(0));
+
+        // arithmetic and comparisons do not
+        assert_eq!(LogicBit::X, eval("x + zero", &inputs).0.bit(1));
+        assert_eq!(LogicBit::X, eval("x == zero", &inputs).0.bit(0));
+        assert_eq!(LogicBit::X, eval("one / zero", &inputs).0.bit(0));
+    }
+}
@@ -0,0 +1,119 @@
+use regex::Regex;
+
+use crate::error::*;
+use crate::data::*;
+use crate::formatting::{EnumId, EnumNames, EnumTables, WaveFormat};
+
+
+/// Shows matching signals by the names of their values, e.g. the states of a state machine
+pub struct Enum {
+    pattern: Regex,
+    names: EnumNames,
+    id: EnumId,
+}
+
+
+impl Enum {
+    pub fn new(pattern: &str, names: EnumNames) -> Result<Self> {
+        let pattern = Regex::new(pattern)?;
+        let id = names.id();
+
+        Ok(Self {
+            pattern,
+            names,
+            id,
+        })
+    }
+}
+
+
+impl<I> TranslateSignals<I> for Enum {
+    type IntoSigIter = Vec<Signal<I>>;
+    type IntoIdIter = Vec<I>;
+
+    fn translate_signals(&self, signals: Self::IntoSigIter) -> Result<Self::IntoSigIter> {
+        let filtered_signals = signals.into_iter()
+            .map(|mut signal| {
+                if self.pattern.is_match(&signal.name) {
+                    signal.format = match signal.format {
+                        WaveFormat::Bit => WaveFormat::Enum(1, self.id),
+                        WaveFormat::BitVector(sz)
+                        | WaveFormat::Vector(sz) => WaveFormat::Enum(sz, self.id),
+                        _ => signal.format
+                    };
+                }
+
+                signal
+            })
+            .collect();
+
+        Ok(filtered_signals)
+    }
+
+    fn rev_translate_ids(&self, signals: Self::IntoIdIter) -> Result<Self::IntoIdIter> {
+        Ok(signals)
+    }
+}
+
+impl<I> Transform<I> for Enum {
+    type Value = LogicValue;
+}
+
+impl ConfigurePipeline for Enum {}
+
+impl<I> Filter<I, LogicValue> for Enum {
+    fn add_enums(&self, enums: &mut EnumTables) {
+        enums.insert(self.names.clone());
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::formatting::format_value;
+    use crate::load::vcd::VcdLoader;
+    use crate::pipeline::Pipeline;
+    use rug::Integer;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_enum() {
+        let dir = TempDir::new("viow-test").unwrap();
+        let vcd = "$timescale 1ns $end\n\
+            $scope module tb $end\n\
+            $var wire 2 ! state $end\n\
+            $var wire 2 \" data $end\n\
+            $upscope $end\n\
+            $enddefinitions $end\n\
+            #0\nb0 !\nb1 \"\n#10\nb1 !\n#20\nb11 !\n#30\n";
+        let path = dir.path().join("enum.vcd");
+        std::fs::write(&path, vcd).unwrap();
+
+        let names = EnumNames::new(vec![
+            (Integer::from(0), "IDLE".to_string()),
+            (Integer::from(1), "BUSY".to_string()),
+        ]);
+        let loader = VcdLoader::new(path, Some(SimTime::from_ns(10))).unwrap();
+        let filter = Enum::new("state", names.clone()).unwrap();
+        let mut pipe = Pipeline::new(Box::new(loader)).push(Box::new(filter));
+
+        let formats: Vec<WaveFormat> = pipe.query_signals().unwrap()
+            .into_iter()
+            .map(|signal| signal.format)
+            .collect();
+        assert!(formats[0] == WaveFormat::Enum(2, names.id()));
+        assert!(formats[1] == WaveFormat::Vector(2));
+
+        let enums = pipe.query_enums();
+        assert_eq!(Some(&names), enums.get(names.id()));
+
+        let times = SimTimeRange(SimTime::zero(), SimTime::from_ns(30));
+        let data = pipe.sample(&vec![0, 1], &times).unwrap();
+        assert_eq!("IDLE", format_value(&data[[0, 0]], formats[0], &enums));
+        assert_eq!("BUSY", format_value(&data[[1, 0]], formats[0], &enums));
+        // values without a name are shown as illegal
+        assert_eq!("!0x3", format_value(&data[[2, 0]], formats[0], &enums));
+        assert_eq!("0x1", format_value(&data[[0, 1]], formats[1], &enums));
+    }
+}
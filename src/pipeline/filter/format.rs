@@ -0,0 +1,114 @@
+use regex::RegexSet;
+
+use crate::error::*;
+use crate::data::*;
+use crate::formatting::{Numeric, WaveFormat};
+
+
+/// Sets the format of matching vectors by name, see `WaveFormat::from_name`. Vectors, that
+/// the format does not fit, are left alone.
+pub struct Format {
+    patterns: RegexSet,
+    name: String,
+}
+
+
+impl Format {
+    pub fn new<T: AsRef<str>>(patterns: &[T], name: &str) -> Result<Self> {
+        let patterns = RegexSet::new(patterns)?;
+
+        if WaveFormat::from_name(name, 64).is_none() && Numeric::from_name(name).is_none() {
+            return Err(Error::InvalidArgument(format!("Unknown format '{}'", name)));
+        }
+
+        Ok(Self {
+            patterns,
+            name: name.to_string(),
+        })
+    }
+}
+
+
+impl<I> TranslateSignals<I> for Format {
+    type IntoSigIter = Vec<Signal<I>>;
+    type IntoIdIter = Vec<I>;
+
+    fn translate_signals(&self, signals: Self::IntoSigIter) -> Result<Self::IntoSigIter> {
+        let filtered_signals = signals.into_iter()
+            .map(|mut signal| {
+                if self.patterns.is_match(&signal.name) {
+                    signal.format = match signal.format {
+                        WaveFormat::BitVector(sz)
+                        | WaveFormat::Vector(sz) => {
+                            WaveFormat::from_name(&self.name, sz).unwrap_or(signal.format)
+                        }
+                        _ => signal.format
+                    };
+                }
+
+                signal
+            })
+            .collect();
+
+        Ok(filtered_signals)
+    }
+
+    fn rev_translate_ids(&self, signals: Self::IntoIdIter) -> Result<Self::IntoIdIter> {
+        Ok(signals)
+    }
+}
+
+impl<I> Transform<I> for Format {
+    type Value = LogicValue;
+}
+
+impl ConfigurePipeline for Format {}
+
+impl<I> Filter<I, LogicValue> for Format {}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::load::vcd::VcdLoader;
+    use crate::pipeline::Pipeline;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_format() {
+        let dir = TempDir::new("viow-test").unwrap();
+        let vcd = "$timescale 1ns $end\n\
+            $scope module tb $end\n\
+            $var wire 8 ! count $end\n\
+            $var wire 8 \" data $end\n\
+            $var wire 1 # valid $end\n\
+            $upscope $end\n\
+            $enddefinitions $end\n\
+            #0\nb11111110 !\nb101 \"\n1#\n#10\nb11 !\nb110 \"\n0#\n#20\n";
+        let path = dir.path().join("format.vcd");
+        std::fs::write(&path, vcd).unwrap();
+
+        let loader = VcdLoader::new(path, Some(SimTime::from_ns(10))).unwrap();
+        let filter = Format::new(&["count", "valid"], "signed").unwrap();
+        let mut pipe = Pipeline::new(Box::new(loader)).push(Box::new(filter));
+
+        let formats: Vec<WaveFormat> = pipe.query_signals().unwrap()
+            .into_iter()
+            .map(|signal| signal.format)
+            .collect();
+        assert!(formats[0] == WaveFormat::Number(8, Numeric::Signed));
+        assert!(formats[1] == WaveFormat::Vector(8));
+        // bits are no vectors
+        assert!(formats[2] == WaveFormat::Bit);
+
+        let times = SimTimeRange(SimTime::zero(), SimTime::from_ns(20));
+        let data = pipe.sample(&vec![0, 1, 2], &times).unwrap();
+        assert_eq!(LogicValue::from(0xfe), data[[0, 0]]);
+        assert_eq!(LogicValue::from(5), data[[0, 1]]);
+        assert_eq!(LogicValue::from(3), data[[1, 0]]);
+        assert_eq!(LogicValue::from(0), data[[1, 2]]);
+
+        assert!(Format::new(&["count"], "roman").is_err());
+    }
+}
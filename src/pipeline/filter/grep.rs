@@ -1,5 +1,4 @@
 use regex::Regex;
-use rug;
 
 use crate::error::*;
 use crate::data::*;
@@ -44,10 +43,10 @@ impl<I> TranslateSignals<I> for Grep {
 }
 
 
-impl Transform for Grep {
-    type Value = rug::Integer;
+impl<I> Transform<I> for Grep {
+    type Value = LogicValue;
 }
 
 impl ConfigurePipeline for Grep {}
 
-impl<I> Filter<I, rug::Integer> for Grep {}
+impl<I> Filter<I, LogicValue> for Grep {}
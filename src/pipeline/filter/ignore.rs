@@ -51,10 +51,10 @@ impl<I> TranslateSignals<I> for Ignore {
     }
 }
 
-impl Transform for Ignore {
-    type Value = rug::Integer;
+impl<I> Transform<I> for Ignore {
+    type Value = LogicValue;
 }
 
 impl ConfigurePipeline for Ignore {}
 
-impl<I> Filter<I, rug::Integer> for Ignore {}
+impl<I> Filter<I, LogicValue> for Ignore {}
@@ -0,0 +1,190 @@
+use mlua::{Function, Lua, RegistryKey, Table, Value};
+
+use crate::error::*;
+use crate::data::*;
+use crate::load::lua::{value_from_lua, value_to_lua};
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Filter defined by callbacks in a Lua script
+///
+/// `translate_signals(signals)` gets a list of tables with `id` and `name` of each signal. It
+/// returns the signals to show in the same form, e.g. renamed, reordered or with some left out.
+/// `transform(names, rows)` gets one row of values for each cycle and returns the rows to show
+/// instead. Values are integers, or strings of bits like `"10xz"`, if they have unknown bits.
+/// Either callback may be missing.
+pub struct LuaFilter {
+    lua: Rc<Lua>,
+    /// The callbacks, kept in the registry of `lua`
+    translate: Option<RegistryKey>,
+    transform: Option<RegistryKey>,
+    /// Names of the translated signals by id
+    names: RefCell<HashMap<usize, String>>,
+}
+
+impl LuaFilter {
+    pub fn new(lua: Rc<Lua>, translate: Option<Function>, transform: Option<Function>) -> Result<Self> {
+        let translate = translate.map(|callback| lua.create_registry_value(callback)).transpose()?;
+        let transform = transform.map(|callback| lua.create_registry_value(callback)).transpose()?;
+
+        Ok(Self {
+            lua,
+            translate,
+            transform,
+            names: RefCell::new(HashMap::new()),
+        })
+    }
+
+    fn translate_in_lua(&self, translate: &RegistryKey, signals: Vec<Signal<usize>>)
+        -> Result<Vec<Signal<usize>>>
+    {
+        let translate: Function = self.lua.registry_value(translate)?;
+        let mut formats = HashMap::with_capacity(signals.len());
+        let mut entries = Vec::with_capacity(signals.len());
+        for signal in signals {
+            let entry = self.lua.create_table()?;
+            entry.set("id", signal.id)?;
+            entry.set("name", signal.name)?;
+            entries.push(entry);
+            formats.insert(signal.id, signal.format);
+        }
+
+        let translated: Vec<Table> = translate.call(entries)?;
+        translated.into_iter()
+            .map(|entry| {
+                let id: usize = entry.get("id")?;
+                let name: String = entry.get("name")?;
+                let format = formats.get(&id)
+                    .copied()
+                    .ok_or_else(|| Error::InvalidArgument(format!("Lua filter: unknown signal id {}", id)))?;
+
+                Ok(Signal { id, name, format })
+            })
+            .collect()
+    }
+}
+
+
+impl TranslateSignals<usize> for LuaFilter {
+    type IntoSigIter = Vec<Signal<usize>>;
+    type IntoIdIter = Vec<usize>;
+
+    fn translate_signals(&self, signals: Self::IntoSigIter) -> Result<Self::IntoSigIter> {
+        let translated = match self.translate {
+            Some(ref translate) => self.translate_in_lua(translate, signals)?,
+            None => signals,
+        };
+
+        *self.names.borrow_mut() = translated.iter()
+            .map(|signal| (signal.id, signal.name.clone()))
+            .collect();
+
+        Ok(translated)
+    }
+
+    fn rev_translate_ids(&self, signals: Self::IntoIdIter) -> Result<Self::IntoIdIter> {
+        Ok(signals)
+    }
+}
+
+impl Transform<usize> for LuaFilter {
+    type Value = LogicValue;
+
+    fn transform(&mut self, ids: &[usize], values: &mut CycleValues<Self::Value>) -> Result<()> {
+        let transform: Function = match self.transform {
+            Some(ref transform) => self.lua.registry_value(transform)?,
+            None => return Ok(()),
+        };
+
+        let names: Vec<String> = {
+            let names = self.names.borrow();
+            ids.iter()
+                .map(|id| names.get(id).cloned().unwrap_or_default())
+                .collect()
+        };
+        let rows = values.outer_iter()
+            .map(|row| row.iter()
+                .map(|value| value_to_lua(&self.lua, value))
+                .collect::<Result<Vec<_>>>())
+            .collect::<Result<Vec<_>>>()?;
+
+        let transformed: Option<Vec<Table>> = transform.call((names, rows))?;
+        if let Some(transformed) = transformed {
+            for (mut row, transformed_row) in values.outer_iter_mut().zip(transformed.iter()) {
+                for (i, target) in row.iter_mut().enumerate() {
+                    let value: Value = transformed_row.get(i + 1)?;
+                    value_from_lua(target, &value)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ConfigurePipeline for LuaFilter {}
+
+impl Filter<usize, LogicValue> for LuaFilter {}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::formatting::WaveFormat;
+    use ndarray::prelude::*;
+
+    #[test]
+    fn test_lua_filter() {
+        let lua = Rc::new(Lua::new());
+        let callbacks: Table = lua.load(r#"
+            return {
+                translate_signals = function(signals)
+                    local rv = {}
+                    for i = #signals, 1, -1 do
+                        local signal = signals[i]
+                        signal.name = string.upper(signal.name)
+                        rv[#rv + 1] = signal
+                    end
+                    return rv
+                end,
+                transform = function(names, rows)
+                    for _, row in ipairs(rows) do
+                        for i, name in ipairs(names) do
+                            if name == "B" and type(row[i]) == "number" then
+                                row[i] = row[i] + 1
+                            end
+                        end
+                    end
+                    return rows
+                end,
+            }
+        "#).eval().unwrap();
+
+        let mut filter = LuaFilter::new(lua.clone(),
+            callbacks.get("translate_signals").unwrap(),
+            callbacks.get("transform").unwrap()).unwrap();
+        let signals = vec![
+            Signal { id: 0, name: "a".to_string(), format: WaveFormat::Bit },
+            Signal { id: 1, name: "b".to_string(), format: WaveFormat::Vector(8) },
+        ];
+        let translated = filter.translate_signals(signals).unwrap();
+        assert_eq!(vec![1, 0], translated.iter().map(|s| s.id).collect::<Vec<_>>());
+        assert_eq!("B", translated[0].name);
+        assert!(translated[0].format == WaveFormat::Vector(8));
+
+        let mut x = LogicValue::new();
+        x.set_bit(0, LogicBit::X);
+        let mut values = Array2::from_shape_vec((2, 2), vec![
+            LogicValue::from(5), LogicValue::from(1),
+            x.clone(), LogicValue::from(0),
+        ]).unwrap();
+        filter.transform(&[1, 0], &mut values).unwrap();
+
+        assert_eq!(LogicValue::from(6), values[[0, 0]]);
+        assert_eq!(LogicValue::from(1), values[[0, 1]]);
+        assert_eq!(x, values[[1, 0]]);
+    }
+}
@@ -1,5 +1,3 @@
-use rug;
-
 use crate::error::*;
 use crate::data::*;
 use crate::formatting::WaveFormat;
@@ -31,10 +29,10 @@ impl<I> TranslateSignals<I> for RemoveComments {
 }
 
 
-impl Transform for RemoveComments {
-    type Value = rug::Integer;
+impl<I> Transform<I> for RemoveComments {
+    type Value = LogicValue;
 }
 
 impl ConfigurePipeline for RemoveComments {}
 
-impl<I> Filter<I, rug::Integer> for RemoveComments {}
+impl<I> Filter<I, LogicValue> for RemoveComments {}
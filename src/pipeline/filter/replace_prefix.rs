@@ -1,5 +1,3 @@
-use rug;
-
 use crate::error::*;
 use crate::data::*;
 
@@ -41,11 +39,11 @@ impl<I> TranslateSignals<I> for ReplacePrefix {
 }
 
 
-impl Transform for ReplacePrefix {
-    type Value = rug::Integer;
+impl<I> Transform<I> for ReplacePrefix {
+    type Value = LogicValue;
 }
 
 impl ConfigurePipeline for ReplacePrefix {}
 
-impl<I> Filter<I, rug::Integer> for ReplacePrefix {}
+impl<I> Filter<I, LogicValue> for ReplacePrefix {}
 
@@ -0,0 +1,127 @@
+use regex::RegexSet;
+
+use crate::error::*;
+use crate::data::*;
+use crate::formatting::WaveFormat;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+
+/// Shows matching signals a number of cycles later, or earlier if negative, e.g. to line up the
+/// stages of a pipeline
+///
+/// Shifted signals are renamed like `uut.s4.data@-3`. Cycles shifted in from outside of the trace
+/// are unknown.
+pub struct Shift {
+    patterns: RegexSet,
+    cycles: isize,
+    /// Widths of the shifted signals by id, set once signals are translated
+    widths: RefCell<Option<HashMap<usize, u32>>>,
+}
+
+
+impl Shift {
+    pub fn new<T: AsRef<str>>(patterns: &[T], cycles: isize) -> Result<Self> {
+        let patterns = RegexSet::new(patterns)?;
+
+        Ok(Self {
+            patterns,
+            cycles,
+            widths: RefCell::new(None),
+        })
+    }
+}
+
+
+impl TranslateSignals<usize> for Shift {
+    type IntoSigIter = Vec<Signal<usize>>;
+    type IntoIdIter = Vec<usize>;
+
+    fn translate_signals(&self, signals: Self::IntoSigIter) -> Result<Self::IntoSigIter> {
+        let mut widths = HashMap::new();
+        let shifted_signals = signals.into_iter()
+            .map(|mut signal| {
+                if signal.format != WaveFormat::Comment && self.patterns.is_match(&signal.name) {
+                    signal.name = format!("{}@{:+}", signal.name, self.cycles);
+                    widths.insert(signal.id, signal.format.width().unwrap_or(1));
+                }
+
+                signal
+            })
+            .collect();
+
+        *self.widths.borrow_mut() = Some(widths);
+        Ok(shifted_signals)
+    }
+
+    fn rev_translate_ids(&self, signals: Self::IntoIdIter) -> Result<Self::IntoIdIter> {
+        Ok(signals)
+    }
+}
+
+impl Transform<usize> for Shift {
+    type Value = LogicValue;
+}
+
+impl ConfigurePipeline for Shift {}
+
+impl Filter<usize, LogicValue> for Shift {
+    fn cycle_shift(&self, id: &usize) -> Result<Option<(isize, LogicValue)>> {
+        let widths = self.widths.borrow();
+        let widths = widths.as_ref()
+            .ok_or_else(|| Error::Internal("Shifted signals sampled before they were looked up".to_string()))?;
+
+        Ok(widths.get(id).map(|width| (self.cycles, LogicValue::unknown(*width))))
+    }
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::load::vcd::VcdLoader;
+    use crate::pipeline::Pipeline;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_shift() {
+        let dir = TempDir::new("viow-test").unwrap();
+        let mut vcd = "$timescale 1ns $end\n\
+            $scope module uut $end\n\
+            $var wire 8 ! s1 $end\n\
+            $var wire 8 \" s4 $end\n\
+            $upscope $end\n\
+            $enddefinitions $end\n".to_string();
+        // s4 follows s1 three cycles later
+        for i in 0..8 {
+            vcd.push_str(&format!("#{}\nb{:b} !\nb{:b} \"\n", i * 10, i, i.max(3) - 3));
+        }
+        vcd.push_str("#80\n");
+        let path = dir.path().join("pipe.vcd");
+        std::fs::write(&path, vcd).unwrap();
+
+        let loader = VcdLoader::new(path, Some(SimTime::from_ns(10))).unwrap();
+        let filter = Shift::new(&["s4"], -3).unwrap();
+        assert!(filter.cycle_shift(&1).is_err());
+        let mut pipe = Pipeline::new(Box::new(loader)).push(Box::new(filter));
+
+        let names: Vec<String> = pipe.query_signals().unwrap()
+            .into_iter()
+            .map(|signal| signal.name)
+            .collect();
+        assert_eq!(vec!["uut.s1", "uut.s4@-3"], names);
+        assert_eq!(8, pipe.query_cycle_count());
+
+        let times = SimTimeRange(SimTime::from_ns(20), SimTime::from_ns(80));
+        let data = pipe.sample(&vec![0, 1], &times).unwrap();
+        assert_eq!((6, 2), data.dim());
+        for row in 0..3 {
+            assert_eq!(data[[row, 0]], data[[row, 1]]);
+        }
+        // shifted in from beyond the end
+        assert!(data[[3, 1]].has_x());
+        assert_eq!(LogicValue::from(5), data[[3, 0]]);
+    }
+}
@@ -68,8 +68,8 @@ impl<I> TranslateSignals<I> for SignalList {
     }
 }
 
-impl Transform for SignalList {
-    type Value = rug::Integer;
+impl<I> Transform<I> for SignalList {
+    type Value = LogicValue;
 }
 
 impl ConfigurePipeline for SignalList {
@@ -86,4 +86,4 @@ impl ConfigurePipeline for SignalList {
     }
 }
 
-impl<I> Filter<I, rug::Integer> for SignalList {}
+impl<I> Filter<I, LogicValue> for SignalList {}
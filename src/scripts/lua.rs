@@ -14,29 +14,47 @@ use mlua::{
 use std::fs::File;
 use std::io::Read;
 use std::path::{Path,PathBuf};
+use std::rc::Rc;
 use std::stringify;
 
 macro_rules! add_global_function {
     ($lua:expr, $name:ident) => {
         $lua.globals().set(stringify!($name), $lua.create_function(api::$name)?)?;
-    }
+    };
+    // for names, that are keywords in Rust
+    ($lua:expr, $name:ident as $lua_name:literal) => {
+        $lua.globals().set($lua_name, $lua.create_function(api::$name)?)?;
+    };
+    // for functions, that keep the interpreter alive in the sources or filters they create
+    ($lua:expr, $name:ident with interpreter) => {
+        let interpreter = Rc::downgrade(&$lua);
+        $lua.globals().set(stringify!($name), $lua.create_function(move |_, args| {
+            let lua = interpreter.upgrade()
+                .ok_or_else(|| lua::Error::RuntimeError("Lua interpreter was dropped".to_string()))?;
+            api::$name(&lua, args)
+        })?)?;
+    };
 }
 
 pub struct LuaInterpreter {
-    lua: Lua,
+    /// Shared with the sources and filters defined in Lua, so they can call back into it, whenever
+    /// the wave is sampled.
+    lua: Rc<Lua>,
 }
 
 impl LuaInterpreter {
     pub fn new(config: impl AsRef<Config>, plugin_map: PluginMap) -> Result<Self> {
-        let lua = Lua::new();
+        let lua = Rc::new(Lua::new());
 
         Self::install_plugins(&lua, plugin_map)?;
+        Self::set_index_directory(&lua, &config)?;
         Self::configure_lua_path(&lua, config)?;
 
         let work_dir = std::env::current_dir()?;
         Self::set_working_directory(&lua, &work_dir)?;
 
         add_global_function!(lua, open);
+        add_global_function!(lua, open_parts);
         add_global_function!(lua, filter_signals);
         add_global_function!(lua, grep);
         add_global_function!(lua, ignore);
@@ -45,6 +63,22 @@ impl LuaInterpreter {
         add_global_function!(lua, pop_filter);
         add_global_function!(lua, replace_prefix);
         add_global_function!(lua, analog);
+        add_global_function!(lua, analog_auto);
+        add_global_function!(lua, format);
+        add_global_function!(lua, slice);
+        add_global_function!(lua, split);
+        add_global_function!(lua, concat);
+        add_global_function!(lua, enum_names as "enum");
+        add_global_function!(lua, reload);
+        add_global_function!(lua, lua_source with interpreter);
+        add_global_function!(lua, lua_filter with interpreter);
+        add_global_function!(lua, derive);
+        add_global_function!(lua, crop);
+        add_global_function!(lua, decimate);
+        add_global_function!(lua, resample);
+        add_global_function!(lua, shift);
+        add_global_function!(lua, diff);
+        add_global_function!(lua, merge);
 
         // Try to load viow.lua as entry to standard library. Silently ignore if not found.
         let chunk = lua.load("require('viow')")
@@ -72,6 +106,14 @@ impl LuaInterpreter {
         Ok(())
     }
 
+    fn set_index_directory(lua: &Lua, config: impl AsRef<Config>) -> Result<()> {
+        let index_dir = config.as_ref().get_index_dir()
+            .and_then(|dir| dir.to_str())
+            .map(|dir| dir.to_string());
+        lua.globals().set("_index_dir", index_dir)?;
+        Ok(())
+    }
+
     fn configure_lua_path(lua: &Lua, config: impl AsRef<Config>) -> Result<()> {
         let lua_path = config.as_ref().get_script_dir()
             .and_then(|script_path| script_path.to_str())
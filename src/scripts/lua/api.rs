@@ -1,40 +1,153 @@
 use super::*;
 use crate::pipeline::{SrcBox, filter};
 use crate::load::plugin::PluggedLoader;
+use crate::load::ghw::GhwLoader;
+use crate::load::fst::FstLoader;
+use crate::load::lua::LuaLoader;
+use crate::load::compressed;
+use crate::load::concat;
+use crate::load::vcd::{CycleGrid, ClockEdge};
+use crate::formatting::{EnumNames, Numeric, Scale};
 
-pub(super) fn open<'callback>(lua: &'callback Lua, args: (String, u64, String)) -> mlua::Result<Wave> {
-    let (filename, period, timeunit) = args;
+use rug::Integer;
+use std::collections::HashMap;
+
+/// Open a waveform file
+///
+/// The optional fourth argument is a table of options. For VCD files, `clock` names a signal
+/// whose edges start each cycle instead of the fixed period, and `edge` selects `"rising"` (the
+/// default) or `"falling"` edges. For VCD files and plugins, `sample_at` selects whether the
+/// value at the `"start"` or the last value before the `"end"` (the default) of each cycle is
+/// shown, and `sample_offset` shifts this sample point by the given number of time units.
+/// Uncompressed VCD files, that are still being written, are watched for new data with
+/// `follow = true`. With `pin = true`, the cursor also stays at the newest cycle. With
+/// `auto_reload = true`, the file is loaded again, when a new simulation run replaces it.
+pub(super) fn open<'callback>(lua: &'callback Lua, args: (String, u64, String, Option<mlua::Table<'callback>>)) -> mlua::Result<Wave> {
+    let (filename, period, timeunit, options) = args;
 
     let plugins: Plugins = lua.globals().get("_plugins")?;
     let work_dir: String = lua.globals().get("_cwd")?;
+    let index_dir: Option<String> = lua.globals().get("_index_dir")?;
 
     let mut path = PathBuf::from(work_dir);
     path.push(filename);
     let suffix = path.extension()
         .map(|ext| ext.to_string_lossy().to_string())
-        .ok_or(Error::UnknownFileFormat(path.to_string_lossy().to_string()))?;
+        .unwrap_or_default();
     //let suffix = filename.split('.').last()
         //.ok_or(Error::UnknownFileFormat(filename.clone()))?;
 
-    let cycle_time = SimTime::new(period, SimTimeUnit::from_string(timeunit)?);
+    let timeunit = SimTimeUnit::from_string(timeunit)?;
+    let cycle_time = SimTime::new(period, timeunit);
+    let mut clock: Option<String> = None;
+    let mut edge = ClockEdge::Rising;
+    let mut policy = SamplePolicy::End;
+    let mut offset: i64 = 0;
+    let mut follow = Follow::Off;
+    let mut auto_reload = false;
+    if let Some(options) = options {
+        clock = options.get("clock")?;
+        if let Some(edge_name) = options.get::<_, Option<String>>("edge")? {
+            edge = ClockEdge::from_string(edge_name)?;
+        }
+        if let Some(policy_name) = options.get::<_, Option<String>>("sample_at")? {
+            policy = SamplePolicy::from_string(policy_name)?;
+        }
+        offset = options.get::<_, Option<i64>>("sample_offset")?.unwrap_or(0);
+        auto_reload = options.get::<_, Option<bool>>("auto_reload")?.unwrap_or(false);
+        if options.get::<_, Option<bool>>("pin")?.unwrap_or(false) {
+            follow = Follow::Pinned;
+        } else if options.get::<_, Option<bool>>("follow")?.unwrap_or(false) {
+            follow = Follow::On;
+        }
+    }
+    let sampling = Sampling::new(policy, offset, timeunit);
+
     let loader: SrcBox;
 
-    if suffix == "vcd" {
+    if suffix == "vcd" || compressed::is_compressed_vcd(&path)? {
         //load_vcd(lua, (path, period, timeunit))
-        loader = Box::new(VcdLoader::new(path, Some(cycle_time))?);
+        let grid = match clock {
+            Some(clock) => CycleGrid::Clock(clock, edge),
+            None => CycleGrid::Period(Some(cycle_time)),
+        };
+
+        let mut vcd_loader = if follow != Follow::Off {
+            VcdLoader::follow(path, grid)?
+        } else if let Some(index_dir) = index_dir {
+            VcdLoader::with_index(path, grid, index_dir)?
+        } else {
+            VcdLoader::with_grid(path, grid)?
+        };
+        vcd_loader.set_sampling(sampling);
+        loader = Box::new(vcd_loader);
+    } else if clock.is_some() {
+        return Err(Error::InvalidArgument("Option 'clock' is only supported for VCD files".into()).into());
+    } else if follow != Follow::Off {
+        return Err(Error::InvalidArgument("Options 'follow' and 'pin' are only supported for VCD files".into()).into());
+    } else if (suffix == "fst" || suffix == "ghw") && (policy != SamplePolicy::End || offset != 0) {
+        return Err(Error::InvalidArgument("Options 'sample_at' and 'sample_offset' are only supported for VCD files and plugins".into()).into());
+    } else if suffix == "fst" {
+        loader = Box::new(FstLoader::new(path, Some(cycle_time))?);
+    } else if suffix == "ghw" {
+        loader = Box::new(GhwLoader::new(path, cycle_time)?);
     } else {
         if let Some(plugin) = plugins.plugin_map.get(&suffix) {
             let path_str = path.to_string_lossy();
-            loader = Box::new(PluggedLoader::new(plugin.clone(), path_str, cycle_time)?);
+            loader = Box::new(PluggedLoader::with_sampling(plugin.clone(), path_str, cycle_time, sampling)?);
         } else {
             return Err(Error::UnknownFileFormat(path.to_string_lossy().to_string()).into());
         }
     }
 
-    let new_wave = Wave::load(loader)?;
+    let mut new_wave = Wave::load(loader)?;
+    new_wave.set_follow(follow);
+    new_wave.set_auto_reload(auto_reload);
     Ok(new_wave)
 }
 
+/// Open several files of one run, e.g. split by checkpoints, and show them one after another
+///
+/// `files` is a list of file names in order, or a pattern like `"run_*.vcd"`, whose matches are
+/// sorted by name. All files need the same signals. The other arguments are those of `open`.
+pub(super) fn open_parts<'callback>(lua: &'callback Lua, args: (Value<'callback>, u64, String, Option<mlua::Table<'callback>>)) -> mlua::Result<Wave> {
+    let (files, period, timeunit, options) = args;
+
+    let filenames: Vec<String> = match files {
+        Value::String(pattern) => {
+            let work_dir: String = lua.globals().get("_cwd")?;
+            let pattern = Path::new(&work_dir).join(pattern.to_str()?);
+
+            concat::glob(pattern)?
+                .into_iter()
+                .map(|path| path.to_string_lossy().to_string())
+                .collect()
+        }
+        files => Vec::from_lua(files, lua)?,
+    };
+
+    let waves = filenames.into_iter()
+        .map(|filename| open(lua, (filename, period, timeunit.clone(), options.clone())))
+        .collect::<mlua::Result<Vec<_>>>()?;
+    let wave = Wave::concat(waves)?;
+
+    Ok(wave)
+}
+
+/// Use a Lua table as source of a wave
+///
+/// The table provides the methods `signals()`, `cycle_count()` and `sample(ids, first, last)`,
+/// see `LuaLoader`. Cycles are `period` long, in units of `timeunit`.
+pub(super) fn lua_source<'callback>(lua: &Rc<Lua>, args: (mlua::Table<'callback>, u64, String)) -> mlua::Result<Wave> {
+    let (source, period, timeunit) = args;
+
+    let cycle_time = SimTime::new(period, SimTimeUnit::from_string(timeunit)?);
+    let loader = Box::new(LuaLoader::new(lua.clone(), source, cycle_time)?);
+    let wave = Wave::load(loader)?;
+
+    Ok(wave)
+}
+
 pub(super) fn filter_signals<'callback>(_lua: &'callback Lua, args: (Wave, Vec<String>)) -> mlua::Result<Wave>
 {
     let (mut wave, signals) = args;
@@ -85,6 +198,12 @@ pub(super) fn remove_comments<'callback>(_lua: &'callback Lua, wave: Wave) -> ml
     Ok(wave)
 }
 
+/// Load the file of the wave again, keeping all filters and signal formats
+pub(super) fn reload<'callback>(_lua: &'callback Lua, mut wave: Wave) -> mlua::Result<Wave> {
+    wave.reload_source()?;
+    Ok(wave)
+}
+
 pub(super) fn pop_filter<'callback>(_lua: &'callback Lua, wave: Wave) -> mlua::Result<Wave> {
     let (wave, _) = wave.pop_filter()?;
     Ok(wave)
@@ -100,11 +219,208 @@ pub(super) fn replace_prefix<'callback>(_lua: &'callback Lua, args: (Wave, Strin
 }
 
 
-pub(super) fn analog<'callback>(_lua: &'callback Lua, args: (Wave, Vec<String>, f64, f64)) -> mlua::Result<Wave>
+/// Add signal `name`, computed from other signals by `expression`, see `Derive`
+pub(super) fn derive<'callback>(_lua: &'callback Lua, args: (Wave, String, String)) -> mlua::Result<Wave>
+{
+    let (wave, name, expression) = args;
+
+    let filter = Box::new(filter::Derive::new(name, &expression)?);
+    let wave = wave.push_filter(filter)?;
+
+    Ok(wave)
+}
+
+
+/// Keep the cycles, that start between `start` and `stop`, in units of `timeunit`
+pub(super) fn crop<'callback>(_lua: &'callback Lua, args: (Wave, u64, u64, String)) -> mlua::Result<Wave>
+{
+    let (wave, start, stop, timeunit) = args;
+
+    let timeunit = SimTimeUnit::from_string(timeunit)?;
+    let filter = Box::new(filter::Crop::new(SimTime::new(start, timeunit), SimTime::new(stop, timeunit))?);
+    let wave = wave.push_filter(filter)?;
+
+    Ok(wave)
+}
+
+/// Keep every `n`-th cycle
+pub(super) fn decimate<'callback>(_lua: &'callback Lua, args: (Wave, usize)) -> mlua::Result<Wave>
+{
+    let (wave, n) = args;
+
+    let filter = Box::new(filter::Decimate::new(filter::Decimation::Every(n))?);
+    let wave = wave.push_filter(filter)?;
+
+    Ok(wave)
+}
+
+/// Keep cycles at a coarser `period`, in units of `timeunit`
+pub(super) fn resample<'callback>(_lua: &'callback Lua, args: (Wave, u64, String)) -> mlua::Result<Wave>
+{
+    let (wave, period, timeunit) = args;
+
+    let period = SimTime::new(period, SimTimeUnit::from_string(timeunit)?);
+    let filter = Box::new(filter::Decimate::new(filter::Decimation::Period(period))?);
+    let wave = wave.push_filter(filter)?;
+
+    Ok(wave)
+}
+
+/// Show signals matching one of `patterns` `cycles` later, or earlier if negative, see `Shift`
+pub(super) fn shift<'callback>(_lua: &'callback Lua, args: (Wave, Vec<String>, isize)) -> mlua::Result<Wave>
+{
+    let (wave, patterns, cycles) = args;
+
+    let filter = Box::new(filter::Shift::new(&patterns, cycles)?);
+    let wave = wave.push_filter(filter)?;
+
+    Ok(wave)
+}
+
+/// Compare two waves cycle by cycle, matching their signals by name, see `DiffSource`
+pub(super) fn diff<'callback>(_lua: &'callback Lua, args: (Wave, Wave)) -> mlua::Result<Wave>
+{
+    let (wave_a, wave_b) = args;
+    let wave = Wave::diff(wave_a, wave_b)?;
+
+    Ok(wave)
+}
+
+/// Combine several waves into one, see `MergeSource`. The names of the signals of each wave get
+/// the prefix at the same position in `prefixes`. All waves are resampled onto the cycles of the
+/// first one.
+pub(super) fn merge<'callback>(_lua: &'callback Lua, args: (Vec<Wave>, Vec<String>)) -> mlua::Result<Wave>
+{
+    let (waves, prefixes) = args;
+
+    if waves.len() != prefixes.len() {
+        let msg = format!("merge got {} waves, but {} prefixes", waves.len(), prefixes.len());
+        return Err(Error::InvalidArgument(msg).into());
+    }
+    let wave = Wave::merge(prefixes.into_iter().zip(waves).collect())?;
+
+    Ok(wave)
+}
+
+/// Add a filter defined by the Lua functions `translate_signals` and `transform` in `callbacks`,
+/// see `LuaFilter`
+pub(super) fn lua_filter<'callback>(lua: &Rc<Lua>, args: (Wave, mlua::Table<'callback>)) -> mlua::Result<Wave> {
+    let (wave, callbacks) = args;
+
+    let filter = Box::new(filter::LuaFilter::new(lua.clone(),
+        callbacks.get("translate_signals")?,
+        callbacks.get("transform")?)?);
+    let wave = wave.push_filter(filter)?;
+
+    Ok(wave)
+}
+
+/// Show vectors matching one of `patterns` as analog waveform between `min` and `max`
+///
+/// The optional fifth argument tells how to read the vectors as numbers, see `Numeric::from_name`.
+/// It defaults to `"unsigned"`.
+pub(super) fn analog<'callback>(_lua: &'callback Lua, args: (Wave, Vec<String>, f64, f64, Option<String>)) -> mlua::Result<Wave>
+{
+    let (wave, patterns, min, max, numeric) = args;
+
+    let numeric = numeric_from_name(numeric)?;
+    let filter = Box::new(filter::Analog::new(&patterns, numeric, Scale::Fixed(min, max))?);
+    let wave = wave.push_filter(filter)?;
+
+    Ok(wave)
+}
+
+/// Like `analog`, but the range is taken from the values
+///
+/// The optional table of options selects the range over the values in view with
+/// `window = true`, instead of over all values of the trace. With `symmetric = true`, the range
+/// is symmetric around zero. `numeric` tells how to read the vectors as numbers, like the last
+/// argument of `analog`.
+pub(super) fn analog_auto<'callback>(_lua: &'callback Lua, args: (Wave, Vec<String>, Option<mlua::Table<'callback>>)) -> mlua::Result<Wave>
+{
+    let (wave, patterns, options) = args;
+
+    let mut window = false;
+    let mut symmetric = false;
+    let mut numeric = None;
+    if let Some(options) = options {
+        window = options.get::<_, Option<bool>>("window")?.unwrap_or(false);
+        symmetric = options.get::<_, Option<bool>>("symmetric")?.unwrap_or(false);
+        numeric = options.get("numeric")?;
+    }
+
+    let scale = if window {
+        Scale::Window { symmetric }
+    } else {
+        Scale::Trace { symmetric }
+    };
+    let filter = Box::new(filter::Analog::new(&patterns, numeric_from_name(numeric)?, scale)?);
+    let wave = wave.push_filter(filter)?;
+
+    Ok(wave)
+}
+
+fn numeric_from_name(name: Option<String>) -> Result<Numeric> {
+    match name {
+        Some(name) => Numeric::from_name(&name)
+            .ok_or_else(|| Error::InvalidArgument(format!("Unknown number format '{}'", name))),
+        None => Ok(Numeric::Unsigned),
+    }
+}
+
+/// Show vectors matching one of `patterns` in format `name`, see `WaveFormat::from_name`
+pub(super) fn format<'callback>(_lua: &'callback Lua, args: (Wave, Vec<String>, String)) -> mlua::Result<Wave>
+{
+    let (wave, patterns, name) = args;
+
+    let filter = Box::new(filter::Format::new(&patterns, &name)?);
+    let wave = wave.push_filter(filter)?;
+
+    Ok(wave)
+}
+
+/// Add bits `high` down to `low` of each vector matching `pattern` as a new signal
+pub(super) fn slice<'callback>(_lua: &'callback Lua, args: (Wave, String, u32, u32)) -> mlua::Result<Wave>
+{
+    let (wave, pattern, high, low) = args;
+
+    let filter = Box::new(filter::Slice::new(&pattern, high, low)?);
+    let wave = wave.push_filter(filter)?;
+
+    Ok(wave)
+}
+
+/// Show each vector matching one of `patterns` as its single bits
+pub(super) fn split<'callback>(_lua: &'callback Lua, args: (Wave, Vec<String>)) -> mlua::Result<Wave>
+{
+    let (wave, patterns) = args;
+
+    let filter = Box::new(filter::Split::new(&patterns)?);
+    let wave = wave.push_filter(filter)?;
+
+    Ok(wave)
+}
+
+/// Group bits like `x[0]`, `x[1]`, ... matching one of `patterns` into vectors
+pub(super) fn concat<'callback>(_lua: &'callback Lua, args: (Wave, Vec<String>)) -> mlua::Result<Wave>
+{
+    let (wave, patterns) = args;
+
+    let filter = Box::new(filter::Concat::new(&patterns)?);
+    let wave = wave.push_filter(filter)?;
+
+    Ok(wave)
+}
+
+/// Show signals matching `pattern` by the names of their values, given as a table like
+/// `{[0] = 'IDLE', [1] = 'REQ'}`
+pub(super) fn enum_names<'callback>(_lua: &'callback Lua, args: (Wave, String, HashMap<i64, String>)) -> mlua::Result<Wave>
 {
-    let (wave, patterns, min, max) = args;
+    let (wave, pattern, names) = args;
 
-    let filter = Box::new(filter::Analog::new(&patterns, min, max)?);
+    let names = EnumNames::new(names.into_iter()
+        .map(|(value, name)| (Integer::from(value), name)));
+    let filter = Box::new(filter::Enum::new(&pattern, names)?);
     let wave = wave.push_filter(filter)?;
 
     Ok(wave)
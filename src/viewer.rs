@@ -1,5 +1,6 @@
 use crate::error::*;
-use crate::formatting::build_waveform;
+use crate::data::LogicValue;
+use crate::formatting::{analog_range, build_waveform, format_range};
 use crate::wave::Wave;
 use crate::config::Config;
 
@@ -72,6 +73,9 @@ pub struct State {
 
     /// Readline editor
     line_editor: ReadlineEditor,
+
+    /// Message shown in the status line until the next key press
+    message: Option<String>,
 }
 
 impl State {
@@ -94,6 +98,7 @@ impl State {
             table_state: TableState::default(),
             zoom: 1,
             line_editor,
+            message: None,
         })
     }
 
@@ -110,9 +115,27 @@ impl State {
         self.wave_rows = wave_height as usize;
     }
 
+    /// Set size of data. A cursor outside of the data, e.g. after reloading a shorter file, is
+    /// moved to the last row or column.
     pub fn data_size(&mut self, rows: usize, cols: usize) {
         self.data_rows = rows;
         self.data_cols = cols;
+
+        if cols > 0 && self.cur_wave_col >= cols {
+            self.set_cur_wave_col(self.cur_wave_col);
+        }
+
+        if self.table_state.selected().is_some() && self.cur_wave_row >= rows {
+            self.set_cur_wave_row(Some(self.cur_wave_row));
+        }
+    }
+
+    pub fn set_message(&mut self, message: impl Into<String>) {
+        self.message = Some(message.into());
+    }
+
+    pub fn clear_message(&mut self) {
+        self.message = None;
     }
 
     pub fn get_mut_table_state(&mut self) -> &mut TableState {
@@ -427,6 +450,9 @@ pub fn build_table<'a>(wave: &'a mut Wave, state: &State) -> (u16, u16, Table<'a
     let cursor_style = Style::default()
         .bg(Color::Green)
         .add_modifier(Modifier::BOLD);
+    let mismatch_style = Style::default()
+        .fg(Color::White)
+        .bg(Color::Red);
 
 
     let mut rows = Vec::with_capacity(state.wave_rows);
@@ -443,19 +469,27 @@ pub fn build_table<'a>(wave: &'a mut Wave, state: &State) -> (u16, u16, Table<'a
         .unwrap();   // Can't report error, because called from tui drawing closure.
 
     for row_i in top..bot {
-        let signal_slice = wave_slice.signal_iter(row_i)
-            .unwrap();  // should not happen, due to for loop limits
-        let fmt = build_waveform(signal_slice, wave_slice.formatter(row_i), state.zoom);
-        let cur_cycle = (state.cur_wave_col - state.left_wave_col) * state.zoom;
-        let s_pre: String = fmt.chars().take(cur_cycle).collect();
-        let s_cur: String = fmt.chars().skip(cur_cycle).take(state.zoom).collect();
-        let s_post: String = fmt.chars().skip(cur_cycle+state.zoom).collect();
+        let signal_values: Vec<&LogicValue> = wave_slice.signal_iter(row_i)
+            .unwrap()   // should not happen, due to for loop limits
+            .collect();
+        let format = wave_slice.formatter(row_i);
+        let fmt = build_waveform(signal_values.iter().copied(), format, state.zoom, wave_slice.enums());
+        let cur_cycle = state.cur_wave_col - state.left_wave_col;
+        let mismatches: Vec<bool> = signal_values.iter()
+            .map(|value| value.has_mismatch())
+            .collect();
+        let cur_mismatch = mismatches.get(cur_cycle).copied().unwrap_or(false);
 
         let ref cur_style = if row_i % 2 == 0 { even_style } else { odd_style };
 
         let name = wave_slice.name(row_i).unwrap_or("⁇⁇⁇");
-        if name.len() as u16 > max_name_width {
-            max_name_width = name.len() as u16;
+        // show range of analog waveforms, so their height can be read
+        let name = match analog_range(signal_values.iter().copied(), format) {
+            Some(range) => format!("{} {}", name, format_range(range)),
+            None => name.to_string(),
+        };
+        if name.chars().count() as u16 > max_name_width {
+            max_name_width = name.chars().count() as u16;
         }
         let name_cell = Cell::from(name)
             .style(*cur_style);
@@ -466,13 +500,19 @@ pub fn build_table<'a>(wave: &'a mut Wave, state: &State) -> (u16, u16, Table<'a
             max_value_width = value_txt.len() as u16;
         }
         let value_cell = Cell::from(value_txt)
-            .style(*cur_style);
-
-        let wave_cell = Cell::from(Spans::from(vec![
-                Span::raw(s_pre),
-                Span::styled(s_cur, cursor_style),
-                Span::raw(s_post)
-            ]))
+            .style(if cur_mismatch { mismatch_style } else { *cur_style });
+
+        let styles = (0..mismatches.len())
+            .map(|cycle| {
+                if cycle == cur_cycle {
+                    Some(cursor_style)
+                } else if mismatches[cycle] {
+                    Some(mismatch_style)
+                } else {
+                    None
+                }
+            });
+        let wave_cell = Cell::from(Spans::from(waveform_spans(&fmt, styles, state.zoom)))
             .style(*cur_style);
 
         rows.push(Row::new(vec![name_cell, value_cell, wave_cell]));
@@ -494,18 +534,58 @@ pub fn build_table<'a>(wave: &'a mut Wave, state: &State) -> (u16, u16, Table<'a
     )
 }
 
+/// Split a waveform into spans of cycles with the same style. `styles` has the style of each
+/// cycle, `None` for the style of the row. Each cycle takes `zoom` characters.
+fn waveform_spans<T>(waveform: &str, styles: T, zoom: usize) -> Vec<Span<'static>>
+    where
+        T: Iterator<Item = Option<Style>>
+{
+    let mut chars = waveform.chars();
+    let mut spans = Vec::new();
+    let mut text = String::new();
+    let mut style = None;
+
+    for cycle_style in styles {
+        if cycle_style != style && !text.is_empty() {
+            spans.push(styled_span(std::mem::take(&mut text), style));
+        }
+        style = cycle_style;
+        text.extend(chars.by_ref().take(zoom));
+    }
+    if !text.is_empty() {
+        spans.push(styled_span(text, style));
+    }
+    // waveforms may be longer than the cycles, e.g. by a trailing label
+    let rest: String = chars.collect();
+    if !rest.is_empty() {
+        spans.push(Span::raw(rest));
+    }
+
+    spans
+}
+
+fn styled_span(text: String, style: Option<Style>) -> Span<'static> {
+    match style {
+        Some(style) => Span::styled(text, style),
+        None => Span::raw(text),
+    }
+}
+
 pub fn build_statusline(state: &State) -> Paragraph {
     let mode_txt = match state.mode {
         Mode::Normal => "  NORMAL",
         Mode::Insert(_) => "  INSERT",
     };
 
-    let line_txt = vec![
-        Spans::from(vec![
-            Span::raw(format!("Cursor: {},{}", state.cur_wave_row, state.cur_wave_col)),
-            Span::raw(mode_txt),
-        ])
+    let mut spans = vec![
+        Span::raw(format!("Cursor: {},{}", state.cur_wave_row, state.cur_wave_col)),
+        Span::raw(mode_txt),
     ];
+    if let Some(ref message) = state.message {
+        spans.push(Span::styled(format!("  {}", message), Style::default().fg(Color::Yellow)));
+    }
+
+    let line_txt = vec![Spans::from(spans)];
 
     Paragraph::new(line_txt)
 }
@@ -1,20 +1,29 @@
 mod cache;
+mod diff;
 mod pipeline_cid;
+mod watch;
 
 use cache::*;
+use diff::DiffSource;
 use pipeline_cid::PipelineCId;
+use watch::Watch;
 use crate::error::*;
-use crate::formatting::{WaveFormat,format_value};
+use crate::formatting::{WaveFormat,Scale,EnumTables,format_value,value_extent,extent_range};
 use crate::data::*;
 use crate::pipeline::*;
+use crate::load::concat::ConcatSource;
+use crate::load::merge::MergeSource;
 use crate::config::Config;
 
 use ndarray::prelude::*;
 use ndarray;
-use rug::Integer;
+use std::collections::HashMap;
 
 const SEARCH_HORIZON: usize = 1024;
 
+/// Number of cycles sampled at once, while finding the range of a trace
+const TRACE_RANGE_CHUNK: usize = 1 << 16;
+
 // Do not sample data on load or ever hold any data in Wave. Always defer to pipe to fetch
 // data:
 //  - ✓ replace slice_of_signal() with multi-id version, that samples the requested data from pipe,
@@ -23,14 +32,42 @@ const SEARCH_HORIZON: usize = 1024;
 //  - ✓ (opt) Add a LRU cache as pipeline stage on values using cycle and id as tag. Invalidate on
 //  reload. Limit in size.
 //  - ✓ VCD loader parses whole file, but only allocates data for requested range.
+
+/// How the viewer keeps up with data appended to the source
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Follow {
+    Off,
+    /// Extend the wave with new cycles
+    On,
+    /// Like `On`, but also keep the cursor at the last cycle, like `tail -f`
+    Pinned,
+}
+
+/// Least and largest value of an analog signal over the start of its trace
+#[derive(Clone, Copy, Debug)]
+struct TraceExtent {
+    extent: Option<(f64, f64)>,
+    /// Number of cycles covered
+    cycles: usize,
+}
+
 pub struct Wave 
 {
     formatters: Vec<WaveFormat>,
+    /// Formats given by the pipeline, before any were changed
+    default_formatters: Vec<WaveFormat>,
+    /// Extents of analog signals, that are scaled to their whole trace, by signal index
+    trace_extents: HashMap<usize, TraceExtent>,
+    /// Names of the enumerations, that the formats refer to
+    enums: EnumTables,
     names: Vec<String>,
     pipe: PipelineCId,
     config: PipelineConfig,
     num_signals: usize,
     cache: Cache,
+    follow: Follow,
+    /// Set, if the source is reloaded when its files change
+    watch: Option<Watch>,
 }
 
 impl Wave {
@@ -40,9 +77,29 @@ impl Wave {
         Self::load_from_pipe(pipe, config)
     }
 
-    fn load_from_pipe(mut pipe: PipelineCId, config: PipelineConfig) -> Result<Self> {
-        pipe.query_init()?;
-        let signals = pipe.query_signals()?;
+    fn load_from_pipe(pipe: PipelineCId, config: PipelineConfig) -> Result<Self> {
+        let mut wave = Self {
+            formatters: vec![],
+            default_formatters: vec![],
+            trace_extents: HashMap::new(),
+            enums: EnumTables::default(),
+            names: vec![],
+            pipe,
+            config,
+            num_signals: 0,
+            cache: Cache::new(1, 1, 1, 0, 0),
+            follow: Follow::Off,
+            watch: None,
+        };
+
+        wave.init()?;
+        Ok(wave)
+    }
+
+    /// Query signals from pipe and start with an empty cache
+    fn init(&mut self) -> Result<()> {
+        self.pipe.query_init()?;
+        let signals = self.pipe.query_signals()?;
         let num_signals = signals.len();
         let mut ids = Vec::with_capacity(signals.len());
         let mut names = Vec::with_capacity(signals.len());
@@ -54,7 +111,7 @@ impl Wave {
             formatters.push(format);
         }
 
-        let num_cycles = pipe.query_cycle_count();
+        let num_cycles = self.pipe.query_cycle_count();
         // TODO use config object
         let cache = Cache::new(128, 128, 1024, num_signals, num_cycles
             //config.wave_cache_capacity(),
@@ -62,14 +119,26 @@ impl Wave {
             //config.wave_cache_cycles_per_tile()
         );
 
-        Ok(Self {
-            formatters,
-            names,
-            pipe,
-            config,
-            num_signals,
-            cache
-        })
+        self.default_formatters = formatters.clone();
+        self.formatters = formatters;
+        self.trace_extents.clear();
+        self.enums = self.pipe.query_enums();
+        self.names = names;
+        self.num_signals = num_signals;
+        self.cache = cache;
+
+        Ok(())
+    }
+
+    /// Drop all signals, e.g. when they could not be queried from the pipeline
+    fn clear(&mut self) {
+        self.default_formatters.clear();
+        self.formatters.clear();
+        self.trace_extents.clear();
+        self.enums = EnumTables::default();
+        self.names.clear();
+        self.num_signals = 0;
+        self.cache = Cache::new(128, 128, 1024, 0, 0);
     }
 
     pub fn num_cycles(&self) -> usize {
@@ -80,6 +149,67 @@ impl Wave {
         self.num_signals
     }
 
+    pub fn follow(&self) -> Follow {
+        self.follow
+    }
+
+    pub fn set_follow(&mut self, follow: Follow) {
+        self.follow = follow;
+    }
+
+    /// Reload the source automatically, when its files change on disk.
+    pub fn set_auto_reload(&mut self, enable: bool) {
+        self.watch = if enable {
+            Some(Watch::new(self.pipe.query_files()))
+        } else {
+            None
+        };
+    }
+
+    pub fn auto_reload(&self) -> bool {
+        self.watch.is_some()
+    }
+
+    /// True, if the source is checked for new data from time to time
+    pub fn is_watching(&self) -> bool {
+        self.follow != Follow::Off || self.auto_reload()
+    }
+
+    /// True, if files of the source changed on disk since the last check and auto reload is
+    /// enabled. Files, that only grew while being followed, do not count as changed.
+    pub fn files_changed(&mut self) -> bool {
+        let following = self.follow != Follow::Off;
+
+        match self.watch.as_mut() {
+            Some(watch) => watch.changed(following),
+            None => false,
+        }
+    }
+
+    /// Load data appended to the source since it was loaded
+    ///
+    /// Returns true, if new data was found. Cached data is kept, except for the last cycles.
+    pub fn refresh(&mut self) -> Result<bool> {
+        if !self.pipe.query_refresh()? {
+            return Ok(false);
+        }
+
+        self.cache.set_num_cycles(self.pipe.query_cycle_count());
+        // the last cycle may have changed, like the last tile of the cache
+        for trace in self.trace_extents.values_mut() {
+            trace.cycles = trace.cycles.saturating_sub(1);
+        }
+
+        // range of real values may have grown
+        for (format, signal) in self.formatters.iter_mut().zip(self.pipe.query_signals()?) {
+            if let (WaveFormat::Real(..), WaveFormat::Real(..)) = (*format, signal.format) {
+                *format = signal.format;
+            }
+        }
+
+        Ok(true)
+    }
+
     /// Return an interval [left, right) of cycles from the wave
     //pub fn slice(&self, ids: std::ops::Range<usize>, cycles: std::ops::Range<usize>) -> Result<WaveSlice> {
         //let a = self.pipe.query_time(cycles.start);
@@ -101,6 +231,8 @@ impl Wave {
     /// LRU cache over blocks of data, e.g. 128x1024. Use sample to get those individually.
     /// Pick from cache and copy to WaveSlice.
     pub fn cached_slice(&mut self, ids: std::ops::Range<usize>, cycles: std::ops::Range<usize>) -> Result<WaveSlice> {
+        self.resolve_trace_ranges(ids.clone())?;
+
         let mut data = Array2::default((cycles.len(), ids.len()));
 
         for (i,id) in ids.clone().enumerate() {
@@ -111,20 +243,62 @@ impl Wave {
             data,
             names: &self.names,
             formatters: &self.formatters,
+            trace_extents: &self.trace_extents,
+            enums: &self.enums,
             cycles,
             ids
         })
     }
 
+    /// Extend the extents of analog signals among `ids`, that are scaled to their whole trace,
+    /// over the cycles added since they were last resolved.
+    ///
+    /// These cycles are sampled in chunks, bypassing the cache, so each one is read only once,
+    /// even while following a growing trace.
+    fn resolve_trace_ranges(&mut self, ids: std::ops::Range<usize>) -> Result<()> {
+        let num_cycles = self.pipe.query_cycle_count();
+
+        for id in ids {
+            let (size, numeric) = match self.formatters.get(id) {
+                Some(&WaveFormat::Analog(size, numeric, Scale::Trace { .. })) => (size, numeric),
+                _ => continue,
+            };
+            let trace = self.trace_extents.entry(id)
+                .or_insert(TraceExtent { extent: None, cycles: 0 });
+
+            while trace.cycles < num_cycles {
+                let end = std::cmp::min(trace.cycles + TRACE_RANGE_CHUNK, num_cycles);
+                let times = SimTimeRange(self.pipe.query_time(trace.cycles), self.pipe.query_time(end));
+                let data = self.pipe.sample(&vec![id], &times)?;
+
+                trace.extent = match (trace.extent, value_extent(data.iter(), size, numeric)) {
+                    (Some((min, max)), Some((chunk_min, chunk_max))) => {
+                        Some((min.min(chunk_min), max.max(chunk_max)))
+                    }
+                    (extent, chunk_extent) => extent.or(chunk_extent),
+                };
+                trace.cycles = end;
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn formatter(&self, signal_index: usize) -> WaveFormat {
         self.formatters[signal_index]
     }
 
     pub fn set_formatter(&mut self, signal_index: usize, format: WaveFormat) {
         self.formatters[signal_index] = format;
+        self.trace_extents.remove(&signal_index);
     }
 
-    pub fn value(&mut self, signal_index: usize, cycle: usize) -> Option<Integer> {
+    /// Format of a signal as given by the source and filters
+    pub fn default_formatter(&self, signal_index: usize) -> WaveFormat {
+        self.default_formatters[signal_index]
+    }
+
+    pub fn value(&mut self, signal_index: usize, cycle: usize) -> Option<LogicValue> {
         let wave_slice = self.cached_slice(signal_index..signal_index+1, cycle..cycle+1).ok()?;
         wave_slice.value(signal_index, cycle)
             .map(|x| x.clone())
@@ -134,7 +308,7 @@ impl Wave {
         self.value(signal_index, cycle)
             .map(|val| {
                 let format = self.formatters[signal_index];
-                format_value(&val, format)
+                format_value(&val, format, &self.enums)
             })
     }
 
@@ -149,12 +323,20 @@ impl Wave {
     }
 
     pub fn push_filter(self, filter: FilterBox) -> Result<Self> {
-        Self::load_from_pipe(self.pipe.push(filter), self.config)
+        let Self { pipe, config, follow, watch, .. } = self;
+        let mut new_self = Self::load_from_pipe(pipe.push(filter), config)?;
+        new_self.follow = follow;
+        new_self.watch = watch;
+
+        Ok(new_self)
     }
 
     pub fn pop_filter(self) -> Result<(Self, Option<FilterBox>)> {
-        let (pipe, filter) = self.pipe.pop();
-        let new_self = Self::load_from_pipe(pipe, self.config)?;
+        let Self { pipe, config, follow, watch, .. } = self;
+        let (pipe, filter) = pipe.pop();
+        let mut new_self = Self::load_from_pipe(pipe, config)?;
+        new_self.follow = follow;
+        new_self.watch = watch;
 
         Ok((new_self, filter))
     }
@@ -167,8 +349,73 @@ impl Wave {
         self.pipe.configure_pipeline(&self.config)
     }
 
-    pub fn reload(self) -> Result<Self> {
-        Self::load_from_pipe(self.pipe, self.config)
+    /// Compare two waves cycle by cycle, see `DiffSource`. Filters of both waves are kept.
+    pub fn diff(a: Wave, b: Wave) -> Result<Self> {
+        let source = DiffSource::new(a.pipe, b.pipe)?;
+        Self::load(Box::new(source))
+    }
+
+    /// Combine the sources of several waves, see `MergeSource`. The names of the signals of each
+    /// wave get the prefix given with it. Filters of the waves are dropped.
+    pub fn merge(waves: Vec<(String, Wave)>) -> Result<Self> {
+        let sources = waves.into_iter()
+            .map(|(prefix, wave)| (prefix, wave.pipe.into_source()))
+            .collect();
+        let source = MergeSource::new(sources)?;
+        Self::load(Box::new(source))
+    }
+
+    /// Show the sources of several waves one after another as one trace, see `ConcatSource`.
+    /// Filters of the waves are dropped. Following and reloading is taken from the last wave.
+    pub fn concat(waves: Vec<Wave>) -> Result<Self> {
+        let (follow, auto_reload) = match waves.last() {
+            Some(last) => (last.follow(), last.auto_reload()),
+            None => (Follow::Off, false),
+        };
+        let sources = waves.into_iter()
+            .map(|wave| wave.pipe.into_source())
+            .collect();
+
+        let mut wave = Self::load(Box::new(ConcatSource::new(sources)?))?;
+        wave.set_follow(follow);
+        wave.set_auto_reload(auto_reload);
+        Ok(wave)
+    }
+
+    pub fn reload(mut self) -> Result<Self> {
+        self.init()?;
+        Ok(self)
+    }
+
+    /// Load the source again, e.g. after a new simulation run replaced its file
+    ///
+    /// All filters stay in place. Formats set for a signal are kept, if the signal still exists
+    /// with the same width. If the source fails to load, the wave is left unchanged. If it loads,
+    /// but the filters fail on its signals, e.g. because a signal they use is gone, the wave is
+    /// left without signals. Either way, watched files still count as changed.
+    pub fn reload_source(&mut self) -> Result<()> {
+        self.pipe.query_reload()?;
+
+        let formats: HashMap<String, WaveFormat> = self.names.iter()
+            .cloned()
+            .zip(self.formatters.iter().copied())
+            .collect();
+        if let Err(err) = self.init() {
+            // the signals of the old source are gone
+            self.clear();
+            return Err(err);
+        }
+
+        for (name, format) in self.names.iter().zip(self.formatters.iter_mut()) {
+            if let Some(old) = formats.get(name) {
+                *format = keep_format(*old, *format);
+            }
+        }
+
+        if let Some(watch) = self.watch.as_mut() {
+            watch.reloaded();
+        }
+        Ok(())
     }
 
     /// Find the next transition for a single signal
@@ -224,13 +471,73 @@ impl Wave {
         //let wave_slice = self.cached_slice(signal_index..signal_index+1, 0..start_cycle+1).ok()?;
         //wave_slice.prev_transition(signal_index, start_cycle)
     }
+
+    /// Find the first cycle, in which the waves of a diff differ
+    pub fn cached_first_divergence(&mut self) -> Option<usize> {
+        self.cached_find_divergence(0)
+    }
+
+    /// Find the next cycle after `start_cycle`, in which the waves of a diff start to differ
+    ///
+    /// Cycles directly following a mismatch are skipped, so each divergence is found once.
+    pub fn cached_next_divergence(&mut self, start_cycle: usize) -> Option<usize> {
+        self.cached_find_divergence(start_cycle + 1)
+    }
+
+    /// First cycle from `start_cycle` on, that has values flagged as mismatch, while the
+    /// previous cycle has none.
+    fn cached_find_divergence(&mut self, mut start_cycle: usize) -> Option<usize> {
+        let num_signals = self.num_signals();
+        let num_cycles = self.num_cycles();
+
+        while start_cycle < num_cycles {
+            // include the previous cycle, to know whether a divergence starts at `start_cycle`
+            let first = start_cycle.saturating_sub(1);
+            let horizon = std::cmp::min(start_cycle + SEARCH_HORIZON, num_cycles);
+            let wave_slice = self.cached_slice(0..num_signals, first..horizon).ok()?;
+
+            let mismatches: Vec<bool> = (first..horizon)
+                .map(|cycle| {
+                    (0..num_signals).any(|i| {
+                        wave_slice.value(i, cycle).is_some_and(|value| value.has_mismatch())
+                    })
+                })
+                .collect();
+
+            for cycle in start_cycle..horizon {
+                let i = cycle - first;
+                if mismatches[i] && (cycle == 0 || !mismatches[i - 1]) {
+                    return Some(cycle);
+                }
+            }
+
+            start_cycle = horizon;
+        }
+
+        None
+    }
+}
+
+/// Format of a reloaded signal, that was shown with format `old` before
+fn keep_format(old: WaveFormat, new: WaveFormat) -> WaveFormat {
+    use WaveFormat::*;
+
+    match (old, new) {
+        (Vector(a), Vector(b)) | (BitVector(a), Vector(b)) | (Octal(a), Vector(b))
+        | (Number(a, _), Vector(b)) | (Analog(a, ..), Vector(b)) if a == b => old,
+        (Vector(a), Enum(b, _)) | (BitVector(a), Enum(b, _)) | (Octal(a), Enum(b, _))
+        | (Number(a, _), Enum(b, _)) if a == b => old,
+        _ => new,
+    }
 }
 
 /// Owns data of a collection of signals in an interval of cycles
 pub struct WaveSlice<'a> {
-    data: Array2<Integer>,
+    data: Array2<LogicValue>,
     names: &'a Vec<String>,
     formatters: &'a Vec<WaveFormat>,
+    trace_extents: &'a HashMap<usize, TraceExtent>,
+    enums: &'a EnumTables,
     cycles: std::ops::Range<usize>,
     ids: std::ops::Range<usize>,
 }
@@ -250,8 +557,25 @@ impl<'a> WaveSlice<'a> {
         }
     }
 
+    /// Format of a signal. Analog signals scaled to their whole trace get their range.
     pub fn formatter(&self, signal_index: usize) -> WaveFormat {
-        self.formatters[signal_index]
+        match self.formatters[signal_index] {
+            WaveFormat::Analog(size, numeric, Scale::Trace { symmetric }) => {
+                match self.trace_extents.get(&signal_index) {
+                    Some(trace) => {
+                        let (min, max) = extent_range(trace.extent, symmetric);
+                        WaveFormat::Analog(size, numeric, Scale::Fixed(min, max))
+                    }
+                    None => self.formatters[signal_index],
+                }
+            }
+            format => format,
+        }
+    }
+
+    /// Names of the enumerations, that the formats refer to
+    pub fn enums(&self) -> &'a EnumTables {
+        self.enums
     }
 
     pub fn name(&self, signal_index: usize) -> Option<&'a str> {
@@ -260,7 +584,7 @@ impl<'a> WaveSlice<'a> {
             .map(|s| s.as_str())
     }
 
-    pub fn value(&self, signal_index: usize, cycle: usize) -> Option<&Integer> {
+    pub fn value(&self, signal_index: usize, cycle: usize) -> Option<&LogicValue> {
         self.data.get([cycle - self.cycles.start, signal_index - self.ids.start])
     }
 
@@ -268,7 +592,7 @@ impl<'a> WaveSlice<'a> {
         self.value(signal_index, cycle)
             .map(|val| {
                 let format = self.formatters[signal_index];
-                format_value(&val, format)
+                format_value(val, format, self.enums)
             })
     }
 
@@ -322,7 +646,7 @@ impl<'a> WaveSlice<'a> {
 
 /// Iterator over data belonging to a single signal
 pub struct SliceIter<'a> {
-    data: &'a Array2<Integer>,
+    data: &'a Array2<LogicValue>,
     ptr: usize,
     end: usize,
     signal_index: usize,
@@ -330,7 +654,7 @@ pub struct SliceIter<'a> {
 
 
 impl<'a> Iterator for SliceIter<'a> {
-    type Item = &'a Integer;
+    type Item = &'a LogicValue;
 
     fn next(&mut self) -> Option<Self::Item> {
         let rv;
@@ -366,18 +690,22 @@ mod test {
         assert_eq!(16, wave.num_signals());
         assert_eq!(211, wave.num_cycles());
 
-        assert_eq!(Some(Integer::from(0)), wave.value(7, 0));
-        assert_eq!(Some(Integer::from(1)), wave.value(7, 1));
-        assert_eq!(Some(Integer::from(1)), wave.value(7, 40));
-        assert_eq!(Some(Integer::from(0)), wave.value(7, 41));
+        // nothing is assigned before the first timestamp
+        let mut unknown = LogicValue::new();
+        unknown.set_bit(0, LogicBit::X);
+
+        assert_eq!(Some(unknown.clone()), wave.value(7, 0));
+        assert_eq!(Some(LogicValue::from(1)), wave.value(7, 1));
+        assert_eq!(Some(LogicValue::from(1)), wave.value(7, 40));
+        assert_eq!(Some(LogicValue::from(0)), wave.value(7, 41));
 
         let wave_slice = wave.cached_slice(0..wave.num_signals(), 0..wave.num_cycles()).unwrap();
         let col = wave_slice.data.column(7);
 
-        assert_eq!(Integer::from(0), col[0]);
-        assert_eq!(Integer::from(1), col[1]);
-        assert_eq!(Integer::from(1), col[40]);
-        assert_eq!(Integer::from(0), col[41]);
+        assert_eq!(unknown, col[0]);
+        assert_eq!(LogicValue::from(1), col[1]);
+        assert_eq!(LogicValue::from(1), col[40]);
+        assert_eq!(LogicValue::from(0), col[41]);
     }
 
     #[test]
@@ -391,6 +719,115 @@ mod test {
         assert_eq!(Some(0), wave.cached_prev_transition(7, 40));
     }
 
+    #[test]
+    fn test_reload_source() {
+        let mut wave = make_test_wave()
+            .expect("Failed to load test wave data");
+
+        let width = match wave.formatter(5) {
+            WaveFormat::Vector(width) => width,
+            _ => panic!("Expected a vector"),
+        };
+        wave.set_formatter(5, WaveFormat::BitVector(width));
+        wave.value(5, 13);
+
+        wave.reload_source().unwrap();
+        assert_eq!(16, wave.num_signals());
+        assert_eq!(211, wave.num_cycles());
+        assert!(wave.formatter(5) == WaveFormat::BitVector(width));
+        assert_eq!(Some(LogicValue::from(2)), wave.value(5, 13));
+
+        assert!(keep_format(WaveFormat::BitVector(8), WaveFormat::Vector(16)) == WaveFormat::Vector(16));
+        assert!(keep_format(WaveFormat::BitVector(8), WaveFormat::Bit) == WaveFormat::Bit);
+    }
+
+    #[test]
+    fn test_reload_source_failed() {
+        use crate::pipeline::filter::Derive;
+        use tempdir::TempDir;
+
+        let dir = TempDir::new("viow-test").unwrap();
+        let path = dir.path().join("run.vcd");
+        let vcd = |vars: &str| format!("$timescale 1ns $end\n\
+            $scope module tb $end\n{}$upscope $end\n\
+            $enddefinitions $end\n#0\n1!\n1\"\n#10\n", vars);
+        let both = "$var wire 1 ! valid $end\n$var wire 1 \" ready $end\n";
+        std::fs::write(&path, vcd(both)).unwrap();
+
+        let loader = VcdLoader::new(&path, Some(SimTime::from_ns(10))).unwrap();
+        let filter = Derive::new("fire", "tb.valid && tb.ready").unwrap();
+        let mut wave = Wave::load(Box::new(loader)).unwrap()
+            .push_filter(Box::new(filter)).unwrap();
+        assert_eq!(3, wave.num_signals());
+
+        // the derived signal can not be computed without one of its inputs
+        std::fs::write(&path, vcd("$var wire 1 ! valid $end\n")).unwrap();
+        assert!(wave.reload_source().is_err());
+        assert_eq!(0, wave.num_signals());
+        assert!(wave.get_names().is_empty());
+
+        std::fs::write(&path, vcd(both)).unwrap();
+        wave.reload_source().unwrap();
+        assert_eq!(3, wave.num_signals());
+        assert_eq!(Some(LogicValue::from(1)), wave.value(2, 0));
+    }
+
+    #[test]
+    fn test_trace_range() {
+        use crate::formatting::Numeric;
+
+        let mut wave = make_test_wave()
+            .expect("Failed to load test wave data");
+
+        let width = wave.formatter(5).width().unwrap();
+        let num_cycles = wave.num_cycles();
+        let known: Vec<f64> = wave.cached_slice(5..6, 0..num_cycles).unwrap()
+            .signal_iter(5)
+            .unwrap()
+            .filter(|value| value.is_known())
+            .map(|value| value.to_f64())
+            .collect();
+        let min = known.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = known.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+        wave.set_formatter(5, WaveFormat::Analog(width, Numeric::Unsigned, Scale::Trace { symmetric: false }));
+        let wave_slice = wave.cached_slice(5..6, 0..10).unwrap();
+        assert!(wave_slice.formatter(5) == WaveFormat::Analog(width, Numeric::Unsigned, Scale::Fixed(min, max)));
+        assert!(wave.formatter(5) == WaveFormat::Analog(width, Numeric::Unsigned, Scale::Trace { symmetric: false }));
+    }
+
+    #[test]
+    fn test_trace_range_follow() {
+        use crate::formatting::Numeric;
+        use crate::load::vcd::CycleGrid;
+        use std::io::Write;
+        use tempdir::TempDir;
+
+        let dir = TempDir::new("viow-test").unwrap();
+        let path = dir.path().join("growing.vcd");
+        std::fs::write(&path, "$timescale 1ns $end\n\
+            $scope module tb $end\n\
+            $var wire 8 ! level $end\n\
+            $upscope $end\n\
+            $enddefinitions $end\n\
+            #0\nb11 !\n#10\nb101 !\n#20\n").unwrap();
+
+        let loader = VcdLoader::follow(&path, CycleGrid::Period(Some(SimTime::from_ns(10)))).unwrap();
+        let mut wave = Wave::load(Box::new(loader)).unwrap();
+        let format = WaveFormat::Analog(8, Numeric::Unsigned, Scale::Trace { symmetric: false });
+        wave.set_formatter(0, format);
+        let wave_slice = wave.cached_slice(0..1, 0..1).unwrap();
+        assert!(wave_slice.formatter(0) == WaveFormat::Analog(8, Numeric::Unsigned, Scale::Fixed(3.0, 5.0)));
+
+        // extended by the appended cycles
+        let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"b1 !\n#30\nb1001 !\n#40\n").unwrap();
+        assert!(wave.refresh().unwrap());
+        assert_eq!(4, wave.num_cycles());
+        let wave_slice = wave.cached_slice(0..1, 0..1).unwrap();
+        assert!(wave_slice.formatter(0) == WaveFormat::Analog(8, Numeric::Unsigned, Scale::Fixed(1.0, 9.0)));
+    }
+
     #[test]
     fn test_wave_slice() {
         let mut wave = make_test_wave()
@@ -401,10 +838,10 @@ mod test {
             .unwrap()
             .collect();
 
-        assert_eq!(Integer::from(0), *data[0]);
-        assert_eq!(Integer::from(1), *data[1]);
-        assert_eq!(Integer::from(1), *data[40]);
-        assert_eq!(Integer::from(0), *data[41]);
+        assert!(data[0].has_x());
+        assert_eq!(LogicValue::from(1), *data[1]);
+        assert_eq!(LogicValue::from(1), *data[40]);
+        assert_eq!(LogicValue::from(0), *data[41]);
 
 
         let wave_slice = wave.cached_slice(0..8, 39..53).unwrap();
@@ -412,7 +849,7 @@ mod test {
             .unwrap()
             .collect();
 
-        assert_eq!(Integer::from(1), *data[1]);
-        assert_eq!(Integer::from(0), *data[2]);
+        assert_eq!(LogicValue::from(1), *data[1]);
+        assert_eq!(LogicValue::from(0), *data[2]);
     }
 }
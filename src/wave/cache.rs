@@ -3,7 +3,7 @@ use super::*;
 use lru::LruCache;
 use std::ops::Range;
 
-#[derive(PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
 struct CacheIndex {
     x: usize,
     y: usize,
@@ -11,7 +11,7 @@ struct CacheIndex {
 
 #[derive(Debug)]
 struct CacheTile {
-    data: Array2<Integer>,
+    data: Array2<LogicValue>,
 }
 
 pub(super) struct Cache {
@@ -39,6 +39,25 @@ impl Cache {
         }
     }
 
+    /// Change number of cycles, after data was appended to the source.
+    ///
+    /// Only tiles at the end of the data are dropped, since they may be incomplete or sampled
+    /// from values that have changed since.
+    pub(super) fn set_num_cycles(&mut self, num_cycles: usize) {
+        let first_stale = self.num_cycles.saturating_sub(1) / self.cycles_per_tile;
+        let stale: Vec<CacheIndex> = self.cache.iter()
+            .map(|(index, _)| index)
+            .filter(|index| index.y >= first_stale)
+            .cloned()
+            .collect();
+
+        for index in stale.iter() {
+            self.cache.pop(index);
+        }
+
+        self.num_cycles = num_cycles;
+    }
+
     fn tile_index(&self, id: usize, cycle: usize) -> CacheIndex {
         CacheIndex {
             x: id / self.signals_per_tile,
@@ -78,7 +97,7 @@ impl Cache {
         }
     }
 
-    pub(super) fn get(&mut self, pipe: &mut PipelineCId, id: usize, cycle_range: Range<usize>) -> Array1<Integer> {
+    pub(super) fn get(&mut self, pipe: &mut PipelineCId, id: usize, cycle_range: Range<usize>) -> Array1<LogicValue> {
         debug_assert!(id < self.num_signals);
         debug_assert!(cycle_range.end <= self.num_cycles);
 
@@ -160,26 +179,46 @@ mod test {
 
         let needle = cache.get(&mut pipe, 7, 0..50);
 
-        assert_eq!(Integer::from(0), needle[0]);
+        assert_eq!(LogicBit::X, needle[0].bit(0));
         for i in 1..40 {
-            assert_eq!(Integer::from(1), needle[i]);
+            assert_eq!(LogicValue::from(1), needle[i]);
         }
-        assert_eq!(Integer::from(0), needle[41]);
+        assert_eq!(LogicValue::from(0), needle[41]);
 
-        assert_eq!(Integer::from(2), cache.get(&mut pipe, 5, 0..20)[13]);
-        assert_eq!(Integer::from(3), cache.get(&mut pipe, 5, 0..16)[15]);
-        assert_eq!(Integer::from(7), cache.get(&mut pipe, 5, 0..24)[23]);
-        assert_eq!(Integer::from(7), cache.get(&mut pipe, 5, 10..24)[13]);
-        assert_eq!(Integer::from(7), cache.get(&mut pipe, 5, 23..24)[0]);
+        assert_eq!(LogicValue::from(2), cache.get(&mut pipe, 5, 0..20)[13]);
+        assert_eq!(LogicValue::from(3), cache.get(&mut pipe, 5, 0..16)[15]);
+        assert_eq!(LogicValue::from(7), cache.get(&mut pipe, 5, 0..24)[23]);
+        assert_eq!(LogicValue::from(7), cache.get(&mut pipe, 5, 10..24)[13]);
+        assert_eq!(LogicValue::from(7), cache.get(&mut pipe, 5, 23..24)[0]);
 
         for i in 0..15 {
             cache.get(&mut pipe, i, 0..200);
         }
 
-        assert_eq!(Integer::from(2), cache.get(&mut pipe, 5, 0..20)[13]);
-        assert_eq!(Integer::from(3), cache.get(&mut pipe, 5, 0..16)[15]);
-        assert_eq!(Integer::from(7), cache.get(&mut pipe, 5, 0..24)[23]);
-        assert_eq!(Integer::from(7), cache.get(&mut pipe, 5, 10..24)[13]);
-        assert_eq!(Integer::from(7), cache.get(&mut pipe, 5, 23..24)[0]);
+        assert_eq!(LogicValue::from(2), cache.get(&mut pipe, 5, 0..20)[13]);
+        assert_eq!(LogicValue::from(3), cache.get(&mut pipe, 5, 0..16)[15]);
+        assert_eq!(LogicValue::from(7), cache.get(&mut pipe, 5, 0..24)[23]);
+        assert_eq!(LogicValue::from(7), cache.get(&mut pipe, 5, 10..24)[13]);
+        assert_eq!(LogicValue::from(7), cache.get(&mut pipe, 5, 23..24)[0]);
+    }
+
+    #[test]
+    fn test_set_num_cycles() {
+        let loader = Box::new(VcdLoader::new("examples/verilator.vcd", Some(SimTime::from_ps(1))).unwrap());
+        let mut pipe = PipelineCId::new(loader).unwrap();
+        pipe.query_init().unwrap();
+        let num_signals = pipe.query_signals().unwrap().len();
+        let num_cycles = pipe.query_cycle_count();
+        let mut cache = Cache::new(16, 4, 50, num_signals, num_cycles);
+
+        for i in 0..8 {
+            cache.get(&mut pipe, i, 0..num_cycles);
+        }
+        assert_eq!(10, cache.cache.len());
+
+        // only tiles containing the last cycle are dropped
+        cache.set_num_cycles(num_cycles + 10);
+        assert_eq!(8, cache.cache.len());
+        assert_eq!(LogicValue::from(0), cache.get(&mut pipe, 7, 0..50)[41]);
     }
 }
@@ -0,0 +1,296 @@
+use super::pipeline_cid::PipelineCId;
+use crate::data::*;
+use crate::error::*;
+use crate::formatting::{EnumTables, WaveFormat};
+
+use ndarray::prelude::*;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Which part of a compared pair of signals is shown
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Side {
+    A,
+    B,
+    /// Set, where both differ
+    Mismatch,
+}
+
+struct DiffSignal {
+    name: String,
+    format: WaveFormat,
+    side: Side,
+    /// Id of the compared signal in wave a
+    a: usize,
+    /// Id of the compared signal in wave b
+    b: usize,
+}
+
+/// Compares two waves cycle by cycle
+///
+/// Signals are matched by name. Each pair is shown as `a:<name>` and `b:<name>`, followed by the
+/// bit `diff:<name>`, that is set in cycles, in which both differ. All three are flagged as
+/// mismatch in these cycles. Signals found in only one of the waves are left out. The diff covers
+/// the cycles present in both waves, timed like wave a.
+pub(super) struct DiffSource {
+    a: PipelineCId,
+    b: PipelineCId,
+    signals: Vec<DiffSignal>,
+    signal_names: HashMap<String, usize>,
+}
+
+impl DiffSource {
+    pub(super) fn new(a: PipelineCId, b: PipelineCId) -> Result<Self> {
+        let mut rv = Self {
+            a,
+            b,
+            signals: vec![],
+            signal_names: HashMap::new(),
+        };
+
+        rv.init()?;
+        Ok(rv)
+    }
+
+    /// Match the signals of both waves
+    fn init(&mut self) -> Result<()> {
+        self.a.query_init()?;
+        self.b.query_init()?;
+
+        let b_signals: HashMap<String, (usize, WaveFormat)> = self.b.query_signals()?
+            .into_iter()
+            .map(|signal| (signal.name, (signal.id, signal.format)))
+            .collect();
+
+        let mut signals = Vec::new();
+        for signal in self.a.query_signals()? {
+            if signal.format == WaveFormat::Comment {
+                continue;
+            }
+
+            let (b, b_format) = match b_signals.get(&signal.name) {
+                Some(&(b, b_format)) => (b, b_format),
+                None => continue,
+            };
+
+            let parts = vec![
+                ("a:", Side::A, signal.format),
+                ("b:", Side::B, b_format),
+                ("diff:", Side::Mismatch, WaveFormat::Bit),
+            ];
+            for (prefix, side, format) in parts {
+                signals.push(DiffSignal {
+                    name: format!("{}{}", prefix, signal.name),
+                    format,
+                    side,
+                    a: signal.id,
+                    b,
+                });
+            }
+        }
+
+        self.signal_names = signals.iter()
+            .enumerate()
+            .map(|(i, signal)| (signal.name.clone(), i))
+            .collect();
+        self.signals = signals;
+
+        Ok(())
+    }
+}
+
+/// Sample `ids` in the cycles from `first` up to, but not including `last`. Returns the values and
+/// the column of each id.
+fn sample_cycles(pipe: &mut PipelineCId, mut ids: Vec<usize>, first: usize, last: usize)
+    -> Result<(CycleValues<LogicValue>, HashMap<usize, usize>)>
+{
+    ids.sort_unstable();
+    ids.dedup();
+
+    let times = SimTimeRange(pipe.query_time(first), pipe.query_time(last));
+    let values = pipe.sample(&ids, &times)?;
+    let columns = ids.into_iter()
+        .enumerate()
+        .map(|(column, id)| (id, column))
+        .collect();
+
+    Ok((values, columns))
+}
+
+impl QuerySource for DiffSource {
+    type Id = String;
+    type IntoSignalIter = Vec<Signal<Self::Id>>;
+
+    fn query_signals(&self) -> Result<Self::IntoSignalIter> {
+        let rv = self.signals.iter()
+            .map(|signal| Signal {
+                id: signal.name.clone(),
+                name: signal.name.clone(),
+                format: signal.format,
+            })
+            .collect();
+
+        Ok(rv)
+    }
+
+    fn query_time_range(&self) -> Result<SimTimeRange> {
+        let start = self.a.query_time(0);
+        let stop = self.a.query_time(self.query_cycle_count());
+
+        Ok(SimTimeRange(start, stop))
+    }
+
+    fn query_time(&self, cycle: usize) -> SimTime {
+        self.a.query_time(cycle)
+    }
+
+    fn query_cycle_count(&self) -> usize {
+        std::cmp::min(self.a.query_cycle_count(), self.b.query_cycle_count())
+    }
+
+    fn query_refresh(&mut self) -> Result<bool> {
+        let a = self.a.query_refresh()?;
+        let b = self.b.query_refresh()?;
+
+        Ok(a || b)
+    }
+
+    fn query_reload(&mut self) -> Result<()> {
+        self.a.query_reload()?;
+        self.b.query_reload()?;
+        self.init()
+    }
+
+    fn query_files(&self) -> Vec<PathBuf> {
+        let mut files = self.a.query_files();
+        files.extend(self.b.query_files());
+        files
+    }
+
+    fn query_enums(&self) -> EnumTables {
+        let mut enums = self.a.query_enums();
+        enums.extend(self.b.query_enums());
+        enums
+    }
+}
+
+impl LookupId for DiffSource {
+    type FromId = String;
+    type ToId = usize;
+
+    fn lookup_id(&self, id: &Self::FromId) -> Result<Self::ToId> {
+        self.signal_names.get(id)
+            .copied()
+            .ok_or_else(|| Error::NotFound(id.clone()))
+    }
+
+    fn rev_lookup_id(&self, id: &Self::ToId) -> Result<Self::FromId> {
+        if *id < self.signals.len() {
+            Ok(self.signals[*id].name.clone())
+        } else {
+            Err(Error::IdOutOfRange(*id, 0..self.signals.len()))
+        }
+    }
+}
+
+impl Sample for DiffSource {
+    type Id = String;
+    type Value = LogicValue;
+
+    fn sample(
+        &mut self,
+        ids: &Vec<Self::Id>,
+        times: &SimTimeRange,
+    ) -> Result<CycleValues<Self::Value>> {
+        let first = self.query_cycle(times.0);
+        let last = self.query_cycle(times.1).max(first);
+
+        let indices = ids.iter()
+            .map(|id| self.lookup_id(id))
+            .collect::<Result<Vec<_>>>()?;
+        let a_ids = indices.iter().map(|i| self.signals[*i].a).collect();
+        let b_ids = indices.iter().map(|i| self.signals[*i].b).collect();
+        let (a_values, a_columns) = sample_cycles(&mut self.a, a_ids, first, last)?;
+        let (b_values, b_columns) = sample_cycles(&mut self.b, b_ids, first, last)?;
+
+        let mut data = Array2::default((last - first, ids.len()));
+        for (column, i) in indices.iter().enumerate() {
+            let signal = &self.signals[*i];
+            let a_column = a_values.column(a_columns[&signal.a]);
+            let b_column = b_values.column(b_columns[&signal.b]);
+
+            for (row, target) in data.column_mut(column).iter_mut().enumerate() {
+                let a = a_column.get(row);
+                let b = b_column.get(row);
+                let mismatch = a != b;
+
+                let mut value = match signal.side {
+                    Side::A => a.cloned().unwrap_or_default(),
+                    Side::B => b.cloned().unwrap_or_default(),
+                    Side::Mismatch => LogicValue::from(mismatch as u32),
+                };
+                value.set_mismatch(mismatch);
+                *target = value;
+            }
+        }
+
+        Ok(data)
+    }
+}
+
+impl Source<String, usize, LogicValue> for DiffSource {}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::load::vcd::VcdLoader;
+    use tempdir::TempDir;
+
+    const HEADER: &str = "$timescale 1ns $end\n\
+        $scope module tb $end\n\
+        $var wire 4 ! count $end\n\
+        $var wire 1 \" clk $end\n\
+        $upscope $end\n\
+        $enddefinitions $end\n";
+
+    fn load(dir: &TempDir, name: &str, body: &str) -> PipelineCId {
+        let path = dir.path().join(name);
+        std::fs::write(&path, format!("{}{}", HEADER, body)).unwrap();
+
+        let loader = VcdLoader::new(path, Some(SimTime::from_ns(10))).unwrap();
+        let mut pipe = PipelineCId::new(Box::new(loader)).unwrap();
+        pipe.query_init().unwrap();
+        pipe
+    }
+
+    #[test]
+    fn test_diff() {
+        let dir = TempDir::new("viow-test").unwrap();
+        let a = load(&dir, "a.vcd", "#0\nb0 !\n0\"\n#10\nb1 !\n#20\nb10 !\n#30\nb11 !\n#40\n");
+        let b = load(&dir, "b.vcd", "#0\nb0 !\n0\"\n#10\nb1 !\n#20\nb11 !\n#50\n");
+
+        let mut diff = DiffSource::new(a, b).unwrap();
+        assert_eq!(4, diff.query_cycle_count());
+
+        let names: Vec<String> = diff.query_signals().unwrap()
+            .into_iter()
+            .map(|signal| signal.name)
+            .collect();
+        assert_eq!(vec!["a:tb.count", "b:tb.count", "diff:tb.count", "a:tb.clk", "b:tb.clk", "diff:tb.clk"], names);
+
+        let ids = vec!["diff:tb.count".to_string(), "b:tb.count".to_string(), "a:tb.clk".to_string()];
+        let times = SimTimeRange(SimTime::from_ns(10), SimTime::from_ns(40));
+        let data = diff.sample(&ids, &times).unwrap();
+
+        assert_eq!((3, 3), data.dim());
+        assert_eq!(LogicValue::from(0), data[[0, 0]]);
+        assert!(!data[[0, 1]].has_mismatch());
+        assert_eq!(LogicValue::from(1), data[[1, 0]]);
+        assert_eq!(LogicValue::from(3), data[[1, 1]]);
+        assert!(data[[1, 1]].has_mismatch());
+        assert_eq!(LogicValue::from(0), data[[2, 0]]);
+        assert!(!data[[2, 2]].has_mismatch());
+    }
+}
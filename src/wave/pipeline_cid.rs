@@ -1,7 +1,10 @@
 use crate::data::*;
 use crate::error::*;
+use crate::formatting::EnumTables;
 use crate::pipeline::*;
 
+use std::path::PathBuf;
+
 /// Pipeline adapter providing contiguous signal ids.
 pub(super) struct PipelineCId {
     /// Underlying Pipeline that is adapted.
@@ -35,6 +38,10 @@ impl PipelineCId {
 
         (rv, tail)
     }
+
+    pub(super) fn into_source(self) -> SrcBox {
+        self.pipe.into_source()
+    }
 }
 
 impl QuerySource for PipelineCId {
@@ -77,11 +84,27 @@ impl QuerySource for PipelineCId {
     fn query_cycle_count(&self) -> usize {
         self.pipe.query_cycle_count()
     }
+
+    fn query_refresh(&mut self) -> Result<bool> {
+        self.pipe.query_refresh()
+    }
+
+    fn query_reload(&mut self) -> Result<()> {
+        self.pipe.query_reload()
+    }
+
+    fn query_files(&self) -> Vec<PathBuf> {
+        self.pipe.query_files()
+    }
+
+    fn query_enums(&self) -> EnumTables {
+        self.pipe.query_enums()
+    }
 }
 
 impl Sample for PipelineCId {
     type Id = usize;
-    type Value = rug::Integer;
+    type Value = LogicValue;
 
     fn sample(
         &mut self,
@@ -0,0 +1,139 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Size and modification time of a file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Stamp {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl Stamp {
+    /// None, if the file does not exist (anymore).
+    fn of(path: &Path) -> Option<Self> {
+        let meta = fs::metadata(path).ok()?;
+
+        Some(Self {
+            len: meta.len(),
+            modified: meta.modified().ok(),
+        })
+    }
+}
+
+struct WatchedFile {
+    path: PathBuf,
+    /// Stamp of the loaded file
+    loaded: Option<Stamp>,
+    /// Stamp seen at the previous check
+    seen: Option<Stamp>,
+}
+
+/// Notices changes of files on disk by polling their stamps
+///
+/// A change is only reported, once the file kept the same stamp for two checks in a row. This
+/// avoids reloading a file that a simulator is still writing.
+pub(super) struct Watch {
+    files: Vec<WatchedFile>,
+}
+
+impl Watch {
+    pub(super) fn new(paths: Vec<PathBuf>) -> Self {
+        let files = paths.into_iter()
+            .map(|path| {
+                let stamp = Stamp::of(&path);
+
+                WatchedFile {
+                    path,
+                    loaded: stamp,
+                    seen: stamp,
+                }
+            })
+            .collect();
+
+        Self { files }
+    }
+
+    /// True, if any file changed since it was loaded and is stable now.
+    ///
+    /// With `ignore_growth`, files that only grew are taken as loaded, because their new data
+    /// is picked up by following the source. A change is reported again at the next check, until
+    /// `reloaded` is called.
+    pub(super) fn changed(&mut self, ignore_growth: bool) -> bool {
+        let mut changed = false;
+
+        for file in self.files.iter_mut() {
+            let stamp = Stamp::of(&file.path);
+            let stable = stamp == file.seen;
+            file.seen = stamp;
+
+            let stamp = match stamp {
+                Some(stamp) if Some(stamp) != file.loaded => stamp,
+                _ => continue,
+            };
+
+            let grew = file.loaded
+                .map(|loaded| stamp.len >= loaded.len)
+                .unwrap_or(false);
+
+            if ignore_growth && grew {
+                file.loaded = Some(stamp);
+            } else if stable {
+                changed = true;
+            }
+        }
+
+        changed
+    }
+
+    /// Take the files as loaded, as they were at the last check
+    pub(super) fn reloaded(&mut self) {
+        for file in self.files.iter_mut() {
+            file.loaded = file.seen;
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Write;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_changed() {
+        let dir = TempDir::new("viow-test").unwrap();
+        let path = dir.path().join("wave.vcd");
+        fs::write(&path, "12345678").unwrap();
+
+        let mut watch = Watch::new(vec![path.clone(), dir.path().join("missing.vcd")]);
+        assert!(!watch.changed(false));
+
+        // replaced by a shorter file, reported once it is stable and until it is reloaded
+        fs::write(&path, "1234").unwrap();
+        assert!(!watch.changed(false));
+        assert!(watch.changed(false));
+        assert!(watch.changed(false));
+        watch.reloaded();
+        assert!(!watch.changed(false));
+
+        // growth is ignored while following
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"5678").unwrap();
+        assert!(!watch.changed(true));
+        assert!(!watch.changed(true));
+        assert!(!watch.changed(false));
+
+        // a deleted file is not reported, until it is written again
+        fs::remove_file(&path).unwrap();
+        assert!(!watch.changed(false));
+        assert!(!watch.changed(false));
+        fs::write(&path, "12").unwrap();
+        assert!(!watch.changed(false));
+        assert!(watch.changed(false));
+        watch.reloaded();
+        assert!(!watch.changed(false));
+    }
+}
@@ -40,6 +40,26 @@ fn open_examples() {
 }
 
 
+/// Options, that only apply to VCD files, are rejected for other files.
+fn reject_vcd_options() {
+    let rejected = [
+        ["--clock", "tb.clk"],
+        ["--edge", "falling"],
+        ["--sample-at", "start"],
+    ];
+
+    for file in ["examples/counter.ghw", "examples/counter.fst"] {
+        for option in rejected.iter() {
+            let args = ["viow", "--cycle-step", "10", "--timeunits", "ns", option[0], option[1], file];
+            let opts = Opts::parse_from(&args);
+            let config = Rc::new(config::Config::test_config());
+
+            assert!(setup(opts, config).is_err(), "{} accepted for {}", option[0], file);
+        }
+    }
+}
+
+
 /// Test a single render step.
 fn render() {
     const EXAMPLE_FILES: [&'static str; 2] = [
@@ -85,5 +105,6 @@ fn render() {
 #[test]
 fn cmd_test() {
     open_examples();
+    reject_vcd_options();
     render();
 }
@@ -1,9 +1,12 @@
 use viow::{
-    data::{SimTime, SimTimeUnit},
+    data::{LogicValue, QuerySource, Sample, SimTime, SimTimeRange, SimTimeUnit},
     wave::Wave,
-    load::vcd::VcdLoader
+    load::vcd::VcdLoader,
+    load::ghw::GhwLoader,
+    load::fst::FstLoader,
+    formatting::WaveFormat,
 };
-use rug::Integer;
+use std::fs::File;
 use std::path::PathBuf;
 
 
@@ -18,12 +21,12 @@ fn load_vcd_test() {
     assert_eq!(100, wave.num_cycles());
     assert_eq!(50, wave.num_signals());
     assert_eq!(Some("tb_core.mem.act_rd_q"), wave.name(11));
-    assert_eq!(Some(Integer::from(1)), wave.value(11, 13));
-    assert_eq!(Some(Integer::from(2)), wave.value(36, 37));
+    assert_eq!(Some(LogicValue::from(1)), wave.value(11, 13));
+    assert_eq!(Some(LogicValue::from(2)), wave.value(36, 37));
 
     {
-        let one = Integer::from(1);
-        let zero = Integer::from(0);
+        let one = LogicValue::from(1);
+        let zero = LogicValue::from(0);
         let wave_slice = wave.cached_slice(0..1, 0..wave.num_cycles()).unwrap();
         let clk_vals = wave_slice.signal_iter(0).unwrap();
 
@@ -49,12 +52,12 @@ fn load_vcd_cycletime_test() {
     assert_eq!(200, wave.num_cycles());
     assert_eq!(50, wave.num_signals());
     assert_eq!(Some("tb_core.mem.act_rd_q"), wave.name(11));
-    assert_eq!(Some(Integer::from(1u32)), wave.value(11, 13*2));
-    assert_eq!(Some(Integer::from(2u32)), wave.value(36, 37*2));
+    assert_eq!(Some(LogicValue::from(1u32)), wave.value(11, 13*2));
+    assert_eq!(Some(LogicValue::from(2u32)), wave.value(36, 37*2));
 
     {
-        let one = Integer::from(1);
-        let zero = Integer::from(0);
+        let one = LogicValue::from(1);
+        let zero = LogicValue::from(0);
         let wave_slice = wave.cached_slice(0..1, 0..wave.num_cycles()).unwrap();
         let clk_vals = wave_slice.signal_iter(0).unwrap();
 
@@ -67,3 +70,104 @@ fn load_vcd_cycletime_test() {
         }
     }
 }
+
+
+#[test]
+fn load_ghw_test() {
+    const FILE_NAME: &'static str = "examples/counter.ghw";
+    const CYCLE_TIME: SimTime = SimTime::new(10, SimTimeUnit::Ns);
+
+    let loader = Box::new(GhwLoader::new(PathBuf::from(FILE_NAME), CYCLE_TIME).unwrap());
+    let signals = loader.query_signals().unwrap();
+    assert!(matches!(signals[2].format, WaveFormat::Enum(2, _)));
+    assert!(signals[6].format == WaveFormat::Real(-1.0, 4.0));
+    let mut wave = Wave::load(loader).unwrap();
+
+    assert_eq!(10, wave.num_cycles());
+    assert_eq!(7, wave.num_signals());
+    assert_eq!(Some("tb.cnt"), wave.name(1));
+    assert_eq!(Some("tb.rec.b"), wave.name(5));
+    assert_eq!(Some(LogicValue::from(1)), wave.value(0, 3));
+    assert_eq!(Some(LogicValue::from(4)), wave.value(1, 3));
+    assert_eq!(Some(LogicValue::from(2)), wave.value(2, 8));
+    assert_eq!(Some("done".to_string()), wave.formatted_value(2, 8));
+    assert_eq!(Some(LogicValue::from(350)), wave.value(3, 3));
+    assert_eq!(Some(LogicValue::from(-4i32 as u32)), wave.value(5, 3));
+    assert_eq!(Some(1.0), wave.value(6, 3).map(|value| value.to_real()));
+}
+
+
+#[test]
+fn load_truncated_ghw_test() {
+    use tempdir::TempDir;
+
+    const FILE_NAME: &'static str = "examples/counter.ghw";
+    const CYCLE_TIME: SimTime = SimTime::new(10, SimTimeUnit::Ns);
+
+    // file cut off in the middle of a cycle, e.g. while the simulation is running
+    let content = std::fs::read(FILE_NAME).unwrap();
+    let tmpd = TempDir::new("truncated").unwrap();
+    let cut_name = tmpd.path().join("counter.ghw");
+    std::fs::write(&cut_name, &content[..580]).unwrap();
+
+    let mut loader = GhwLoader::new(cut_name, CYCLE_TIME).unwrap();
+    assert_eq!(6, loader.query_cycle_count());
+
+    let ids = vec!["tb.cnt".to_string()];
+    let complete = SimTimeRange(SimTime::zero(), SimTime::from_ns(60));
+    assert!(loader.sample(&ids, &complete).is_ok());
+    let broken = SimTimeRange(SimTime::zero(), SimTime::from_ns(70));
+    assert!(loader.sample(&ids, &broken).is_err());
+}
+
+
+#[test]
+fn load_fst_test() {
+    const FILE_NAME: &'static str = "examples/counter.fst";
+    const CYCLE_TIME: SimTime = SimTime::new(10, SimTimeUnit::Ns);
+
+    let loader = Box::new(FstLoader::new(PathBuf::from(FILE_NAME), Some(CYCLE_TIME)).unwrap());
+    let signals = loader.query_signals().unwrap();
+    assert!(signals[2].format == WaveFormat::Real(-1.0, 4.0));
+    let mut wave = Wave::load(loader).unwrap();
+
+    assert_eq!(10, wave.num_cycles());
+    assert_eq!(3, wave.num_signals());
+    assert_eq!(Some("tb.clk"), wave.name(0));
+    assert_eq!(Some("tb.cnt"), wave.name(1));
+    assert_eq!(Some("tb.level"), wave.name(2));
+    assert_eq!(Some(LogicValue::from(3)), wave.value(1, 3));
+    assert_eq!(Some(LogicValue::from(9)), wave.value(1, 9));
+    assert_eq!(Some(0.5), wave.value(2, 3).map(|value| value.to_real()));
+    assert_eq!(Some(-1.0), wave.value(2, 0).map(|value| value.to_real()));
+}
+
+
+#[test]
+fn load_compressed_vcd_test() {
+    use flate2::{write::GzEncoder, Compression};
+    use tempdir::TempDir;
+
+    const FILE_NAME: &'static str = "examples/core.vcd";
+    const CYCLE_TIME: SimTime = SimTime::new(100, SimTimeUnit::Ps);
+
+    // file name without suffix, to make sure detection does not depend on it
+    let tmpd = TempDir::new("compressed").unwrap();
+    let gz_name = tmpd.path().join("core");
+    let mut encoder = GzEncoder::new(File::create(&gz_name).unwrap(), Compression::default());
+    std::io::copy(&mut File::open(FILE_NAME).unwrap(), &mut encoder).unwrap();
+    encoder.finish().unwrap();
+
+    let loader = Box::new(VcdLoader::new(PathBuf::from(FILE_NAME), Some(CYCLE_TIME)).unwrap());
+    let mut wave = Wave::load(loader).unwrap();
+    let gz_loader = Box::new(VcdLoader::new(gz_name, Some(CYCLE_TIME)).unwrap());
+    let mut gz_wave = Wave::load(gz_loader).unwrap();
+
+    assert_eq!(wave.num_cycles(), gz_wave.num_cycles());
+    assert_eq!(wave.num_signals(), gz_wave.num_signals());
+    for signal in 0..wave.num_signals() {
+        for cycle in 0..wave.num_cycles() {
+            assert_eq!(wave.value(signal, cycle), gz_wave.value(signal, cycle));
+        }
+    }
+}